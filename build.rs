@@ -0,0 +1,130 @@
+//! Compiles the processed mapping tables into the library
+//!
+//! With the `embedded-data` feature on, every JSON table under `data/processed`
+//! is turned into a static `&[(&str, &str)]` slice sorted by key (one entry
+//! per candidate where a key lists several) in `OUT_DIR`, so the
+//! library needs no data files at run time and does no JSON parsing.
+
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+/// Generated static name and source file of each embedded table
+const TABLES: &[(&str, &str)] = &[
+    (
+        "TRADITIONAL_TO_SIMPLIFIED",
+        "data/processed/script_conversion/traditional_to_simplified.json",
+    ),
+    (
+        "SIMPLIFIED_TO_TRADITIONAL",
+        "data/processed/script_conversion/simplified_to_traditional.json",
+    ),
+    (
+        "TRADITIONAL_TO_SIMPLIFIED_PHRASES",
+        "data/processed/script_conversion/traditional_to_simplified_phrases.json",
+    ),
+    (
+        "SIMPLIFIED_TO_TRADITIONAL_PHRASES",
+        "data/processed/script_conversion/simplified_to_traditional_phrases.json",
+    ),
+    (
+        "SEMANTIC_VARIANTS",
+        "data/processed/normalization/semantic_variants.json",
+    ),
+    (
+        "COMPATIBILITY_VARIANTS",
+        "data/processed/normalization/compatibility_variants.json",
+    ),
+    (
+        "KANGXI_RADICALS",
+        "data/processed/normalization/kangxi_radicals.json",
+    ),
+    (
+        "HANGUL_READINGS",
+        "data/processed/normalization/hangul_readings.json",
+    ),
+    (
+        "SPOOFING_VARIANTS",
+        "data/processed/normalization/spoofing_variants.json",
+    ),
+];
+
+/// Targets of an entry: a single string, or every candidate in order of preference
+fn targets(path: &str, source: &str, value: serde_json::Value) -> Vec<String> {
+    match value {
+        serde_json::Value::String(target) => vec![target],
+        serde_json::Value::Array(targets) => targets
+            .into_iter()
+            .map(|target| match target {
+                serde_json::Value::String(target) => target,
+                other => panic!("cannot embed {}: candidate {} of {:?}", path, other, source),
+            })
+            .collect(),
+        other => panic!("cannot embed {}: value {} of {:?}", path, other, source),
+    }
+}
+
+/// Source file of the embedded readings
+const READINGS: &str = "data/processed/script_conversion/readings.json";
+
+/// Static `(character, pinyin, frequency)` slice of the readings file, sorted by character
+fn readings(path: &str) -> String {
+    println!("cargo:rerun-if-changed={}", path);
+    let contents =
+        fs::read_to_string(path).unwrap_or_else(|error| panic!("cannot embed {}: {}", path, error));
+    let table: BTreeMap<String, serde_json::Value> = serde_json::from_str(&contents)
+        .unwrap_or_else(|error| panic!("cannot embed {}: {}", path, error));
+
+    let mut generated = String::new();
+    writeln!(generated, "/// Entries of `{}`, sorted by character", path).unwrap();
+    writeln!(generated, "pub static READINGS: &[(&str, &str, u32)] = &[").unwrap();
+    for (ch, entry) in table {
+        let pinyin = entry["pinyin"].as_str().unwrap_or_default();
+        let frequency = entry["frequency"].as_u64().unwrap_or(0);
+        writeln!(generated, "    ({:?}, {:?}, {}),", ch, pinyin, frequency).unwrap();
+    }
+    writeln!(generated, "];").unwrap();
+    generated
+}
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    if env::var_os("CARGO_FEATURE_EMBEDDED_DATA").is_none() {
+        return;
+    }
+
+    let mut generated = String::new();
+    for (name, path) in TABLES {
+        println!("cargo:rerun-if-changed={}", path);
+
+        let contents = fs::read_to_string(path).unwrap_or_else(|error| {
+            panic!(
+                "cannot embed {}: {} (run process-unihan or disable the `embedded-data` feature)",
+                path, error
+            )
+        });
+        let table: BTreeMap<String, serde_json::Value> = serde_json::from_str(&contents)
+            .unwrap_or_else(|error| panic!("cannot embed {}: {}", path, error));
+        // Candidates of one key stay in their order of preference
+        let entries = table.into_iter().flat_map(|(source, value)| {
+            targets(path, &source, value)
+                .into_iter()
+                .map(move |target| (source.clone(), target))
+        });
+
+        writeln!(generated, "/// Entries of `{}`, sorted by key", path).unwrap();
+        writeln!(generated, "pub static {}: &[(&str, &str)] = &[", name).unwrap();
+        for (source, target) in entries {
+            writeln!(generated, "    ({:?}, {:?}),", source, target).unwrap();
+        }
+        writeln!(generated, "];").unwrap();
+    }
+
+    generated.push_str(&readings(READINGS));
+
+    let out_dir = env::var("OUT_DIR").expect("OUT_DIR is set by cargo");
+    fs::write(Path::new(&out_dir).join("embedded_tables.rs"), generated)
+        .expect("cannot write embedded tables");
+}
@@ -3,9 +3,7 @@
 //! This example demonstrates advanced features including individual normalizer
 //! components, statistics, and batch processing.
 
-use zho_text_normalizer::normalizers::{
-    CompatibilityNormalizer, KangxiNormalizer, VariantNormalizer,
-};
+use zho_text_normalizer::normalizers::{KangxiNormalizer, VariantNormalizer};
 use zho_text_normalizer::{Script, TextNormalizer};
 
 fn main() {
@@ -17,7 +15,6 @@ fn main() {
 
     let variant_normalizer = VariantNormalizer::new();
     let kangxi_normalizer = KangxiNormalizer::new();
-    let compatibility_normalizer = CompatibilityNormalizer::new();
 
     // Get statistics about loaded mappings
     let stats = variant_normalizer.get_statistics();
@@ -77,19 +74,28 @@ fn main() {
     println!("------------------");
 
     let normalizer = TextNormalizer::new();
-    let test_texts = vec!["傳統中文", "简体中文", "⽅⾔文字", "呌喫飯", "國際標準"];
+    let test_texts = ["傳統中文", "简体中文", "⽅⾔文字", "呌喫飯", "國際標準"];
 
-    for (i, text) in test_texts.iter().enumerate() {
-        let result = normalizer.normalize(text, Some(Script::SimplifiedChinese));
+    // Texts are normalized in parallel; results come back in input order
+    let (results, statistics) =
+        normalizer.normalize_batch_with_statistics(&test_texts, Some(Script::SimplifiedChinese));
+    for (i, result) in results.iter().enumerate() {
         println!(
             "  {}. {} → {} ({}ms, {} changes)",
             i + 1,
-            text,
+            result.original,
             result.normalized,
             result.processing_time_ms,
             result.changes.len()
         );
     }
+    println!(
+        "  Batch: {} texts, {} changed, {} changes",
+        statistics.texts, statistics.changed_texts, statistics.total_changes
+    );
+    for (change_type, count) in &statistics.changes_by_type {
+        println!("    • {:?}: {}", change_type, count);
+    }
 
     println!();
 
@@ -112,7 +118,7 @@ fn main() {
         for change in &validation_result.changes {
             println!(
                 "  • {} → {} ({})",
-                change.original_char, change.normalized_char, change.reason
+                change.original_text, change.normalized_text, change.reason
             );
         }
     }
@@ -22,10 +22,8 @@ fn main() {
     // Show detailed changes
     for change in &result.changes {
         println!(
-            "  {} → {} ({})",
-            change.original_char,
-            change.normalized_char,
-            format!("{:?}", change.change_type)
+            "  {} → {} ({:?})",
+            change.original_text, change.normalized_text, change.change_type
         );
     }
 
@@ -72,12 +70,12 @@ fn main() {
         println!("All changes:");
         for (i, change) in result.changes.iter().enumerate() {
             println!(
-                "  {}. {} → {} at position {} ({})",
+                "  {}. {} → {} at position {} ({:?})",
                 i + 1,
-                change.original_char,
-                change.normalized_char,
+                change.original_text,
+                change.normalized_text,
                 change.position,
-                format!("{:?}", change.change_type)
+                change.change_type
             );
         }
     }
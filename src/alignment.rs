@@ -0,0 +1,408 @@
+//! Alignment between original and normalized text
+//!
+//! An [`OffsetMap`] splits both texts into aligned segments: each segment is a
+//! char range of the original and the char range it became in the normalized
+//! text. Offsets can be projected in either direction in bytes, chars or UTF-16
+//! code units, so hits found on normalized text can be highlighted in the source.
+
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+/// Unit in which a text offset is expressed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OffsetUnit {
+    /// UTF-8 byte offset (Rust `str` indexing)
+    Byte,
+    /// Unicode scalar value offset (`str::chars`)
+    Char,
+    /// UTF-16 code unit offset (JavaScript, Java, Windows APIs)
+    Utf16,
+}
+
+/// A char range of the original text and the char range it was normalized to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AlignedSegment {
+    pub original: Range<usize>,
+    pub normalized: Range<usize>,
+}
+
+/// Byte and UTF-16 offsets of every char boundary in a text
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+struct BoundaryIndex {
+    bytes: Vec<usize>,
+    utf16: Vec<usize>,
+}
+
+impl BoundaryIndex {
+    fn new(text: &str) -> Self {
+        let mut bytes = Vec::with_capacity(text.len() + 1);
+        let mut utf16 = Vec::with_capacity(text.len() + 1);
+        let (mut byte, mut unit) = (0, 0);
+        bytes.push(0);
+        utf16.push(0);
+        for ch in text.chars() {
+            byte += ch.len_utf8();
+            unit += ch.len_utf16();
+            bytes.push(byte);
+            utf16.push(unit);
+        }
+        Self { bytes, utf16 }
+    }
+
+    fn char_len(&self) -> usize {
+        self.bytes.len().saturating_sub(1)
+    }
+
+    /// Char index of the char containing `offset` (or the end of the text)
+    fn to_char(&self, offset: usize, unit: OffsetUnit) -> Option<usize> {
+        let table = match unit {
+            OffsetUnit::Char => return (offset <= self.char_len()).then_some(offset),
+            OffsetUnit::Byte => &self.bytes,
+            OffsetUnit::Utf16 => &self.utf16,
+        };
+        if offset > *table.last()? {
+            return None;
+        }
+        Some(match table.binary_search(&offset) {
+            Ok(index) => index,
+            Err(index) => index - 1,
+        })
+    }
+
+    fn offset_of(&self, index: usize, unit: OffsetUnit) -> usize {
+        match unit {
+            OffsetUnit::Char => index,
+            OffsetUnit::Byte => self.bytes[index],
+            OffsetUnit::Utf16 => self.utf16[index],
+        }
+    }
+}
+
+/// Bidirectional offset map between an original text and its normalized form
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OffsetMap {
+    segments: Vec<AlignedSegment>,
+    original: BoundaryIndex,
+    normalized: BoundaryIndex,
+}
+
+impl OffsetMap {
+    /// Map of a text that was left unchanged
+    pub fn identity(text: &str) -> Self {
+        Self::one_to_one(text, text)
+    }
+
+    /// Map of a char-for-char rewrite, where the i-th char of `normalized`
+    /// replaced the i-th char of `original`
+    ///
+    /// Falls back to [`OffsetMap::coarse`] if the char counts differ.
+    pub fn one_to_one(original: &str, normalized: &str) -> Self {
+        let len = original.chars().count();
+        if len != normalized.chars().count() {
+            return Self::coarse(original, normalized);
+        }
+        Self::from_lengths(original, normalized, std::iter::repeat_n((1, 1), len))
+    }
+
+    /// Map that aligns the two texts as a single segment
+    pub fn coarse(original: &str, normalized: &str) -> Self {
+        let lengths = (original.chars().count(), normalized.chars().count());
+        Self::from_lengths(original, normalized, [lengths])
+    }
+
+    /// Build a map from consecutive `(original, normalized)` segment lengths in chars
+    ///
+    /// The lengths must add up to the char counts of the two texts; otherwise a
+    /// coarse single-segment map is returned.
+    pub fn from_lengths<I>(original: &str, normalized: &str, lengths: I) -> Self
+    where
+        I: IntoIterator<Item = (usize, usize)>,
+    {
+        let original_index = BoundaryIndex::new(original);
+        let normalized_index = BoundaryIndex::new(normalized);
+
+        let mut segments = Vec::new();
+        let (mut orig_pos, mut norm_pos) = (0, 0);
+        for (orig_len, norm_len) in lengths {
+            segments.push(AlignedSegment {
+                original: orig_pos..orig_pos + orig_len,
+                normalized: norm_pos..norm_pos + norm_len,
+            });
+            orig_pos += orig_len;
+            norm_pos += norm_len;
+        }
+
+        if orig_pos != original_index.char_len() || norm_pos != normalized_index.char_len() {
+            segments = vec![AlignedSegment {
+                original: 0..original_index.char_len(),
+                normalized: 0..normalized_index.char_len(),
+            }];
+        }
+
+        Self {
+            segments,
+            original: original_index,
+            normalized: normalized_index,
+        }
+    }
+
+    /// Whether this map aligns texts of the given char lengths
+    pub fn covers(&self, original_chars: usize, normalized_chars: usize) -> bool {
+        !self.segments.is_empty()
+            && self.original.char_len() == original_chars
+            && self.normalized.char_len() == normalized_chars
+    }
+
+    /// Whether this map aligns nothing, as the default map
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    /// Aligned segments in text order
+    pub fn segments(&self) -> &[AlignedSegment] {
+        &self.segments
+    }
+
+    /// Chain this map (A → B) with a map of a later step (B → C) into A → C
+    pub fn compose(&self, next: &OffsetMap) -> OffsetMap {
+        let first = merge_empty(&self.segments, |segment| &segment.normalized);
+        let second = merge_empty(&next.segments, |segment| &segment.original);
+
+        let mut segments = Vec::new();
+        let (mut i, mut j) = (0, 0);
+        while i < first.len() && j < second.len() {
+            let original_start = first[i].original.start;
+            let normalized_start = second[j].normalized.start;
+            loop {
+                let first_end = first[i].normalized.end;
+                let second_end = second[j].original.end;
+                let last = i + 1 == first.len() && j + 1 == second.len();
+                if first_end == second_end || last {
+                    segments.push(AlignedSegment {
+                        original: original_start..first[i].original.end,
+                        normalized: normalized_start..second[j].normalized.end,
+                    });
+                    i += 1;
+                    j += 1;
+                    break;
+                }
+                if (first_end < second_end && i + 1 < first.len()) || j + 1 == second.len() {
+                    i += 1;
+                } else {
+                    j += 1;
+                }
+            }
+        }
+
+        OffsetMap {
+            segments,
+            original: self.original.clone(),
+            normalized: next.normalized.clone(),
+        }
+    }
+
+    /// Project an offset in the normalized text onto the span of the original it came from
+    pub fn to_original(&self, offset: usize, unit: OffsetUnit) -> Option<Range<usize>> {
+        self.project_offset(offset, unit, Side::Normalized)
+    }
+
+    /// Project an offset in the original text onto the span of the normalized text it became
+    pub fn to_normalized(&self, offset: usize, unit: OffsetUnit) -> Option<Range<usize>> {
+        self.project_offset(offset, unit, Side::Original)
+    }
+
+    /// Project a span of the normalized text onto the smallest covering span of the original
+    pub fn span_to_original(&self, span: Range<usize>, unit: OffsetUnit) -> Option<Range<usize>> {
+        self.project_span(span, unit, Side::Normalized)
+    }
+
+    /// Project a span of the original text onto the smallest covering span of the normalized text
+    pub fn span_to_normalized(&self, span: Range<usize>, unit: OffsetUnit) -> Option<Range<usize>> {
+        self.project_span(span, unit, Side::Original)
+    }
+
+    fn project_offset(&self, offset: usize, unit: OffsetUnit, from: Side) -> Option<Range<usize>> {
+        let index = self.index(from).to_char(offset, unit)?;
+        let target = match self.segment_at(index, from) {
+            Some(segment) => from.other().range(segment).clone(),
+            None => self.end_of(from.other())..self.end_of(from.other()),
+        };
+        Some(self.to_unit(target, unit, from.other()))
+    }
+
+    fn project_span(
+        &self,
+        span: Range<usize>,
+        unit: OffsetUnit,
+        from: Side,
+    ) -> Option<Range<usize>> {
+        if span.start > span.end {
+            return None;
+        }
+        let start = self.index(from).to_char(span.start, unit)?;
+        let end = self.index(from).to_char(span.end, unit)?;
+        let target_start = match self.segment_at(start, from) {
+            Some(segment) => from.other().range(segment).start,
+            None => self.end_of(from.other()),
+        };
+        let target_end = match end.checked_sub(1) {
+            Some(last) if end > start => from.other().range(self.segment_at(last, from)?).end,
+            _ => target_start,
+        };
+        Some(self.to_unit(target_start..target_end, unit, from.other()))
+    }
+
+    /// Segment containing the char at `index`; `None` at the end of the text
+    fn segment_at(&self, index: usize, side: Side) -> Option<&AlignedSegment> {
+        let position = self
+            .segments
+            .partition_point(|segment| side.range(segment).end <= index);
+        self.segments.get(position)
+    }
+
+    fn end_of(&self, side: Side) -> usize {
+        self.index(side).char_len()
+    }
+
+    fn to_unit(&self, range: Range<usize>, unit: OffsetUnit, side: Side) -> Range<usize> {
+        let index = self.index(side);
+        index.offset_of(range.start, unit)..index.offset_of(range.end, unit)
+    }
+
+    fn index(&self, side: Side) -> &BoundaryIndex {
+        match side {
+            Side::Original => &self.original,
+            Side::Normalized => &self.normalized,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Side {
+    Original,
+    Normalized,
+}
+
+impl Side {
+    fn other(self) -> Side {
+        match self {
+            Side::Original => Side::Normalized,
+            Side::Normalized => Side::Original,
+        }
+    }
+
+    fn range(self, segment: &AlignedSegment) -> &Range<usize> {
+        match self {
+            Side::Original => &segment.original,
+            Side::Normalized => &segment.normalized,
+        }
+    }
+}
+
+/// Fold segments that are empty on the shared side into their neighbour so
+/// that every remaining segment has a distinct end on that side
+fn merge_empty<F>(segments: &[AlignedSegment], shared: F) -> Vec<AlignedSegment>
+where
+    F: Fn(&AlignedSegment) -> &Range<usize>,
+{
+    let mut merged: Vec<AlignedSegment> = Vec::with_capacity(segments.len());
+    let mut pending: Option<AlignedSegment> = None;
+
+    for segment in segments {
+        let mut segment = segment.clone();
+        if let Some(empty) = pending.take() {
+            segment.original.start = empty.original.start;
+            segment.normalized.start = empty.normalized.start;
+        }
+        if shared(&segment).is_empty() {
+            pending = Some(segment);
+        } else {
+            merged.push(segment);
+        }
+    }
+
+    if let Some(empty) = pending {
+        match merged.last_mut() {
+            Some(last) => {
+                last.original.end = empty.original.end;
+                last.normalized.end = empty.normalized.end;
+            }
+            None => merged.push(empty),
+        }
+    }
+
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_one_to_one_offsets() {
+        // 'a' is 1 byte, '⽅' is 3 bytes, '𠀀' is 4 bytes / 2 UTF-16 units
+        let map = OffsetMap::one_to_one("a⽅𠀀", "a方𠀀");
+
+        assert_eq!(map.to_original(1, OffsetUnit::Char), Some(1..2));
+        assert_eq!(map.to_original(1, OffsetUnit::Byte), Some(1..4));
+        assert_eq!(map.to_original(4, OffsetUnit::Byte), Some(4..8));
+        assert_eq!(map.to_original(2, OffsetUnit::Utf16), Some(2..4));
+        assert_eq!(map.to_normalized(8, OffsetUnit::Byte), Some(8..8));
+        assert_eq!(map.to_normalized(9, OffsetUnit::Byte), None);
+    }
+
+    #[test]
+    fn test_length_changing_segments() {
+        // "e\u{0301}x" composed to "éx"
+        let map = OffsetMap::from_lengths("e\u{0301}x", "éx", [(2, 1), (1, 1)]);
+
+        assert_eq!(map.to_original(0, OffsetUnit::Char), Some(0..2));
+        assert_eq!(map.to_original(1, OffsetUnit::Char), Some(2..3));
+        assert_eq!(map.to_normalized(1, OffsetUnit::Char), Some(0..1));
+        assert_eq!(map.span_to_original(0..2, OffsetUnit::Byte), Some(0..3));
+        assert_eq!(map.span_to_normalized(0..4, OffsetUnit::Byte), Some(0..3));
+    }
+
+    #[test]
+    fn test_compose() {
+        // "e\u{0301}⽅" → "é⽅" → "é方"
+        let first = OffsetMap::from_lengths("e\u{0301}⽅", "é⽅", [(2, 1), (1, 1)]);
+        let second = OffsetMap::one_to_one("é⽅", "é方");
+        let map = first.compose(&second);
+
+        assert_eq!(
+            map.segments(),
+            &[
+                AlignedSegment {
+                    original: 0..2,
+                    normalized: 0..1
+                },
+                AlignedSegment {
+                    original: 2..3,
+                    normalized: 1..2
+                },
+            ]
+        );
+        assert_eq!(map.to_original(1, OffsetUnit::Char), Some(2..3));
+    }
+
+    #[test]
+    fn test_compose_with_expansion_and_deletion() {
+        // "ab" → "xyzb" (a expanded) → "xyz" (b deleted)
+        let first = OffsetMap::from_lengths("ab", "xyzb", [(1, 3), (1, 1)]);
+        let second = OffsetMap::from_lengths("xyzb", "xyz", [(1, 1), (1, 1), (1, 1), (1, 0)]);
+        let map = first.compose(&second);
+
+        assert!(map.covers(2, 3));
+        assert_eq!(map.to_original(2, OffsetUnit::Char), Some(0..1));
+        assert_eq!(map.to_normalized(0, OffsetUnit::Char), Some(0..3));
+    }
+
+    #[test]
+    fn test_mismatched_lengths_fall_back_to_coarse() {
+        let map = OffsetMap::from_lengths("abc", "ab", [(1, 1)]);
+
+        assert_eq!(map.segments().len(), 1);
+        assert_eq!(map.to_original(1, OffsetUnit::Char), Some(0..3));
+    }
+}
@@ -0,0 +1,358 @@
+//! Composition analysis of the mapping tables
+//!
+//! Stages run in sequence, so one stage can produce a character that a later
+//! stage rewrites again, and a second run over the output can change it once
+//! more. [`TableAnalysis`] probes every character the tables of a normalizer
+//! mention and reports cycles between stages, characters rewritten by more
+//! than one stage in a single pass, and characters whose normalization is not
+//! idempotent. A [`FixedPointTable`] collapses the tables into one mapping
+//! whose single pass is idempotent (see
+//! [`NormalizationConfig::with_collapsed_tables`](crate::types::NormalizationConfig::with_collapsed_tables)).
+
+use crate::alignment::OffsetMap;
+use crate::normalizers::text_normalizer::TextNormalizer;
+use crate::types::{ChangeType, Script, TextChange};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+/// A character rewritten by more than one stage in a single pass
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Chain {
+    /// The character and every intermediate result, in stage order
+    pub steps: Vec<String>,
+    /// Change type of each rewrite
+    pub change_types: Vec<ChangeType>,
+}
+
+/// A character that a second pass changes again
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Unstable {
+    pub source: char,
+    /// Result of one pass
+    pub once: String,
+    /// Result of normalizing `once` again
+    pub twice: String,
+}
+
+/// Report on how the tables of a normalizer compose
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TableAnalysis {
+    /// Script conversion included in the analysis, if any
+    pub target_script: Option<Script>,
+    /// Number of characters probed
+    pub characters: usize,
+    /// Groups of characters that the stages map onto each other in a loop
+    pub cycles: Vec<Vec<char>>,
+    /// Characters rewritten more than once in one pass
+    pub chains: Vec<Chain>,
+    /// Characters where normalize(normalize(x)) != normalize(x)
+    pub unstable: Vec<Unstable>,
+}
+
+impl TableAnalysis {
+    /// Analyze the tables of `normalizer` as used when converting to `target_script`
+    ///
+    /// Script conversion is applied to every probed character as if the text
+    /// were in the other script, whatever the detector would say.
+    pub fn of(normalizer: &TextNormalizer, target_script: Option<Script>) -> Self {
+        let chars = normalizer.mapped_chars();
+        let conversion = TextNormalizer::forced_conversion(target_script.as_ref());
+        let target = conversion.as_ref();
+
+        let mut edges: BTreeMap<char, BTreeSet<char>> = BTreeMap::new();
+        let mut chains = Vec::new();
+        let mut unstable = Vec::new();
+        for &ch in &chars {
+            for output in normalizer.stage_outputs(ch, target) {
+                let mut output_chars = output.chars();
+                if let (Some(next), None) = (output_chars.next(), output_chars.next()) {
+                    if next != ch {
+                        edges.entry(ch).or_default().insert(next);
+                    }
+                }
+            }
+
+            let (once, changes) = normalizer.single_pass(&ch.to_string(), target);
+            if changes.len() > 1 {
+                chains.push(Chain {
+                    steps: std::iter::once(ch.to_string())
+                        .chain(changes.iter().map(|change| change.normalized_text.clone()))
+                        .collect(),
+                    change_types: changes
+                        .into_iter()
+                        .map(|change| change.change_type)
+                        .collect(),
+                });
+            }
+            let (twice, _) = normalizer.single_pass(&once, target);
+            if twice != once {
+                unstable.push(Unstable {
+                    source: ch,
+                    once,
+                    twice,
+                });
+            }
+        }
+
+        Self {
+            target_script,
+            characters: chars.len(),
+            cycles: find_cycles(&edges),
+            chains,
+            unstable,
+        }
+    }
+
+    /// Whether one pass of the normalizer is idempotent on every probed character
+    pub fn is_idempotent(&self) -> bool {
+        self.unstable.is_empty()
+    }
+
+    /// Whether no cycles, chains or unstable characters were found
+    pub fn is_clean(&self) -> bool {
+        self.cycles.is_empty() && self.chains.is_empty() && self.unstable.is_empty()
+    }
+}
+
+/// Characters that can reach each other through the edges, one group per cycle
+fn find_cycles(edges: &BTreeMap<char, BTreeSet<char>>) -> Vec<Vec<char>> {
+    let reachable = |start: char| -> HashSet<char> {
+        let mut seen = HashSet::new();
+        let mut pending = vec![start];
+        while let Some(ch) = pending.pop() {
+            for &next in edges.get(&ch).into_iter().flatten() {
+                if seen.insert(next) {
+                    pending.push(next);
+                }
+            }
+        }
+        seen
+    };
+
+    let mut in_cycle = HashSet::new();
+    let mut cycles = Vec::new();
+    for &ch in edges.keys() {
+        if in_cycle.contains(&ch) {
+            continue;
+        }
+        let from_ch = reachable(ch);
+        if !from_ch.contains(&ch) {
+            continue;
+        }
+        let mut cycle: Vec<char> = from_ch
+            .into_iter()
+            .filter(|&other| reachable(other).contains(&ch))
+            .collect();
+        cycle.sort_unstable();
+        in_cycle.extend(cycle.iter().copied());
+        cycles.push(cycle);
+    }
+    cycles
+}
+
+/// Result of repeating a pass on a character until it no longer changes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FixedPoint {
+    pub target: String,
+    /// Change type of the first rewrite
+    pub change_type: ChangeType,
+    /// Intermediate results, starting with the source character
+    pub path: Vec<String>,
+}
+
+/// All tables of a normalizer collapsed into one idempotent mapping
+///
+/// Every probed character maps to the text repeated passes settle on. When
+/// the passes loop, all characters of the loop map to its smallest member.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FixedPointTable {
+    entries: HashMap<char, FixedPoint>,
+}
+
+impl FixedPointTable {
+    /// Collapse the tables of `normalizer` as used when converting to `target_script`
+    ///
+    /// Script conversion is applied as if the text were in the other script.
+    pub fn of(normalizer: &TextNormalizer, target_script: Option<&Script>) -> Self {
+        let conversion = TextNormalizer::forced_conversion(target_script);
+        Self::converting(normalizer, conversion.as_ref())
+    }
+
+    /// Collapse the tables of `normalizer` followed by the `(target, source)` script conversion
+    pub(crate) fn converting(
+        normalizer: &TextNormalizer,
+        conversion: Option<&(Script, Script)>,
+    ) -> Self {
+        let mut entries = HashMap::new();
+        for ch in normalizer.mapped_chars() {
+            let source = ch.to_string();
+            let (first, changes) = normalizer.single_pass(&source, conversion);
+            let Some(first_change) = changes.first() else {
+                continue;
+            };
+
+            let mut path = vec![source.clone(), first];
+            loop {
+                let last = path.last().expect("path is never empty");
+                let (next, _) = normalizer.single_pass(last, conversion);
+                if &next == last {
+                    break;
+                }
+                if let Some(start) = path.iter().position(|step| *step == next) {
+                    // Settle loops on their smallest member
+                    let smallest = path[start..]
+                        .iter()
+                        .min()
+                        .expect("loop is not empty")
+                        .clone();
+                    path.push(next);
+                    path.push(smallest);
+                    break;
+                }
+                path.push(next);
+            }
+
+            let target = path.last().expect("path is never empty").clone();
+            if target != source {
+                entries.insert(
+                    ch,
+                    FixedPoint {
+                        target,
+                        change_type: first_change.change_type.clone(),
+                        path,
+                    },
+                );
+            }
+        }
+        Self { entries }
+    }
+
+    /// Fixed point of `ch`, if it is rewritten at all
+    pub fn get(&self, ch: char) -> Option<&FixedPoint> {
+        self.entries.get(&ch)
+    }
+
+    /// Number of rewritten characters
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether no character is rewritten
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Replace every character by its fixed point
+    pub fn apply(&self, text: &str) -> (String, Vec<TextChange>, OffsetMap) {
+        let mut result = String::with_capacity(text.len());
+        let mut changes = Vec::new();
+        let mut lengths = Vec::new();
+
+        for (pos, ch) in text.chars().enumerate() {
+            match self.entries.get(&ch) {
+                Some(entry) => {
+                    result.push_str(&entry.target);
+                    lengths.push((1, entry.target.chars().count()));
+                    changes.push(TextChange::replacement(
+                        pos,
+                        ch.to_string(),
+                        entry.target.clone(),
+                        entry.change_type.clone(),
+                        format!(
+                            "{} → {} (collapsed: {})",
+                            ch,
+                            entry.target,
+                            entry.path.join(" → ")
+                        ),
+                    ));
+                }
+                None => {
+                    result.push(ch);
+                    lengths.push((1, 1));
+                }
+            }
+        }
+
+        let offset_map = OffsetMap::from_lengths(text, &result, lengths);
+        (result, changes, offset_map)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{DataFile, DataSource, DataStore};
+    use crate::types::NormalizationConfig;
+
+    fn store() -> DataStore {
+        // ⽅ → A → B → C in one pass; C ↔ D loop; E → A needs a second pass
+        DataStore::new(DataSource::memory([
+            (
+                DataFile::KangxiRadicals,
+                r#"{"⽅": "A"}"#.as_bytes().to_vec(),
+            ),
+            (
+                DataFile::SemanticVariants,
+                br#"{"A": "B", "C": "D"}"#.to_vec(),
+            ),
+            (
+                DataFile::CompatibilityVariants,
+                br#"{"B": "C", "D": "C", "E": "A"}"#.to_vec(),
+            ),
+        ]))
+    }
+
+    fn normalizer() -> TextNormalizer {
+        TextNormalizer::with_data(NormalizationConfig::default(), &store())
+    }
+
+    #[test]
+    fn test_analysis() {
+        let analysis = TableAnalysis::of(&normalizer(), None);
+
+        assert_eq!(analysis.cycles, [vec!['C', 'D']]);
+        let chain = analysis
+            .chains
+            .iter()
+            .find(|chain| chain.steps[0] == "⽅")
+            .unwrap();
+        assert_eq!(chain.steps, ["⽅", "A", "B", "C"]);
+        assert_eq!(
+            analysis.unstable,
+            [Unstable {
+                source: 'E',
+                once: "A".to_string(),
+                twice: "C".to_string(),
+            }]
+        );
+        assert!(!analysis.is_idempotent());
+    }
+
+    #[test]
+    fn test_fixed_point_table_is_idempotent() {
+        let normalizer = normalizer();
+        let table = FixedPointTable::of(&normalizer, None);
+
+        let (once, changes, _) = table.apply("⽅EDX");
+        assert_eq!(once, "CCCX");
+        assert_eq!(changes[0].change_type, ChangeType::KangxiRadical);
+        let (twice, changes, _) = table.apply(&once);
+        assert_eq!(twice, once);
+        assert!(changes.is_empty());
+    }
+
+    #[test]
+    fn test_collapsed_tables_mode() {
+        let config = NormalizationConfig::default().with_collapsed_tables(true);
+        let normalizer = TextNormalizer::with_data(config, &store());
+
+        let once = normalizer.normalize("⽅E", None);
+        assert_eq!(once.normalized, "CC");
+        assert!(once.changes.iter().all(|change| change.stage == 1));
+        assert!(once.verify_round_trip().is_ok());
+        assert!(normalizer
+            .normalize(&once.normalized, None)
+            .changes
+            .is_empty());
+    }
+}
@@ -2,7 +2,9 @@
 
 use clap::Parser;
 use std::path::Path;
+use zho_text_normalizer::types::NormalizationConfig;
 use zho_text_normalizer::utils::UnihanDataProcessor;
+use zho_text_normalizer::{DataSource, DataStore, Script, TableAnalysis, TextNormalizer};
 
 #[derive(Parser)]
 #[command(
@@ -18,11 +20,24 @@ struct Args {
     /// Force reprocessing even if output files exist
     #[arg(short, long)]
     force: bool,
+
+    /// Analyze how the processed tables compose and write analysis.json to the output directory
+    #[arg(short, long)]
+    analyze: bool,
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let args = Args::parse();
 
+    // Analyze existing tables without reprocessing them
+    if args.analyze && !args.force {
+        let script_dir = format!("{}/script_conversion", args.output_dir);
+        let norm_dir = format!("{}/normalization", args.output_dir);
+        if Path::new(&script_dir).exists() && Path::new(&norm_dir).exists() {
+            return analyze(&args.output_dir);
+        }
+    }
+
     println!("Processing Unihan database files...");
 
     // Check if Unihan directory exists
@@ -54,5 +69,48 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("  📁 Script conversion: data/processed/script_conversion/");
     println!("  📁 Normalization: data/processed/normalization/");
 
+    if args.analyze {
+        analyze(&args.output_dir)?;
+    }
+
+    Ok(())
+}
+
+/// Report cycles, chains and non-idempotent characters of the tables in `output_dir`
+fn analyze(output_dir: &str) -> Result<(), Box<dyn std::error::Error>> {
+    println!("🔍 Analyzing table composition in {}...", output_dir);
+
+    let store = DataStore::new(DataSource::directory(output_dir));
+    let normalizer = TextNormalizer::try_with_data(NormalizationConfig::default(), &store)?;
+
+    let targets = [
+        None,
+        Some(Script::SimplifiedChinese),
+        Some(Script::TraditionalChinese),
+    ];
+    let analyses: Vec<TableAnalysis> = targets
+        .into_iter()
+        .map(|target| TableAnalysis::of(&normalizer, target))
+        .collect();
+
+    for analysis in &analyses {
+        let target = match &analysis.target_script {
+            Some(script) => format!("{:?}", script),
+            None => "no conversion".to_string(),
+        };
+        println!(
+            "  {}: {} characters, {} cycles, {} chains, {} not idempotent",
+            target,
+            analysis.characters,
+            analysis.cycles.len(),
+            analysis.chains.len(),
+            analysis.unstable.len()
+        );
+    }
+
+    let path = Path::new(output_dir).join("analysis.json");
+    std::fs::write(&path, serde_json::to_string_pretty(&analyses)?)?;
+    println!("  📄 Details: {}", path.display());
+
     Ok(())
 }
@@ -1,6 +1,12 @@
 use clap::Parser;
+use std::path::PathBuf;
+use zho_text_normalizer::data::{DataSource, DataStore};
+use zho_text_normalizer::dictionary::UserDictionary;
 use zho_text_normalizer::normalizers::text_normalizer::TextNormalizer;
-use zho_text_normalizer::types::{OutputFormat, Script};
+use zho_text_normalizer::protection::ProtectionRule;
+use zho_text_normalizer::types::{
+    HanjaTransliteration, LossyConversion, NormalizationConfig, OutputFormat, Preset, Script,
+};
 
 #[derive(Parser)]
 #[command(name = "zho-normalize")]
@@ -10,7 +16,7 @@ struct Args {
     #[arg(value_name = "TEXT")]
     text: String,
 
-    /// Target script for conversion (auto, simplified, traditional)
+    /// Target script for conversion (auto, simplified, traditional, taiwan, hongkong, shinjitai)
     #[arg(short, long, default_value = "auto")]
     target: String,
 
@@ -25,18 +31,98 @@ struct Args {
     /// Validation mode (no conversion, just analysis)
     #[arg(long)]
     validate: bool,
+
+    /// Load mapping tables from this directory instead of the built-in data
+    #[arg(long, value_name = "DIR")]
+    data_dir: Option<PathBuf>,
+
+    /// Convert scripts with the native engine over OpenCC's configs and dictionaries in this directory
+    #[arg(long, value_name = "DIR")]
+    opencc_dir: Option<PathBuf>,
+
+    /// Start from a named preset (search-index, display, security, archival)
+    #[arg(short, long, value_parser = parse_preset)]
+    preset: Option<Preset>,
+
+    /// Layer this user dictionary (JSON) over the built-in data; repeat to add more
+    #[arg(long, value_name = "FILE")]
+    dictionary: Vec<PathBuf>,
+
+    /// Leave these spans untouched (urls, emails, code), comma separated
+    #[arg(long, value_delimiter = ',', value_parser = parse_protection)]
+    protect: Vec<ProtectionRule>,
+
+    /// Fail if the mapping data cannot be fully loaded instead of degrading
+    #[arg(long)]
+    strict: bool,
+
+    /// Apply all mapping tables as one fixed-point mapping so one pass is idempotent
+    #[arg(long)]
+    collapse_tables: bool,
+
+    /// Use Taiwan vocabulary (软件 → 軟體) when converting to Taiwan Traditional
+    #[arg(long)]
+    taiwan_vocabulary: bool,
+
+    /// Transliterate hanja to Hangul (hangul, or hangul-hanja to keep the hanja in parentheses)
+    #[arg(long, value_name = "MODE", value_parser = parse_hanja)]
+    hanja: Option<HanjaTransliteration>,
+
+    /// Report the characters that converting to the target and back would not restore
+    #[arg(long)]
+    round_trip: bool,
+
+    /// Keep characters that would not survive a round trip unconverted (keep) or annotate them (annotate)
+    #[arg(long, value_name = "MODE", value_parser = parse_lossless)]
+    lossless: Option<LossyConversion>,
 }
 
 fn parse_script(script: &str) -> Script {
     match script.to_lowercase().as_str() {
         "simplified" => Script::SimplifiedChinese,
         "traditional" => Script::TraditionalChinese,
+        "taiwan" | "zh-tw" => Script::TraditionalTaiwan,
+        "hongkong" | "zh-hk" => Script::TraditionalHongKong,
+        "shinjitai" => Script::JapaneseShinjitai,
         "japanese" => Script::Japanese,
         "korean" => Script::Korean,
         _ => Script::Auto,
     }
 }
 
+fn parse_preset(name: &str) -> Result<Preset, String> {
+    Preset::from_name(name).ok_or_else(|| {
+        let names: Vec<&str> = Preset::ALL.iter().map(|preset| preset.name()).collect();
+        format!("unknown preset, expected one of: {}", names.join(", "))
+    })
+}
+
+fn parse_protection(kind: &str) -> Result<ProtectionRule, String> {
+    match kind.to_lowercase().as_str() {
+        "urls" | "url" => Ok(ProtectionRule::Urls),
+        "emails" | "email" => Ok(ProtectionRule::Emails),
+        "code" => Ok(ProtectionRule::InlineCode),
+        _ => Err("unknown span kind, expected one of: urls, emails, code".to_string()),
+    }
+}
+
+fn parse_hanja(mode: &str) -> Result<HanjaTransliteration, String> {
+    match mode.to_lowercase().as_str() {
+        "hangul" => Ok(HanjaTransliteration::Hangul),
+        "hangul-hanja" => Ok(HanjaTransliteration::HangulWithHanja),
+        "none" => Ok(HanjaTransliteration::None),
+        _ => Err("unknown mode, expected one of: hangul, hangul-hanja, none".to_string()),
+    }
+}
+
+fn parse_lossless(mode: &str) -> Result<LossyConversion, String> {
+    match mode.to_lowercase().as_str() {
+        "keep" => Ok(LossyConversion::Keep),
+        "annotate" => Ok(LossyConversion::Annotate),
+        _ => Err("unknown mode, expected one of: keep, annotate".to_string()),
+    }
+}
+
 fn parse_format(format: &str) -> OutputFormat {
     match format.to_lowercase().as_str() {
         "detailed" => OutputFormat::Detailed,
@@ -45,19 +131,63 @@ fn parse_format(format: &str) -> OutputFormat {
     }
 }
 
+fn print_confidence(normalizer: &TextNormalizer, text: &str) {
+    let detection = normalizer.analyze_script(text);
+    println!(
+        "Script Confidence: {:.2} ({} CJK characters)",
+        detection.confidence, detection.cjk_char_count
+    );
+}
+
 fn main() {
     let args = Args::parse();
-    let normalizer = TextNormalizer::new();
+    let config = args
+        .preset
+        .map_or_else(NormalizationConfig::default, NormalizationConfig::from)
+        .with_strict(args.strict)
+        .with_collapsed_tables(args.collapse_tables)
+        .with_taiwan_vocabulary(args.taiwan_vocabulary)
+        .with_hanja_transliteration(args.hanja.unwrap_or_default())
+        .with_lossy_conversion(args.lossless.unwrap_or_default());
+    let mut store = match args.data_dir {
+        Some(ref dir) => DataStore::new(DataSource::directory(dir)),
+        None => DataStore::new(DataSource::default()),
+    };
+    if let Some(ref dir) = args.opencc_dir {
+        store = store.with_opencc_dictionaries(dir);
+    }
+    let normalizer = match TextNormalizer::try_with_data(config.clone(), &store) {
+        Ok(normalizer) => normalizer,
+        Err(error) if args.strict => {
+            eprintln!("Error: {}", error);
+            std::process::exit(1);
+        }
+        Err(_) => TextNormalizer::with_data(config, &store),
+    };
+    let mut normalizer = normalizer;
+    for path in &args.dictionary {
+        match UserDictionary::from_path(path) {
+            Ok(dictionary) => normalizer = normalizer.with_dictionary(&dictionary),
+            Err(error) => {
+                eprintln!("Error: {}", error);
+                std::process::exit(1);
+            }
+        }
+    }
+    let normalizer = args
+        .protect
+        .into_iter()
+        .fold(normalizer, TextNormalizer::with_protection);
 
+    let target_script = if args.target == "auto" {
+        None
+    } else {
+        Some(parse_script(&args.target))
+    };
     let result = if args.validate {
         normalizer.validate(&args.text)
     } else {
-        let target_script = if args.target == "auto" {
-            None
-        } else {
-            Some(parse_script(&args.target))
-        };
-        normalizer.normalize(&args.text, target_script)
+        normalizer.normalize(&args.text, target_script.clone())
     };
 
     match parse_format(&args.format) {
@@ -68,12 +198,18 @@ fn main() {
             println!("Original: {}", result.original);
             println!("Normalized: {}", result.normalized);
             println!("Detected Script: {:?}", result.detected_script);
+            print_confidence(&normalizer, &args.text);
             println!("Processing Time: {}ms", result.processing_time_ms);
+            let ambiguous = result.ambiguous_changes().count();
+            if ambiguous > 0 {
+                println!("Ambiguous Changes: {}", ambiguous);
+            }
         }
         OutputFormat::Verbose => {
             println!("Original: {}", result.original);
             println!("Normalized: {}", result.normalized);
             println!("Detected Script: {:?}", result.detected_script);
+            print_confidence(&normalizer, &args.text);
             println!("Processing Time: {}ms", result.processing_time_ms);
             println!();
             println!("Changes:");
@@ -81,14 +217,35 @@ fn main() {
                 println!(
                     "  Position {}: {} → {} ({:?})",
                     change.position,
-                    change.original_char,
-                    change.normalized_char,
+                    change.original_text,
+                    change.normalized_text,
                     change.change_type
                 );
+                if !change.pinyin.is_empty() {
+                    println!("    Reading: {} ({})", change.pinyin, change.zhuyin);
+                }
+                if change.ambiguous {
+                    println!("    Ambiguous: {}", change.alternatives.join(" / "));
+                }
                 if args.verbose {
                     println!("    Reason: {}", change.reason);
                 }
             }
         }
     }
+
+    if args.round_trip {
+        let losses = normalizer.round_trip_losses(&args.text, target_script);
+        println!();
+        println!("Round-trip losses: {}", losses.len());
+        for loss in &losses {
+            println!(
+                "  Position {}: {} → {} → {}",
+                loss.position, loss.original, loss.converted, loss.restored
+            );
+            if loss.alternatives.len() > 1 {
+                println!("    Candidates: {}", loss.alternatives.join(" / "));
+            }
+        }
+    }
 }
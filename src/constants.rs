@@ -2,6 +2,8 @@
 
 /// Data file paths to avoid repetition across the codebase
 pub mod paths {
+    /// Default root of the processed data files
+    pub const DATA_DIR: &str = "data/processed";
     pub const SCRIPT_DIR: &str = "data/processed/script_conversion";
     pub const NORM_DIR: &str = "data/processed/normalization";
 
@@ -10,17 +12,27 @@ pub mod paths {
         "data/processed/script_conversion/traditional_to_simplified.json";
     pub const S2T_MAPPINGS: &str =
         "data/processed/script_conversion/simplified_to_traditional.json";
+    pub const T2S_PHRASES: &str =
+        "data/processed/script_conversion/traditional_to_simplified_phrases.json";
+    pub const S2T_PHRASES: &str =
+        "data/processed/script_conversion/simplified_to_traditional_phrases.json";
+    pub const READINGS: &str = "data/processed/script_conversion/readings.json";
     pub const SCRIPT_STATS: &str = "data/processed/script_conversion/script_conversion_stats.json";
 
     // Normalization files
     pub const SEMANTIC_VARIANTS: &str = "data/processed/normalization/semantic_variants.json";
     pub const COMPAT_VARIANTS: &str = "data/processed/normalization/compatibility_variants.json";
     pub const KANGXI_RADICALS: &str = "data/processed/normalization/kangxi_radicals.json";
+    pub const HANGUL_READINGS: &str = "data/processed/normalization/hangul_readings.json";
+    pub const SPOOFING_VARIANTS: &str = "data/processed/normalization/spoofing_variants.json";
     pub const NORM_STATS: &str = "data/processed/normalization/normalization_stats.json";
 
     // Source files
     pub const UNIHAN_IRG: &str = "Unihan/Unihan_IRGSources.txt";
     pub const UNIHAN_VARIANTS: &str = "Unihan/Unihan_Variants.txt";
+    pub const UNIHAN_READINGS: &str = "Unihan/Unihan_Readings.txt";
+    pub const UNIHAN_DICTIONARY_LIKE: &str = "Unihan/Unihan_DictionaryLikeData.txt";
+    pub const UNIHAN_OTHER_MAPPINGS: &str = "Unihan/Unihan_OtherMappings.txt";
 }
 
 /// Algorithm configuration constants
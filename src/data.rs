@@ -0,0 +1,803 @@
+//! Mapping data sources and the process-wide store of loaded tables
+//!
+//! Every table is read and parsed at most once per [`DataStore`], on first use,
+//! and handed out as an `Arc` so that normalizers built afterwards share it
+//! instead of reloading it. Where the tables come from is set by a
+//! [`DataSource`]; by default they are compiled into the library (the
+//! `embedded-data` feature) and no data files are needed.
+
+use crate::constants::paths;
+use crate::diagnostics::{Fallback, LoadReport};
+use crate::error::{Error, Result};
+use crate::normalizers::{
+    compatibility_normalizer::CompatibilityNormalizer, hanja_transliterator::HanjaTransliterator,
+    kangxi_normalizer::KangxiNormalizer, script_converter::ScriptConverter,
+    variant_normalizer::VariantNormalizer,
+};
+use crate::types::{Reading, ScriptMapping, VariantMappings};
+use crate::utils::opencc_validator::OpenCCValidator;
+use crate::utils::pinyin;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, LazyLock, Mutex, MutexGuard, OnceLock};
+
+/// Single-char replacement table
+pub type CharTable = HashMap<char, char>;
+
+/// Script conversion table keyed by the source character, candidates ranked best first
+pub type ScriptTable = HashMap<String, Vec<ScriptMapping>>;
+
+/// Phrase conversion table: phrase → its form in the other script
+pub type PhraseTable = HashMap<String, String>;
+
+/// Mandarin reading and frequency of each character
+pub type ReadingTable = HashMap<char, Reading>;
+
+/// Tables generated by `build.rs` from the processed data files
+#[cfg(feature = "embedded-data")]
+mod embedded {
+    include!(concat!(env!("OUT_DIR"), "/embedded_tables.rs"));
+}
+
+/// Processed data files the normalizers are built from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DataFile {
+    TraditionalToSimplified,
+    SimplifiedToTraditional,
+    /// Traditional phrase → its Simplified form, deciding characters with several candidates in context
+    TraditionalToSimplifiedPhrases,
+    /// Simplified phrase → its Traditional form (头发 → 頭髮, 发展 → 發展)
+    SimplifiedToTraditionalPhrases,
+    SemanticVariants,
+    CompatibilityVariants,
+    KangxiRadicals,
+    /// Pinyin and usage frequency per character, used to rank script conversion candidates
+    Readings,
+    /// Sino-Korean Hangul reading per hanja, before the initial-sound rule
+    HangulReadings,
+    /// Look-alike character → the member of its group it is folded to
+    SpoofingVariants,
+}
+
+impl DataFile {
+    /// Default location of the file, relative to the crate root
+    pub fn path(self) -> &'static str {
+        match self {
+            DataFile::TraditionalToSimplified => paths::T2S_MAPPINGS,
+            DataFile::SimplifiedToTraditional => paths::S2T_MAPPINGS,
+            DataFile::TraditionalToSimplifiedPhrases => paths::T2S_PHRASES,
+            DataFile::SimplifiedToTraditionalPhrases => paths::S2T_PHRASES,
+            DataFile::SemanticVariants => paths::SEMANTIC_VARIANTS,
+            DataFile::CompatibilityVariants => paths::COMPAT_VARIANTS,
+            DataFile::KangxiRadicals => paths::KANGXI_RADICALS,
+            DataFile::Readings => paths::READINGS,
+            DataFile::HangulReadings => paths::HANGUL_READINGS,
+            DataFile::SpoofingVariants => paths::SPOOFING_VARIANTS,
+        }
+    }
+
+    /// Location of the file inside a data directory (e.g. `normalization/kangxi_radicals.json`)
+    pub fn relative_path(self) -> &'static Path {
+        let path = Path::new(self.path());
+        path.strip_prefix(paths::DATA_DIR).unwrap_or(path)
+    }
+
+    /// Compiled-in entries of a `source → target` file
+    #[cfg(feature = "embedded-data")]
+    fn embedded(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            DataFile::TraditionalToSimplified => embedded::TRADITIONAL_TO_SIMPLIFIED,
+            DataFile::SimplifiedToTraditional => embedded::SIMPLIFIED_TO_TRADITIONAL,
+            DataFile::TraditionalToSimplifiedPhrases => embedded::TRADITIONAL_TO_SIMPLIFIED_PHRASES,
+            DataFile::SimplifiedToTraditionalPhrases => embedded::SIMPLIFIED_TO_TRADITIONAL_PHRASES,
+            DataFile::SemanticVariants => embedded::SEMANTIC_VARIANTS,
+            DataFile::CompatibilityVariants => embedded::COMPATIBILITY_VARIANTS,
+            DataFile::KangxiRadicals => embedded::KANGXI_RADICALS,
+            DataFile::HangulReadings => embedded::HANGUL_READINGS,
+            DataFile::SpoofingVariants => embedded::SPOOFING_VARIANTS,
+            // Not a `source → target` table, see `embedded_readings`
+            DataFile::Readings => &[],
+        }
+    }
+}
+
+/// Where the mapping tables are loaded from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DataSource {
+    /// Directory laid out like `data/processed` (`script_conversion/`, `normalization/`)
+    Directory(PathBuf),
+    /// Directory named by an environment variable; the default source is used if it is unset
+    EnvVar(String),
+    /// JSON file contents held in memory; files left out are not found, like
+    /// files missing from a directory
+    Memory(HashMap<DataFile, Vec<u8>>),
+    /// Tables compiled into the library (requires the `embedded-data` feature)
+    Embedded,
+}
+
+impl DataSource {
+    /// Load from the given directory
+    pub fn directory<P: Into<PathBuf>>(path: P) -> Self {
+        DataSource::Directory(path.into())
+    }
+
+    /// Load from the directory named by the given environment variable
+    pub fn env_var<S: Into<String>>(name: S) -> Self {
+        DataSource::EnvVar(name.into())
+    }
+
+    /// Load from in-memory JSON, one entry per file
+    pub fn memory<I>(files: I) -> Self
+    where
+        I: IntoIterator<Item = (DataFile, Vec<u8>)>,
+    {
+        DataSource::Memory(files.into_iter().collect())
+    }
+
+    /// `source → target` entries of a data file, without identity mappings
+    ///
+    /// The load is recorded in `report`.
+    pub(crate) fn load_pairs(
+        &self,
+        file: DataFile,
+        report: &mut LoadReport,
+    ) -> Result<Vec<(String, String)>> {
+        let mut pairs = self.read_pairs(file)?;
+        let total = pairs.len();
+        pairs.retain(|(source, target)| source != target);
+        report.loaded(file, self.to_string(), pairs.len(), total - pairs.len());
+        Ok(pairs)
+    }
+
+    /// `source → target` entries of a data file, identity mappings included
+    ///
+    /// Script conversion tables list a character among its own candidates
+    /// where it is also written as is in the other script (才 → 才, 纔), so
+    /// conversion can keep it. The load is recorded in `report`.
+    pub(crate) fn load_candidates(
+        &self,
+        file: DataFile,
+        report: &mut LoadReport,
+    ) -> Result<Vec<(String, String)>> {
+        let pairs = self.read_pairs(file)?;
+        report.loaded(file, self.to_string(), pairs.len(), 0);
+        Ok(pairs)
+    }
+
+    /// All `source → target` entries of a data file as stored
+    fn read_pairs(&self, file: DataFile) -> Result<Vec<(String, String)>> {
+        match self {
+            DataSource::Directory(dir) => parse_pairs(file, &read_file(dir, file)?),
+            DataSource::EnvVar(name) => match std::env::var_os(name) {
+                Some(dir) => DataSource::Directory(dir.into()).read_pairs(file),
+                None => DataSource::default().read_pairs(file),
+            },
+            DataSource::Memory(files) => match files.get(&file) {
+                Some(contents) => parse_pairs(file, contents),
+                None => Err(Error::DataNotFound {
+                    file,
+                    location: self.to_string(),
+                }),
+            },
+            DataSource::Embedded => embedded_pairs(file),
+        }
+    }
+
+    /// Reading and frequency of every character in the readings file
+    ///
+    /// A file without entries is an error. The load is recorded in `report`.
+    pub(crate) fn load_readings(&self, report: &mut LoadReport) -> Result<Vec<(char, Reading)>> {
+        let readings = self.read_readings()?;
+        if readings.is_empty() {
+            return Err(Error::SchemaMismatch {
+                file: DataFile::Readings,
+                message: "the table has no readings".to_string(),
+            });
+        }
+        report.loaded(DataFile::Readings, self.to_string(), readings.len(), 0);
+        Ok(readings)
+    }
+
+    fn read_readings(&self) -> Result<Vec<(char, Reading)>> {
+        let file = DataFile::Readings;
+        match self {
+            DataSource::Directory(dir) => parse_readings(&read_file(dir, file)?),
+            DataSource::EnvVar(name) => match std::env::var_os(name) {
+                Some(dir) => DataSource::Directory(dir.into()).read_readings(),
+                None => DataSource::default().read_readings(),
+            },
+            DataSource::Memory(files) => match files.get(&file) {
+                Some(contents) => parse_readings(contents),
+                None => Err(Error::DataNotFound {
+                    file,
+                    location: self.to_string(),
+                }),
+            },
+            DataSource::Embedded => embedded_readings(),
+        }
+    }
+
+    /// Entries of a data file whose keys and values must be single characters
+    pub(crate) fn load_char_pairs(
+        &self,
+        file: DataFile,
+        report: &mut LoadReport,
+    ) -> Result<Vec<(char, char)>> {
+        self.load_pairs(file, report)?
+            .into_iter()
+            .map(
+                |(source, target)| match (single_char(&source), single_char(&target)) {
+                    (Some(source), Some(target)) => Ok((source, target)),
+                    _ => Err(Error::SchemaMismatch {
+                        file,
+                        message: format!(
+                            "{:?} → {:?} is not a single-character mapping",
+                            source, target
+                        ),
+                    }),
+                },
+            )
+            .collect()
+    }
+}
+
+impl Default for DataSource {
+    /// Embedded tables if compiled in, otherwise the `data/processed` directory
+    fn default() -> Self {
+        if cfg!(feature = "embedded-data") {
+            DataSource::Embedded
+        } else {
+            DataSource::Directory(PathBuf::from(paths::DATA_DIR))
+        }
+    }
+}
+
+impl fmt::Display for DataSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DataSource::Directory(dir) => write!(f, "directory {}", dir.display()),
+            DataSource::EnvVar(name) => write!(f, "directory ${}", name),
+            DataSource::Memory(_) => write!(f, "in-memory data"),
+            DataSource::Embedded => write!(f, "embedded data"),
+        }
+    }
+}
+
+/// Parse a JSON object of string → string entries
+///
+/// A value may also be an array of strings, listing every candidate target in
+/// order of preference; each becomes an entry of its own.
+fn parse_pairs(file: DataFile, contents: &[u8]) -> Result<Vec<(String, String)>> {
+    let value: serde_json::Value =
+        serde_json::from_slice(contents).map_err(|error| Error::Parse {
+            file,
+            message: error.to_string(),
+        })?;
+    let serde_json::Value::Object(entries) = value else {
+        return Err(Error::SchemaMismatch {
+            file,
+            message: "expected a JSON object".to_string(),
+        });
+    };
+    let mut pairs = Vec::with_capacity(entries.len());
+    for (key, value) in entries {
+        match value {
+            serde_json::Value::String(target) => pairs.push((key, target)),
+            serde_json::Value::Array(targets)
+                if targets.iter().all(serde_json::Value::is_string) =>
+            {
+                for target in targets {
+                    if let serde_json::Value::String(target) = target {
+                        pairs.push((key.clone(), target));
+                    }
+                }
+            }
+            other => {
+                return Err(Error::SchemaMismatch {
+                    file,
+                    message: format!(
+                        "value of {:?} is {}, expected a string or an array of strings",
+                        key, other
+                    ),
+                })
+            }
+        }
+    }
+    Ok(pairs)
+}
+
+/// Contents of a data file in a data directory
+fn read_file(dir: &Path, file: DataFile) -> Result<Vec<u8>> {
+    let path = dir.join(file.relative_path());
+    std::fs::read(&path).map_err(|error| {
+        if error.kind() == std::io::ErrorKind::NotFound {
+            Error::DataNotFound {
+                file,
+                location: path.display().to_string(),
+            }
+        } else {
+            Error::Io {
+                file,
+                location: path.display().to_string(),
+                message: error.to_string(),
+            }
+        }
+    })
+}
+
+/// Entry of the readings file; the Zhuyin is derived when loading
+#[derive(Deserialize)]
+struct ReadingEntry {
+    #[serde(default)]
+    pinyin: String,
+    #[serde(default)]
+    frequency: u32,
+}
+
+impl ReadingEntry {
+    fn into_reading(self) -> Reading {
+        Reading {
+            zhuyin: pinyin::to_zhuyin(&self.pinyin).unwrap_or_default(),
+            pinyin: self.pinyin,
+            frequency: self.frequency,
+        }
+    }
+}
+
+/// Parse a JSON object of character → `{"pinyin": ..., "frequency": ...}` entries
+fn parse_readings(contents: &[u8]) -> Result<Vec<(char, Reading)>> {
+    let file = DataFile::Readings;
+    let value: serde_json::Value =
+        serde_json::from_slice(contents).map_err(|error| Error::Parse {
+            file,
+            message: error.to_string(),
+        })?;
+    let entries: HashMap<String, ReadingEntry> =
+        serde_json::from_value(value).map_err(|error| Error::SchemaMismatch {
+            file,
+            message: error.to_string(),
+        })?;
+    entries
+        .into_iter()
+        .map(|(key, entry)| match single_char(&key) {
+            Some(ch) => Ok((ch, entry.into_reading())),
+            None => Err(Error::SchemaMismatch {
+                file,
+                message: format!("{:?} is not a single character", key),
+            }),
+        })
+        .collect()
+}
+
+fn single_char(text: &str) -> Option<char> {
+    let mut chars = text.chars();
+    match (chars.next(), chars.next()) {
+        (Some(ch), None) => Some(ch),
+        _ => None,
+    }
+}
+
+#[cfg(feature = "embedded-data")]
+fn embedded_pairs(file: DataFile) -> Result<Vec<(String, String)>> {
+    Ok(file
+        .embedded()
+        .iter()
+        .map(|&(source, target)| (source.to_string(), target.to_string()))
+        .collect())
+}
+
+#[cfg(feature = "embedded-data")]
+fn embedded_readings() -> Result<Vec<(char, Reading)>> {
+    embedded::READINGS
+        .iter()
+        .map(|&(key, pinyin, frequency)| {
+            let ch = single_char(key).ok_or_else(|| Error::SchemaMismatch {
+                file: DataFile::Readings,
+                message: format!("{:?} is not a single character", key),
+            })?;
+            let entry = ReadingEntry {
+                pinyin: pinyin.to_string(),
+                frequency,
+            };
+            Ok((ch, entry.into_reading()))
+        })
+        .collect()
+}
+
+#[cfg(not(feature = "embedded-data"))]
+fn embedded_pairs(file: DataFile) -> Result<Vec<(String, String)>> {
+    Err(Error::DataNotFound {
+        file,
+        location: "embedded data (the `embedded-data` feature is disabled)".to_string(),
+    })
+}
+
+#[cfg(not(feature = "embedded-data"))]
+fn embedded_readings() -> Result<Vec<(char, Reading)>> {
+    Err(Error::DataNotFound {
+        file: DataFile::Readings,
+        location: "embedded data (the `embedded-data` feature is disabled)".to_string(),
+    })
+}
+
+/// Lazily loaded mapping tables shared between normalizers
+///
+/// Each table is loaded once and the outcome is kept, including failures. The
+/// `try_*` accessors report a failed load; the plain accessors fall back to an
+/// empty table (or a minimal built-in one for script conversion) so that
+/// normalization degrades instead of stopping. Every load, and the fallback
+/// for every failed one, is recorded in the store's [`LoadReport`].
+#[derive(Default)]
+pub struct DataStore {
+    source: DataSource,
+    kangxi: OnceLock<Result<Arc<CharTable>>>,
+    compatibility: OnceLock<Result<Arc<CharTable>>>,
+    variants: OnceLock<Result<Arc<VariantMappings>>>,
+    script: OnceLock<Result<(Arc<ScriptTable>, Arc<ScriptTable>)>>,
+    phrases: OnceLock<Result<(Arc<PhraseTable>, Arc<PhraseTable>)>>,
+    readings: OnceLock<Result<Arc<ReadingTable>>>,
+    hangul: OnceLock<Result<Arc<CharTable>>>,
+    spoofing: OnceLock<Result<Arc<CharTable>>>,
+    opencc: OnceLock<Result<Arc<OpenCCValidator>>>,
+    /// OpenCC configs and dictionaries for the native engine
+    opencc_dir: Option<PathBuf>,
+    report: Mutex<LoadReport>,
+}
+
+/// Environment variable naming a directory of OpenCC configs and dictionaries
+///
+/// Used by stores without [`DataStore::with_opencc_dictionaries`].
+pub const OPENCC_DIR_VAR: &str = "ZHO_OPENCC_DIR";
+
+static SHARED: LazyLock<DataStore> = LazyLock::new(DataStore::default);
+
+impl DataStore {
+    /// Create a store that loads its tables from the given source
+    pub fn new(source: DataSource) -> Self {
+        Self {
+            source,
+            ..Self::default()
+        }
+    }
+
+    /// Convert with the native OpenCC engine, reading OpenCC's configs and
+    /// plain-text dictionaries from `dir`, builder style
+    ///
+    /// Without this, the directory in [`OPENCC_DIR_VAR`] is used if set, and
+    /// otherwise the system libopencc (the `opencc` feature) or, without
+    /// it, the native engine over the store's script tables.
+    pub fn with_opencc_dictionaries<P: Into<PathBuf>>(mut self, dir: P) -> Self {
+        self.opencc_dir = Some(dir.into());
+        self
+    }
+
+    /// The store used by all normalizers created with `new()`, backed by the default source
+    pub fn shared() -> &'static DataStore {
+        &SHARED
+    }
+
+    /// Source the tables are loaded from
+    pub fn source(&self) -> &DataSource {
+        &self.source
+    }
+
+    /// Tables loaded so far, their entry counts and any fallbacks in use
+    pub fn load_report(&self) -> LoadReport {
+        self.lock_report().clone()
+    }
+
+    /// Kangxi radical → standard character table
+    pub fn try_kangxi(&self) -> Result<Arc<CharTable>> {
+        self.load_once(&self.kangxi, Fallback::Empty, |report| {
+            KangxiNormalizer::load_kangxi_mappings(&self.source, report).map(Arc::new)
+        })
+    }
+
+    /// Compatibility form → standard character table
+    pub fn try_compatibility(&self) -> Result<Arc<CharTable>> {
+        self.load_once(&self.compatibility, Fallback::Empty, |report| {
+            CompatibilityNormalizer::load_compatibility_mappings(&self.source, report).map(Arc::new)
+        })
+    }
+
+    /// Character variant mappings
+    pub fn try_variants(&self) -> Result<Arc<VariantMappings>> {
+        self.load_once(&self.variants, Fallback::Empty, |report| {
+            VariantNormalizer::load_enhanced_variant_mappings(&self.source, report).map(Arc::new)
+        })
+    }
+
+    /// Traditional → Simplified and Simplified → Traditional tables, phrases included
+    pub fn try_script_tables(&self) -> Result<(Arc<ScriptTable>, Arc<ScriptTable>)> {
+        let readings = self.readings();
+        let phrases = self.phrases();
+        self.load_once(&self.script, Fallback::BuiltIn, |report| {
+            ScriptConverter::load_comprehensive_mappings(&self.source, &readings, &phrases, report)
+                .map(|(t2s, s2t)| (Arc::new(t2s), Arc::new(s2t)))
+        })
+    }
+
+    /// Traditional → Simplified and Simplified → Traditional phrase tables
+    pub fn try_phrases(&self) -> Result<(Arc<PhraseTable>, Arc<PhraseTable>)> {
+        self.load_once(&self.phrases, Fallback::Empty, |report| {
+            ScriptConverter::load_phrases(&self.source, report)
+                .map(|(t2s, s2t)| (Arc::new(t2s), Arc::new(s2t)))
+        })
+    }
+
+    /// Pinyin, Zhuyin and frequency per character
+    pub fn try_readings(&self) -> Result<Arc<ReadingTable>> {
+        self.load_once(&self.readings, Fallback::Empty, |report| {
+            self.source
+                .load_readings(report)
+                .map(|readings| Arc::new(readings.into_iter().collect()))
+        })
+    }
+
+    /// Hanja → Sino-Korean Hangul reading table
+    pub fn try_hangul_readings(&self) -> Result<Arc<CharTable>> {
+        self.load_once(&self.hangul, Fallback::Empty, |report| {
+            HanjaTransliterator::load_hangul_readings(&self.source, report).map(Arc::new)
+        })
+    }
+
+    /// Look-alike character → folded form table
+    pub fn try_spoofing_variants(&self) -> Result<Arc<CharTable>> {
+        self.load_once(&self.spoofing, Fallback::Empty, |report| {
+            VariantNormalizer::load_spoofing_variants(&self.source, report).map(Arc::new)
+        })
+    }
+
+    /// OpenCC converters: native over a dictionary directory if one is
+    /// configured, else the system libopencc (the `opencc` feature), else
+    /// native over this store's script tables
+    pub fn try_opencc(&self) -> Result<Arc<OpenCCValidator>> {
+        self.opencc
+            .get_or_init(|| {
+                let dir = self
+                    .opencc_dir
+                    .clone()
+                    .or_else(|| std::env::var_os(OPENCC_DIR_VAR).map(PathBuf::from));
+                let opencc = match dir {
+                    Some(ref dir) => OpenCCValidator::native(dir),
+                    None if cfg!(feature = "opencc") => OpenCCValidator::new(),
+                    None => {
+                        let (t2s, s2t) = self.script_tables();
+                        OpenCCValidator::built_in(&t2s, &s2t)
+                    }
+                }
+                .map(Arc::new);
+                match (&opencc, &dir) {
+                    (Ok(_), Some(dir)) => {
+                        log::info!(
+                            "initialized native OpenCC converters from {}",
+                            dir.display()
+                        )
+                    }
+                    (Ok(_), None) if cfg!(feature = "opencc") => {
+                        log::info!("initialized OpenCC converters")
+                    }
+                    (Ok(_), None) => {
+                        log::info!("initialized native OpenCC converters from the script tables")
+                    }
+                    (Err(error), _) => log::warn!("{}", error),
+                }
+                self.lock_report().opencc = Some(opencc.as_ref().map(|_| ()).map_err(Clone::clone));
+                opencc
+            })
+            .clone()
+    }
+
+    /// Kangxi radical table, empty if it could not be loaded
+    pub fn kangxi(&self) -> Arc<CharTable> {
+        self.try_kangxi().unwrap_or_default()
+    }
+
+    /// Compatibility form table, empty if it could not be loaded
+    pub fn compatibility(&self) -> Arc<CharTable> {
+        self.try_compatibility().unwrap_or_default()
+    }
+
+    /// Character variant mappings, empty if they could not be loaded
+    pub fn variants(&self) -> Arc<VariantMappings> {
+        self.try_variants()
+            .unwrap_or_else(|_| Arc::new(VariantMappings::new()))
+    }
+
+    /// Script conversion tables, or a minimal built-in pair of tables if they could not be loaded
+    pub fn script_tables(&self) -> (Arc<ScriptTable>, Arc<ScriptTable>) {
+        self.try_script_tables().unwrap_or_else(|_| {
+            let (t2s, s2t) = ScriptConverter::fallback_mappings();
+            (Arc::new(t2s), Arc::new(s2t))
+        })
+    }
+
+    /// Phrase tables, empty if they could not be loaded
+    pub fn phrases(&self) -> (Arc<PhraseTable>, Arc<PhraseTable>) {
+        self.try_phrases().unwrap_or_default()
+    }
+
+    /// Readings, empty if they could not be loaded
+    pub fn readings(&self) -> Arc<ReadingTable> {
+        self.try_readings().unwrap_or_default()
+    }
+
+    /// Hangul readings, empty if they could not be loaded
+    pub fn hangul_readings(&self) -> Arc<CharTable> {
+        self.try_hangul_readings().unwrap_or_default()
+    }
+
+    /// Spoofing variant table, empty if it could not be loaded
+    pub fn spoofing_variants(&self) -> Arc<CharTable> {
+        self.try_spoofing_variants().unwrap_or_default()
+    }
+
+    /// OpenCC converters, if the library could be initialized
+    pub fn opencc(&self) -> Option<Arc<OpenCCValidator>> {
+        self.try_opencc().ok()
+    }
+
+    /// Run `load` the first time only, recording what it loaded or the fallback used if it failed
+    fn load_once<T: Clone>(
+        &self,
+        cell: &OnceLock<Result<T>>,
+        fallback: Fallback,
+        load: impl FnOnce(&mut LoadReport) -> Result<T>,
+    ) -> Result<T> {
+        cell.get_or_init(|| {
+            let mut loads = LoadReport::default();
+            let loaded = load(&mut loads);
+            if let Err(error) = &loaded {
+                if let Some(file) = error.file() {
+                    loads.failed(file, self.source.to_string(), error.clone(), fallback);
+                }
+            }
+            self.lock_report().tables.append(&mut loads.tables);
+            loaded
+        })
+        .clone()
+    }
+
+    fn lock_report(&self) -> MutexGuard<'_, LoadReport> {
+        self.report
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tables_are_loaded_once() {
+        let store = DataStore::default();
+        let first = store.kangxi();
+        let second = store.kangxi();
+
+        assert!(Arc::ptr_eq(&first, &second));
+        assert!(Arc::ptr_eq(
+            &DataStore::shared().variants(),
+            &DataStore::shared().variants()
+        ));
+    }
+
+    #[test]
+    fn test_memory_source() {
+        let store = DataStore::new(DataSource::memory([(
+            DataFile::KangxiRadicals,
+            r#"{"⽅": "X"}"#.as_bytes().to_vec(),
+        )]));
+
+        assert_eq!(store.kangxi().get(&'⽅'), Some(&'X'));
+        assert!(store.compatibility().is_empty());
+    }
+
+    #[test]
+    fn test_directory_source() {
+        let store = DataStore::new(DataSource::directory(paths::DATA_DIR));
+        assert_eq!(store.kangxi().get(&'⽅'), Some(&'方'));
+
+        let store = DataStore::new(DataSource::directory("no/such/dir"));
+        assert!(store.kangxi().is_empty());
+    }
+
+    #[test]
+    fn test_load_errors() {
+        let store = DataStore::new(DataSource::memory([
+            (DataFile::KangxiRadicals, b"{not json".to_vec()),
+            (
+                DataFile::CompatibilityVariants,
+                br#"["not", "a", "map"]"#.to_vec(),
+            ),
+            (
+                DataFile::SemanticVariants,
+                r#"{"硏": "研究"}"#.as_bytes().to_vec(),
+            ),
+        ]));
+
+        assert!(matches!(store.try_kangxi(), Err(Error::Parse { .. })));
+        assert!(matches!(
+            store.try_compatibility(),
+            Err(Error::SchemaMismatch { .. })
+        ));
+        assert!(matches!(
+            store.try_variants(),
+            Err(Error::SchemaMismatch { .. })
+        ));
+        assert!(matches!(
+            store.try_script_tables(),
+            Err(Error::DataNotFound {
+                file: DataFile::TraditionalToSimplified,
+                ..
+            })
+        ));
+
+        // The lenient accessors fall back instead
+        assert!(store.kangxi().is_empty());
+        assert!(!store.script_tables().0.is_empty());
+    }
+
+    #[test]
+    fn test_empty_readings_fall_back() {
+        let store = DataStore::new(DataSource::memory([(DataFile::Readings, b"{}".to_vec())]));
+
+        assert!(matches!(
+            store.try_readings(),
+            Err(Error::SchemaMismatch {
+                file: DataFile::Readings,
+                ..
+            })
+        ));
+        assert!(store.readings().is_empty());
+        let report = store.load_report();
+        let readings = report.table(DataFile::Readings).unwrap();
+        assert_eq!(readings.fallback, Some(Fallback::Empty));
+
+        // The shipped table ranks 發 above 髮
+        let readings = DataStore::default().readings();
+        assert!(readings[&'發'].frequency < readings[&'髮'].frequency);
+    }
+
+    #[test]
+    fn test_load_report() {
+        let store = DataStore::new(DataSource::memory([
+            (
+                DataFile::KangxiRadicals,
+                r#"{"⽅": "方", "方": "方"}"#.as_bytes().to_vec(),
+            ),
+            (DataFile::SemanticVariants, b"{not json".to_vec()),
+        ]));
+        store.kangxi();
+        store.variants();
+        store.script_tables();
+
+        let report = store.load_report();
+        let kangxi = report.table(DataFile::KangxiRadicals).unwrap();
+        assert_eq!((kangxi.entries, kangxi.skipped), (1, 1));
+        assert_eq!(kangxi.fallback, None);
+
+        let variants = report.table(DataFile::SemanticVariants).unwrap();
+        assert!(matches!(variants.error, Some(Error::Parse { .. })));
+        assert_eq!(variants.fallback, Some(Fallback::Empty));
+
+        let script = report.table(DataFile::TraditionalToSimplified).unwrap();
+        assert_eq!(script.fallback, Some(Fallback::BuiltIn));
+        assert!(report.table(DataFile::CompatibilityVariants).is_none());
+        assert!(report.is_degraded());
+
+        // Reloading records nothing new
+        store.kangxi();
+        assert_eq!(store.load_report().tables.len(), report.tables.len());
+    }
+
+    #[test]
+    fn test_relative_path() {
+        assert_eq!(
+            DataFile::KangxiRadicals.relative_path(),
+            Path::new("normalization/kangxi_radicals.json")
+        );
+    }
+}
@@ -0,0 +1,105 @@
+//! Load-time diagnostics for the mapping tables
+//!
+//! Loading never writes to stdout or stderr. Every outcome is logged through
+//! the [`log`] facade (`info` for loaded tables, `warn` for fallbacks) and
+//! recorded in a [`LoadReport`] that can be inspected with
+//! [`DataStore::load_report`](crate::data::DataStore::load_report).
+
+use crate::data::DataFile;
+use crate::error::Error;
+
+/// What a table was replaced with after it failed to load
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fallback {
+    /// An empty table: the stage using it changes nothing
+    Empty,
+    /// The minimal table built into the library
+    BuiltIn,
+}
+
+/// Outcome of loading one data file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableLoad {
+    /// File that was loaded
+    pub file: DataFile,
+    /// Source it was loaded from
+    pub source: String,
+    /// Number of entries loaded from the file
+    pub entries: usize,
+    /// Number of entries dropped while loading (identity mappings)
+    pub skipped: usize,
+    /// Why the file could not be loaded, if it failed
+    pub error: Option<Error>,
+    /// What the lenient `DataStore` accessors use instead, if it failed
+    pub fallback: Option<Fallback>,
+}
+
+/// Record of every table loaded by a data store so far
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LoadReport {
+    /// Loaded (or failed) data files, in load order
+    pub tables: Vec<TableLoad>,
+    /// OpenCC initialization outcome; `None` until OpenCC was first needed
+    pub opencc: Option<Result<(), Error>>,
+}
+
+impl LoadReport {
+    /// Outcome of loading the given file, if it was loaded
+    pub fn table(&self, file: DataFile) -> Option<&TableLoad> {
+        self.tables.iter().find(|table| table.file == file)
+    }
+
+    /// Tables replaced by a fallback
+    pub fn fallbacks(&self) -> impl Iterator<Item = &TableLoad> {
+        self.tables.iter().filter(|table| table.fallback.is_some())
+    }
+
+    /// Whether any table or OpenCC failed to load
+    pub fn is_degraded(&self) -> bool {
+        self.fallbacks().next().is_some() || matches!(self.opencc, Some(Err(_)))
+    }
+
+    /// Record a successfully loaded file
+    pub(crate) fn loaded(
+        &mut self,
+        file: DataFile,
+        source: String,
+        entries: usize,
+        skipped: usize,
+    ) {
+        log::info!(
+            "loaded {} entries of {:?} from {} ({} skipped)",
+            entries,
+            file,
+            source,
+            skipped
+        );
+        self.tables.push(TableLoad {
+            file,
+            source,
+            entries,
+            skipped,
+            error: None,
+            fallback: None,
+        });
+    }
+
+    /// Record a file that failed to load and the table used instead
+    pub(crate) fn failed(
+        &mut self,
+        file: DataFile,
+        source: String,
+        error: Error,
+        fallback: Fallback,
+    ) {
+        log::warn!("{}; using {:?} fallback table", error, fallback);
+        self.tables.push(TableLoad {
+            file,
+            source,
+            entries: 0,
+            skipped: 0,
+            error: Some(error),
+            fallback: Some(fallback),
+        });
+    }
+}
@@ -0,0 +1,268 @@
+//! User dictionaries layered on top of the built-in mapping tables
+//!
+//! A [`UserDictionary`] adds, overrides or suppresses single-character
+//! mappings of one stage. Dictionaries take priority over the built-in data
+//! (and over OpenCC for script conversion); when several define the same
+//! character, the one added last wins. Changes they produce name the
+//! dictionary in their `reason`.
+//!
+//! On disk a dictionary is a JSON object with one optional table per stage;
+//! `null` suppresses a mapping:
+//!
+//! ```json
+//! {
+//!     "name": "house-style",
+//!     "variants": { "裏": "裡" },
+//!     "simplified_to_traditional": { "着": null }
+//! }
+//! ```
+
+use crate::error::{Error, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Stage whose mappings a dictionary entry applies to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DictionaryStage {
+    Kangxi,
+    Variant,
+    Compatibility,
+    TraditionalToSimplified,
+    SimplifiedToTraditional,
+}
+
+/// Named set of per-stage mapping overrides
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserDictionary {
+    name: String,
+    entries: HashMap<DictionaryStage, HashMap<char, Option<char>>>,
+}
+
+/// On-disk layout of a dictionary
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DictionaryFile {
+    name: Option<String>,
+    #[serde(default)]
+    kangxi: HashMap<String, Option<String>>,
+    #[serde(default)]
+    variants: HashMap<String, Option<String>>,
+    #[serde(default)]
+    compatibility: HashMap<String, Option<String>>,
+    #[serde(default)]
+    traditional_to_simplified: HashMap<String, Option<String>>,
+    #[serde(default)]
+    simplified_to_traditional: HashMap<String, Option<String>>,
+}
+
+impl UserDictionary {
+    /// Create an empty dictionary
+    pub fn new<S: Into<String>>(name: S) -> Self {
+        Self {
+            name: name.into(),
+            entries: HashMap::new(),
+        }
+    }
+
+    /// Map `source` to `target` in the given stage, builder style
+    pub fn with_mapping(mut self, stage: DictionaryStage, source: char, target: char) -> Self {
+        self.insert(stage, source, Some(target));
+        self
+    }
+
+    /// Leave `source` unchanged in the given stage, builder style
+    pub fn with_suppressed(mut self, stage: DictionaryStage, source: char) -> Self {
+        self.insert(stage, source, None);
+        self
+    }
+
+    /// Add or replace an entry; `None` suppresses the mapping of `source`
+    pub fn insert(&mut self, stage: DictionaryStage, source: char, target: Option<char>) {
+        self.entries
+            .entry(stage)
+            .or_default()
+            .insert(source, target);
+    }
+
+    /// Parse a dictionary from JSON; `name` is used if the JSON does not name the dictionary
+    pub fn from_json(name: &str, contents: &[u8]) -> Result<Self> {
+        let file: DictionaryFile =
+            serde_json::from_slice(contents).map_err(|error| Error::Dictionary {
+                name: name.to_string(),
+                message: error.to_string(),
+            })?;
+
+        let mut dictionary = Self::new(file.name.unwrap_or_else(|| name.to_string()));
+        let tables = [
+            (DictionaryStage::Kangxi, file.kangxi),
+            (DictionaryStage::Variant, file.variants),
+            (DictionaryStage::Compatibility, file.compatibility),
+            (
+                DictionaryStage::TraditionalToSimplified,
+                file.traditional_to_simplified,
+            ),
+            (
+                DictionaryStage::SimplifiedToTraditional,
+                file.simplified_to_traditional,
+            ),
+        ];
+        for (stage, table) in tables {
+            for (source, target) in table {
+                let source = dictionary.single_char(stage, &source)?;
+                let target = match target {
+                    Some(target) => Some(dictionary.single_char(stage, &target)?),
+                    None => None,
+                };
+                dictionary.insert(stage, source, target);
+            }
+        }
+        Ok(dictionary)
+    }
+
+    /// Read a dictionary from a JSON file, named after the file if the JSON does not name it
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = std::fs::read(path).map_err(|error| Error::Dictionary {
+            name: path.display().to_string(),
+            message: error.to_string(),
+        })?;
+        let name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_else(|| path.display().to_string());
+        Self::from_json(&name, &contents)
+    }
+
+    /// Name recorded in the reason of every change the dictionary produces
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Entries of one stage: `source → Some(target)` or `source → None` (suppressed)
+    pub fn entries(
+        &self,
+        stage: DictionaryStage,
+    ) -> impl Iterator<Item = (char, Option<char>)> + '_ {
+        self.entries
+            .get(&stage)
+            .into_iter()
+            .flatten()
+            .map(|(&source, &target)| (source, target))
+    }
+
+    fn single_char(&self, stage: DictionaryStage, text: &str) -> Result<char> {
+        let mut chars = text.chars();
+        match (chars.next(), chars.next()) {
+            (Some(ch), None) => Ok(ch),
+            _ => Err(Error::Dictionary {
+                name: self.name.clone(),
+                message: format!("{:?} entry {:?} is not a single character", stage, text),
+            }),
+        }
+    }
+}
+
+/// Dictionary entry in effect for one character of a stage
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Override {
+    /// Replacement, or `None` to leave the character unchanged
+    pub target: Option<char>,
+    /// Dictionary that defined the entry
+    pub dictionary: Arc<str>,
+}
+
+impl Override {
+    /// Reason of a change made by this entry
+    pub fn reason(&self, description: String) -> String {
+        format!("{} (dictionary: {})", description, self.dictionary)
+    }
+}
+
+/// Dictionary entries of one stage, merged in priority order
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct Overrides {
+    entries: HashMap<char, Override>,
+}
+
+impl Overrides {
+    /// Add the stage's entries of a dictionary, replacing earlier ones
+    pub fn layer(&mut self, dictionary: &UserDictionary, stage: DictionaryStage) {
+        let name: Arc<str> = Arc::from(dictionary.name());
+        for (source, target) in dictionary.entries(stage) {
+            self.entries.insert(
+                source,
+                Override {
+                    target,
+                    dictionary: name.clone(),
+                },
+            );
+        }
+    }
+
+    /// Entry in effect for `ch`, if any dictionary defines one
+    pub fn get(&self, ch: char) -> Option<&Override> {
+        self.entries.get(&ch)
+    }
+
+    /// Every character the entries map from or to
+    pub fn chars(&self) -> impl Iterator<Item = char> + '_ {
+        self.entries
+            .iter()
+            .flat_map(|(&source, entry)| std::iter::once(source).chain(entry.target))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_json() {
+        let dictionary = UserDictionary::from_json(
+            "fallback",
+            r#"{"name": "house", "variants": {"裏": "裡"}, "simplified_to_traditional": {"着": null}}"#
+                .as_bytes(),
+        )
+        .unwrap();
+
+        assert_eq!(dictionary.name(), "house");
+        assert_eq!(
+            dictionary
+                .entries(DictionaryStage::Variant)
+                .collect::<Vec<_>>(),
+            [('裏', Some('裡'))]
+        );
+        assert_eq!(
+            dictionary
+                .entries(DictionaryStage::SimplifiedToTraditional)
+                .collect::<Vec<_>>(),
+            [('着', None)]
+        );
+    }
+
+    #[test]
+    fn test_invalid_json() {
+        for json in [r#"{"kangxi": {"⽅⽅": "方"}}"#, r#"{"radicals": {}}"#, "42"] {
+            assert!(matches!(
+                UserDictionary::from_json("bad", json.as_bytes()),
+                Err(Error::Dictionary { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_later_dictionaries_win() {
+        let first = UserDictionary::new("first").with_mapping(DictionaryStage::Kangxi, '⽅', 'A');
+        let second = UserDictionary::new("second").with_suppressed(DictionaryStage::Kangxi, '⽅');
+
+        let mut overrides = Overrides::default();
+        overrides.layer(&first, DictionaryStage::Kangxi);
+        overrides.layer(&second, DictionaryStage::Kangxi);
+
+        let entry = overrides.get('⽅').unwrap();
+        assert_eq!(entry.target, None);
+        assert_eq!(&*entry.dictionary, "second");
+    }
+}
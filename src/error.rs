@@ -0,0 +1,70 @@
+//! Error type for loading data and building normalizers
+
+use crate::data::DataFile;
+use std::fmt;
+
+/// Errors raised while loading mapping data or setting up a normalizer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// A data file does not exist at its source
+    DataNotFound { file: DataFile, location: String },
+    /// A data file exists but could not be read
+    Io {
+        file: DataFile,
+        location: String,
+        message: String,
+    },
+    /// A data file is not valid JSON
+    Parse { file: DataFile, message: String },
+    /// A data file is valid JSON but not a table of the expected shape
+    SchemaMismatch { file: DataFile, message: String },
+    /// The OpenCC library could not be initialized
+    OpenCCUnavailable(String),
+    /// A user dictionary could not be read or is not valid
+    Dictionary { name: String, message: String },
+}
+
+/// Result type of fallible operations in this crate
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    /// Data file the error is about, if any
+    pub fn file(&self) -> Option<DataFile> {
+        match self {
+            Error::DataNotFound { file, .. }
+            | Error::Io { file, .. }
+            | Error::Parse { file, .. }
+            | Error::SchemaMismatch { file, .. } => Some(*file),
+            Error::OpenCCUnavailable(_) | Error::Dictionary { .. } => None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::DataNotFound { file, location } => {
+                write!(f, "{:?} data not found in {}", file, location)
+            }
+            Error::Io {
+                location, message, ..
+            } => {
+                write!(f, "cannot read {}: {}", location, message)
+            }
+            Error::Parse { file, message } => {
+                write!(f, "{:?} data is not valid JSON: {}", file, message)
+            }
+            Error::SchemaMismatch { file, message } => {
+                write!(f, "{:?} data has an unexpected shape: {}", file, message)
+            }
+            Error::OpenCCUnavailable(message) => {
+                write!(f, "OpenCC is unavailable: {}", message)
+            }
+            Error::Dictionary { name, message } => {
+                write!(f, "user dictionary {} is invalid: {}", name, message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
@@ -6,25 +6,49 @@
 //! - Character variant normalization (via Unihan)
 //! - Compatibility form normalization
 //! - Unicode NFC normalization
+//! - Offset mapping between original and normalized text
+//! - Reconstruction of the original text from the change log
+//! - Protected spans (URLs, email addresses, code) left untouched
 
+pub mod alignment;
+pub mod analysis;
 pub mod constants;
+pub mod data;
+pub mod diagnostics;
+pub mod dictionary;
+pub mod error;
 pub mod normalizers;
+pub mod protection;
+pub mod reversible;
 pub mod types;
 pub mod utils;
 
+pub use alignment::{OffsetMap, OffsetUnit};
+pub use analysis::{FixedPointTable, TableAnalysis};
+pub use data::{DataSource, DataStore};
+pub use diagnostics::LoadReport;
+pub use dictionary::{DictionaryStage, UserDictionary};
+pub use error::Error;
+pub use normalizers::pipeline::{NormalizationPipeline, NormalizationStage};
+pub use normalizers::streaming_normalizer::StreamingNormalizer;
 pub use normalizers::text_normalizer::TextNormalizer;
-pub use types::{NormalizedText, Script};
+pub use protection::ProtectionRule;
+pub use reversible::{apply_inverse, InverseError};
+pub use types::{BatchStatistics, NormalizedText, Preset, Script, ScriptDetection};
+
+use std::sync::LazyLock;
+
+/// Default normalizer shared by the convenience functions
+static DEFAULT_NORMALIZER: LazyLock<TextNormalizer> = LazyLock::new(TextNormalizer::new);
 
 /// Normalize text with default configuration
 pub fn normalize(text: &str) -> NormalizedText {
-    let normalizer = TextNormalizer::new();
-    normalizer.normalize(text, None)
+    DEFAULT_NORMALIZER.normalize(text, None)
 }
 
 /// Normalize text with target script
 pub fn normalize_to_script(text: &str, target_script: Script) -> NormalizedText {
-    let normalizer = TextNormalizer::new();
-    normalizer.normalize(text, Some(target_script))
+    DEFAULT_NORMALIZER.normalize(text, Some(target_script))
 }
 
 #[cfg(test)]
@@ -0,0 +1,201 @@
+//! Parallel batch normalization
+
+use crate::normalizers::text_normalizer::TextNormalizer;
+use crate::types::{BatchStatistics, NormalizedText, Script};
+use std::thread;
+
+/// Number of texts an iterator batch pulls from its source at once
+pub const DEFAULT_BATCH_SIZE: usize = 4096;
+
+impl TextNormalizer {
+    /// Normalize many texts on all available cores, keeping input order
+    pub fn normalize_batch(
+        &self,
+        texts: &[&str],
+        target_script: Option<Script>,
+    ) -> Vec<NormalizedText> {
+        self.normalize_parallel(texts, &target_script)
+    }
+
+    /// Like [`normalize_batch`](Self::normalize_batch), also returning statistics for the batch
+    pub fn normalize_batch_with_statistics(
+        &self,
+        texts: &[&str],
+        target_script: Option<Script>,
+    ) -> (Vec<NormalizedText>, BatchStatistics) {
+        let results = self.normalize_batch(texts, target_script);
+        let statistics = BatchStatistics::from_results(&results);
+        (results, statistics)
+    }
+
+    /// Normalize a stream of texts in parallel batches, yielding results in input order
+    ///
+    /// Texts are pulled from `texts` [`DEFAULT_BATCH_SIZE`] at a time, so
+    /// arbitrarily long sources are processed in bounded memory.
+    pub fn normalize_iter<I>(
+        &self,
+        texts: I,
+        target_script: Option<Script>,
+    ) -> BatchIter<'_, I::IntoIter>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str> + Sync,
+    {
+        BatchIter {
+            normalizer: self,
+            texts: texts.into_iter(),
+            target_script,
+            batch_size: DEFAULT_BATCH_SIZE,
+            results: Vec::new().into_iter(),
+            statistics: BatchStatistics::default(),
+        }
+    }
+
+    /// Split `texts` into one contiguous part per core and normalize the parts concurrently
+    fn normalize_parallel<T>(
+        &self,
+        texts: &[T],
+        target_script: &Option<Script>,
+    ) -> Vec<NormalizedText>
+    where
+        T: AsRef<str> + Sync,
+    {
+        let threads = thread::available_parallelism()
+            .map_or(1, |n| n.get())
+            .min(texts.len());
+        if threads <= 1 {
+            return texts
+                .iter()
+                .map(|text| self.normalize(text.as_ref(), target_script.clone()))
+                .collect();
+        }
+
+        let part_len = texts.len().div_ceil(threads);
+        thread::scope(|scope| {
+            let handles: Vec<_> = texts
+                .chunks(part_len)
+                .map(|part| {
+                    scope.spawn(move || {
+                        part.iter()
+                            .map(|text| self.normalize(text.as_ref(), target_script.clone()))
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .flat_map(|handle| {
+                    handle
+                        .join()
+                        .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+                })
+                .collect()
+        })
+    }
+}
+
+/// Iterator returned by [`TextNormalizer::normalize_iter`]
+pub struct BatchIter<'a, I: Iterator> {
+    normalizer: &'a TextNormalizer,
+    texts: I,
+    target_script: Option<Script>,
+    batch_size: usize,
+    results: std::vec::IntoIter<NormalizedText>,
+    statistics: BatchStatistics,
+}
+
+impl<I> BatchIter<'_, I>
+where
+    I: Iterator,
+    I::Item: AsRef<str> + Sync,
+{
+    /// Set how many texts are pulled from the source and normalized together
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Statistics over the results yielded so far
+    pub fn statistics(&self) -> &BatchStatistics {
+        &self.statistics
+    }
+}
+
+impl<I> Iterator for BatchIter<'_, I>
+where
+    I: Iterator,
+    I::Item: AsRef<str> + Sync,
+{
+    type Item = NormalizedText;
+
+    fn next(&mut self) -> Option<NormalizedText> {
+        if self.results.as_slice().is_empty() {
+            let batch: Vec<I::Item> = self.texts.by_ref().take(self.batch_size).collect();
+            if batch.is_empty() {
+                return None;
+            }
+            self.results = self
+                .normalizer
+                .normalize_parallel(&batch, &self.target_script)
+                .into_iter();
+        }
+
+        let result = self.results.next()?;
+        self.statistics.record(&result);
+        Some(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::ChangeType;
+
+    const TEXTS: [&str; 5] = ["⽅⾯問題", "硏究敎育", "plain", "e\u{0301}", "這個藥"];
+
+    #[test]
+    fn test_batch_keeps_order() {
+        let normalizer = TextNormalizer::new();
+        let texts: Vec<&str> = TEXTS.iter().copied().cycle().take(101).collect();
+        let results = normalizer.normalize_batch(&texts, None);
+
+        assert_eq!(results.len(), texts.len());
+        for (text, result) in texts.iter().zip(&results) {
+            assert_eq!(result.original, *text);
+            assert_eq!(
+                result.normalized,
+                normalizer.normalize(text, None).normalized
+            );
+        }
+    }
+
+    #[test]
+    fn test_batch_statistics() {
+        let normalizer = TextNormalizer::new();
+        let (_, statistics) = normalizer.normalize_batch_with_statistics(&TEXTS, None);
+
+        assert_eq!(statistics.texts, 5);
+        assert_eq!(statistics.changed_texts, 3);
+        assert_eq!(statistics.changes_by_type[&ChangeType::KangxiRadical], 2);
+        assert_eq!(
+            statistics.changes_by_type[&ChangeType::UnicodeNormalization],
+            1
+        );
+        assert_eq!(
+            statistics.total_changes,
+            statistics.changes_by_type.values().sum::<usize>()
+        );
+    }
+
+    #[test]
+    fn test_iterator_in_small_batches() {
+        let normalizer = TextNormalizer::new();
+        let mut results = normalizer
+            .normalize_iter(TEXTS.iter().map(|text| text.to_string()), None)
+            .with_batch_size(2);
+        let normalized: Vec<String> = results.by_ref().map(|result| result.normalized).collect();
+
+        assert_eq!(normalized, ["方面問題", "硏究教育", "plain", "é", "這個藥"]);
+        assert_eq!(results.statistics().texts, 5);
+    }
+}
@@ -1,24 +1,48 @@
 //! Compatibility form normalization
 
+use crate::alignment::OffsetMap;
+use crate::data::{CharTable, DataFile, DataSource, DataStore};
+use crate::diagnostics::LoadReport;
+use crate::dictionary::{DictionaryStage, Overrides, UserDictionary};
+use crate::error::Result;
 use crate::types::{ChangeType, NormalizedText, TextChange};
-use serde_json;
-use std::collections::HashMap;
-use std::fs;
-use std::path::Path;
+use std::sync::Arc;
 
 /// Normalizer for compatibility forms
+#[derive(Clone)]
 pub struct CompatibilityNormalizer {
-    compatibility_map: HashMap<char, char>,
+    compatibility_map: Arc<CharTable>,
+    overrides: Arc<Overrides>,
 }
 
 impl CompatibilityNormalizer {
-    /// Create a new compatibility normalizer
+    /// Create a new compatibility normalizer backed by the shared data store
     pub fn new() -> Self {
+        Self::with_data(DataStore::shared())
+    }
+
+    /// Create a new compatibility normalizer with the tables of the given data store
+    pub fn with_data(store: &DataStore) -> Self {
         Self {
-            compatibility_map: Self::load_compatibility_mappings(),
+            compatibility_map: store.compatibility(),
+            overrides: Arc::default(),
         }
     }
 
+    /// Layer a user dictionary over the built-in table, builder style
+    pub fn with_dictionary(mut self, dictionary: &UserDictionary) -> Self {
+        Arc::make_mut(&mut self.overrides).layer(dictionary, DictionaryStage::Compatibility);
+        self
+    }
+
+    /// Every character the table and dictionaries map from or to
+    pub(crate) fn mapped_chars(&self) -> impl Iterator<Item = char> + '_ {
+        self.compatibility_map
+            .iter()
+            .flat_map(|(&source, &target)| [source, target])
+            .chain(self.overrides.chars())
+    }
+
     /// Normalize compatibility forms in the given text
     pub fn normalize(&self, text: &str) -> NormalizedText {
         let chars: Vec<char> = text.chars().collect();
@@ -26,21 +50,32 @@ impl CompatibilityNormalizer {
         let mut changes = Vec::new();
 
         for (pos, &ch) in chars.iter().enumerate() {
-            if let Some(&normalized) = self.compatibility_map.get(&ch) {
-                result.push(normalized);
-                changes.push(TextChange {
-                    position: pos,
-                    original_char: ch,
-                    normalized_char: normalized,
-                    change_type: ChangeType::CompatibilityForm,
-                    reason: format!("Compatibility form {} → standard {}", ch, normalized),
-                });
-            } else {
-                result.push(ch);
+            let entry = self.overrides.get(ch);
+            let mapped = match entry {
+                Some(entry) => entry.target,
+                None => self.compatibility_map.get(&ch).copied(),
+            };
+            match mapped {
+                Some(normalized) if normalized != ch => {
+                    result.push(normalized);
+                    let reason = format!("Compatibility form {} → standard {}", ch, normalized);
+                    changes.push(TextChange::new(
+                        pos,
+                        ch,
+                        normalized,
+                        ChangeType::CompatibilityForm,
+                        match entry {
+                            Some(entry) => entry.reason(reason),
+                            None => reason,
+                        },
+                    ));
+                }
+                _ => result.push(ch),
             }
         }
 
         NormalizedText {
+            offset_map: OffsetMap::one_to_one(text, &result),
             original: text.to_string(),
             normalized: result,
             changes,
@@ -50,34 +85,14 @@ impl CompatibilityNormalizer {
     }
 
     /// Load compatibility mappings from the new clean normalization structure
-    fn load_compatibility_mappings() -> HashMap<char, char> {
-        let mut compatibility_map = HashMap::new();
-
-        // Load from the new normalization structure
-        let compatibility_path =
-            Path::new("data/processed/normalization/compatibility_variants.json");
-
-        if let Ok(contents) = fs::read_to_string(compatibility_path) {
-            if let Ok(mappings) = serde_json::from_str::<HashMap<String, String>>(&contents) {
-                for (compatibility, standard) in mappings {
-                    if let (Some(compatibility_char), Some(standard_char)) =
-                        (compatibility.chars().next(), standard.chars().next())
-                    {
-                        compatibility_map.insert(compatibility_char, standard_char);
-                    }
-                }
-                println!(
-                    "Loaded {} compatibility variant mappings from clean data",
-                    compatibility_map.len()
-                );
-            }
-        } else {
-            eprintln!(
-                "Warning: Failed to load compatibility mappings from clean normalization data"
-            );
-        }
-
-        compatibility_map
+    pub(crate) fn load_compatibility_mappings(
+        source: &DataSource,
+        report: &mut LoadReport,
+    ) -> Result<CharTable> {
+        Ok(source
+            .load_char_pairs(DataFile::CompatibilityVariants, report)?
+            .into_iter()
+            .collect())
     }
 }
 
@@ -0,0 +1,226 @@
+//! Hanja → Hangul transliteration for Korean text
+//!
+//! Each hanja is replaced by its Sino-Korean reading from Unihan (kHangul,
+//! else kKorean). A run of hanja is read as one word: its first syllable
+//! follows the initial-sound rule (李 → 이, 老 → 노), and 렬/률 inside the
+//! word become 열/율 after a vowel or ㄴ (比率 → 비율). A run directly after
+//! a Hangul syllable continues that word.
+
+use crate::alignment::OffsetMap;
+use crate::data::{CharTable, DataFile, DataSource, DataStore};
+use crate::diagnostics::LoadReport;
+use crate::error::{Error, Result};
+use crate::types::{ChangeType, HanjaTransliteration, NormalizedText, Script, TextChange};
+use crate::utils::hangul;
+use std::sync::Arc;
+
+/// Transliterator from hanja to Hangul
+#[derive(Clone)]
+pub struct HanjaTransliterator {
+    hangul_map: Arc<CharTable>,
+    mode: HanjaTransliteration,
+}
+
+impl HanjaTransliterator {
+    /// Create a transliterator that replaces hanja with Hangul, backed by the shared data store
+    pub fn new() -> Self {
+        Self::with_data(DataStore::shared())
+    }
+
+    /// Create a transliterator with the tables of the given data store
+    pub fn with_data(store: &DataStore) -> Self {
+        Self {
+            hangul_map: store.hangul_readings(),
+            mode: HanjaTransliteration::Hangul,
+        }
+    }
+
+    /// Set whether hanja are kept in parentheses after their reading, builder style
+    pub fn with_mode(mut self, mode: HanjaTransliteration) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Transliteration mode in use
+    pub fn mode(&self) -> HanjaTransliteration {
+        self.mode
+    }
+
+    /// Replace every hanja that has a Hangul reading
+    pub fn normalize(&self, text: &str) -> NormalizedText {
+        let chars: Vec<char> = text.chars().collect();
+        let mut result = String::with_capacity(text.len());
+        let mut changes = Vec::new();
+        let mut lengths = Vec::with_capacity(chars.len());
+
+        let mut pos = 0;
+        while pos < chars.len() {
+            let run_len = match self.mode {
+                HanjaTransliteration::None => 0,
+                _ => chars[pos..]
+                    .iter()
+                    .take_while(|ch| self.hangul_map.contains_key(ch))
+                    .count(),
+            };
+            if run_len == 0 {
+                result.push(chars[pos]);
+                lengths.push((1, 1));
+                pos += 1;
+                continue;
+            }
+
+            let run = &chars[pos..pos + run_len];
+            let mut previous = pos
+                .checked_sub(1)
+                .map(|before| chars[before])
+                .filter(|&before| hangul::is_syllable(before));
+            let mut run_changes = Vec::with_capacity(run_len);
+            for (offset, &ch) in run.iter().enumerate() {
+                let reading = self.hangul_map[&ch];
+                let syllable = match previous {
+                    Some(previous) => hangul::word_medial(previous, reading),
+                    None => hangul::word_initial(reading),
+                };
+                let reason = if syllable == reading {
+                    format!("Hanja {} → Hangul {}", ch, syllable)
+                } else {
+                    format!(
+                        "Hanja {} → Hangul {} ({} by the initial-sound rule)",
+                        ch, syllable, reading
+                    )
+                };
+                run_changes.push(TextChange::new(
+                    pos + offset,
+                    ch,
+                    syllable,
+                    ChangeType::HanjaToHangul,
+                    reason,
+                ));
+                result.push(syllable);
+                lengths.push((1, 1));
+                previous = Some(syllable);
+            }
+
+            if self.mode == HanjaTransliteration::HangulWithHanja {
+                // The annotation belongs to the last change so that it can be undone
+                let annotation = format!("({})", run.iter().collect::<String>());
+                if let (Some(last), Some(length)) = (run_changes.last_mut(), lengths.last_mut()) {
+                    last.normalized_text.push_str(&annotation);
+                    length.1 += annotation.chars().count();
+                }
+                result.push_str(&annotation);
+            }
+            changes.append(&mut run_changes);
+            pos += run_len;
+        }
+
+        NormalizedText {
+            offset_map: OffsetMap::from_lengths(text, &result, lengths),
+            original: text.to_string(),
+            normalized: result,
+            changes,
+            detected_script: Script::Auto,
+            processing_time_ms: 0,
+        }
+    }
+
+    /// Load the hanja → Hangul reading table, keeping the first reading of each hanja
+    pub(crate) fn load_hangul_readings(
+        source: &DataSource,
+        report: &mut LoadReport,
+    ) -> Result<CharTable> {
+        let mut table = CharTable::new();
+        for (hanja, reading) in source.load_char_pairs(DataFile::HangulReadings, report)? {
+            table.entry(hanja).or_insert(reading);
+        }
+        if table.is_empty() {
+            return Err(Error::SchemaMismatch {
+                file: DataFile::HangulReadings,
+                message: "the table has no readings".to_string(),
+            });
+        }
+        Ok(table)
+    }
+}
+
+impl Default for HanjaTransliterator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alignment::OffsetUnit;
+    use crate::reversible::apply_inverse;
+
+    fn transliterator(mode: HanjaTransliteration) -> HanjaTransliterator {
+        let readings = r#"{"大": "대", "韓": "한", "民": "민", "國": "국", "李": "리",
+            "比": "비", "率": "률", "法": "법", "女": "녀", "子": "자"}"#;
+        let store = DataStore::new(DataSource::memory([(
+            DataFile::HangulReadings,
+            readings.as_bytes().to_vec(),
+        )]));
+        HanjaTransliterator::with_data(&store).with_mode(mode)
+    }
+
+    #[test]
+    fn test_hanja_to_hangul() {
+        let result = transliterator(HanjaTransliteration::Hangul).normalize("大韓民國의 李氏");
+
+        // 氏 has no reading in the test table and is left alone
+        assert_eq!(result.normalized, "대한민국의 이氏");
+        assert_eq!(result.changes.len(), 5);
+        assert_eq!(result.changes[4].position, 6);
+        assert_eq!(result.changes[4].change_type, ChangeType::HanjaToHangul);
+        assert!(result.changes[4].reason.contains("initial-sound rule"));
+    }
+
+    #[test]
+    fn test_initial_sound_rule_within_words() {
+        let transliterator = transliterator(HanjaTransliteration::Hangul);
+
+        assert_eq!(
+            transliterator.normalize("比率 法率").normalized,
+            "비율 법률"
+        );
+        assert_eq!(transliterator.normalize("女子").normalized, "여자");
+        // After Hangul the hanja continues the word
+        assert_eq!(transliterator.normalize("한國").normalized, "한국");
+    }
+
+    #[test]
+    fn test_hanja_kept_in_parentheses() {
+        let original = "大韓民國 法";
+        let result = transliterator(HanjaTransliteration::HangulWithHanja).normalize(original);
+
+        assert_eq!(result.normalized, "대한민국(大韓民國) 법(法)");
+        assert_eq!(result.changes.len(), 5);
+        assert_eq!(result.changes[3].normalized_text, "국(大韓民國)");
+        assert_eq!(
+            apply_inverse(&result.normalized, &result.changes).unwrap(),
+            original
+        );
+
+        let span = result.offset_map.span_to_original(11..12, OffsetUnit::Char);
+        assert_eq!(span, Some(5..6));
+    }
+
+    #[test]
+    fn test_empty_table_fails_to_load() {
+        let store = DataStore::new(DataSource::memory([(
+            DataFile::HangulReadings,
+            b"{}".to_vec(),
+        )]));
+
+        assert!(matches!(
+            store.try_hangul_readings(),
+            Err(Error::SchemaMismatch {
+                file: DataFile::HangulReadings,
+                ..
+            })
+        ));
+        assert_eq!(store.load_report().fallbacks().count(), 1);
+    }
+}
@@ -1,24 +1,48 @@
 //! Kangxi radical normalization
 
+use crate::alignment::OffsetMap;
+use crate::data::{CharTable, DataFile, DataSource, DataStore};
+use crate::diagnostics::LoadReport;
+use crate::dictionary::{DictionaryStage, Overrides, UserDictionary};
+use crate::error::Result;
 use crate::types::{ChangeType, NormalizedText, TextChange};
-use serde_json;
-use std::collections::HashMap;
-use std::fs;
-use std::path::Path;
+use std::sync::Arc;
 
 /// Normalizer for Kangxi radicals
+#[derive(Clone)]
 pub struct KangxiNormalizer {
-    kangxi_map: HashMap<char, char>,
+    kangxi_map: Arc<CharTable>,
+    overrides: Arc<Overrides>,
 }
 
 impl KangxiNormalizer {
-    /// Create a new Kangxi normalizer
+    /// Create a new Kangxi normalizer backed by the shared data store
     pub fn new() -> Self {
+        Self::with_data(DataStore::shared())
+    }
+
+    /// Create a new Kangxi normalizer with the tables of the given data store
+    pub fn with_data(store: &DataStore) -> Self {
         Self {
-            kangxi_map: Self::load_kangxi_mappings(),
+            kangxi_map: store.kangxi(),
+            overrides: Arc::default(),
         }
     }
 
+    /// Layer a user dictionary over the built-in table, builder style
+    pub fn with_dictionary(mut self, dictionary: &UserDictionary) -> Self {
+        Arc::make_mut(&mut self.overrides).layer(dictionary, DictionaryStage::Kangxi);
+        self
+    }
+
+    /// Every character the table and dictionaries map from or to
+    pub(crate) fn mapped_chars(&self) -> impl Iterator<Item = char> + '_ {
+        self.kangxi_map
+            .iter()
+            .flat_map(|(&source, &target)| [source, target])
+            .chain(self.overrides.chars())
+    }
+
     /// Normalize Kangxi radicals in the given text
     pub fn normalize(&self, text: &str) -> NormalizedText {
         let chars: Vec<char> = text.chars().collect();
@@ -26,21 +50,33 @@ impl KangxiNormalizer {
         let mut changes = Vec::new();
 
         for (pos, &ch) in chars.iter().enumerate() {
-            if let Some(&normalized) = self.kangxi_map.get(&ch) {
-                result.push(normalized);
-                changes.push(TextChange {
-                    position: pos,
-                    original_char: ch,
-                    normalized_char: normalized,
-                    change_type: ChangeType::KangxiRadical,
-                    reason: format!("Kangxi radical {} → standard character {}", ch, normalized),
-                });
-            } else {
-                result.push(ch);
+            let entry = self.overrides.get(ch);
+            let mapped = match entry {
+                Some(entry) => entry.target,
+                None => self.kangxi_map.get(&ch).copied(),
+            };
+            match mapped {
+                Some(normalized) if normalized != ch => {
+                    result.push(normalized);
+                    let reason =
+                        format!("Kangxi radical {} → standard character {}", ch, normalized);
+                    changes.push(TextChange::new(
+                        pos,
+                        ch,
+                        normalized,
+                        ChangeType::KangxiRadical,
+                        match entry {
+                            Some(entry) => entry.reason(reason),
+                            None => reason,
+                        },
+                    ));
+                }
+                _ => result.push(ch),
             }
         }
 
         NormalizedText {
+            offset_map: OffsetMap::one_to_one(text, &result),
             original: text.to_string(),
             normalized: result,
             changes,
@@ -50,31 +86,14 @@ impl KangxiNormalizer {
     }
 
     /// Load Kangxi mappings from the new clean normalization structure
-    fn load_kangxi_mappings() -> HashMap<char, char> {
-        let mut kangxi_map = HashMap::new();
-
-        // Load from the new normalization structure
-        let kangxi_path = Path::new("data/processed/normalization/kangxi_radicals.json");
-
-        if let Ok(contents) = fs::read_to_string(kangxi_path) {
-            if let Ok(mappings) = serde_json::from_str::<HashMap<String, String>>(&contents) {
-                for (kangxi, standard) in mappings {
-                    if let (Some(kangxi_char), Some(standard_char)) =
-                        (kangxi.chars().next(), standard.chars().next())
-                    {
-                        kangxi_map.insert(kangxi_char, standard_char);
-                    }
-                }
-                println!(
-                    "Loaded {} Kangxi radical mappings from clean data",
-                    kangxi_map.len()
-                );
-            }
-        } else {
-            eprintln!("Warning: Failed to load Kangxi mappings from clean normalization data");
-        }
-
-        kangxi_map
+    pub(crate) fn load_kangxi_mappings(
+        source: &DataSource,
+        report: &mut LoadReport,
+    ) -> Result<CharTable> {
+        Ok(source
+            .load_char_pairs(DataFile::KangxiRadicals, report)?
+            .into_iter()
+            .collect())
     }
 }
 
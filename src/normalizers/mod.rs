@@ -1,17 +1,26 @@
 //! Text normalization components
 
+pub mod batch;
 pub mod compatibility_normalizer;
+pub mod hanja_transliterator;
 pub mod kangxi_normalizer;
+pub mod pipeline;
+pub(crate) mod regional;
 pub mod script_converter;
 pub mod script_detector;
+pub mod streaming_normalizer;
 pub mod text_normalizer;
 pub mod unicode_normalizer;
 pub mod variant_normalizer;
 
+pub use batch::BatchIter;
 pub use compatibility_normalizer::CompatibilityNormalizer;
+pub use hanja_transliterator::HanjaTransliterator;
 pub use kangxi_normalizer::KangxiNormalizer;
+pub use pipeline::{NormalizationPipeline, NormalizationStage, ScriptConversionStage};
 pub use script_converter::ScriptConverter;
 pub use script_detector::ScriptDetector;
+pub use streaming_normalizer::{StreamSummary, StreamingNormalizer};
 pub use text_normalizer::TextNormalizer;
 pub use unicode_normalizer::UnicodeNormalizer;
 pub use variant_normalizer::VariantNormalizer;
@@ -0,0 +1,372 @@
+//! Pluggable normalization stages and user-ordered pipelines
+
+use crate::alignment::OffsetMap;
+use crate::dictionary::UserDictionary;
+use crate::normalizers::{
+    compatibility_normalizer::CompatibilityNormalizer, hanja_transliterator::HanjaTransliterator,
+    kangxi_normalizer::KangxiNormalizer, script_converter::ScriptConverter,
+    script_detector::ScriptDetector, unicode_normalizer::UnicodeNormalizer,
+    variant_normalizer::VariantNormalizer,
+};
+use crate::types::{
+    HanjaTransliteration, NormalizationConfig, NormalizedText, Script, TextChange,
+    UnicodeNormalization,
+};
+use std::time::Instant;
+
+/// A single normalization step that can be placed in a [`NormalizationPipeline`]
+///
+/// Implementations receive the output of the previous stage and report every
+/// change with a position relative to the text they were given. Stages must be
+/// `Send + Sync` so that a pipeline can be shared between threads.
+pub trait NormalizationStage: Send + Sync {
+    /// Short name identifying the stage (e.g. "kangxi")
+    fn name(&self) -> &str;
+
+    /// Normalize the given text
+    fn normalize(&self, text: &str) -> NormalizedText;
+}
+
+impl NormalizationStage for UnicodeNormalizer {
+    fn name(&self) -> &str {
+        "unicode"
+    }
+
+    fn normalize(&self, text: &str) -> NormalizedText {
+        UnicodeNormalizer::normalize(self, text, self.form())
+    }
+}
+
+impl NormalizationStage for KangxiNormalizer {
+    fn name(&self) -> &str {
+        "kangxi"
+    }
+
+    fn normalize(&self, text: &str) -> NormalizedText {
+        KangxiNormalizer::normalize(self, text)
+    }
+}
+
+impl NormalizationStage for VariantNormalizer {
+    fn name(&self) -> &str {
+        "variant"
+    }
+
+    fn normalize(&self, text: &str) -> NormalizedText {
+        VariantNormalizer::normalize(self, text)
+    }
+}
+
+impl NormalizationStage for CompatibilityNormalizer {
+    fn name(&self) -> &str {
+        "compatibility"
+    }
+
+    fn normalize(&self, text: &str) -> NormalizedText {
+        CompatibilityNormalizer::normalize(self, text)
+    }
+}
+
+impl NormalizationStage for HanjaTransliterator {
+    fn name(&self) -> &str {
+        "hanja"
+    }
+
+    fn normalize(&self, text: &str) -> NormalizedText {
+        HanjaTransliterator::normalize(self, text)
+    }
+}
+
+/// Stage that converts text to a fixed target script
+///
+/// The source script is detected on the text this stage receives; in mixed
+/// Chinese text each sentence is converted from its own script.
+#[derive(Clone)]
+pub struct ScriptConversionStage {
+    target_script: Script,
+    script_detector: ScriptDetector,
+    script_converter: ScriptConverter,
+}
+
+impl ScriptConversionStage {
+    /// Create a script conversion stage for the given target script
+    pub fn new(target_script: Script) -> Self {
+        Self {
+            target_script,
+            script_detector: ScriptDetector::new(),
+            script_converter: ScriptConverter::new(),
+        }
+    }
+
+    /// Layer a user dictionary over the conversion tables, builder style
+    pub fn with_dictionary(mut self, dictionary: &UserDictionary) -> Self {
+        self.script_converter = self.script_converter.with_dictionary(dictionary);
+        self
+    }
+
+    /// Convert Simplified vocabulary to Taiwan usage for a Taiwan target, builder style
+    pub fn with_taiwan_vocabulary(mut self, enabled: bool) -> Self {
+        self.script_converter = self.script_converter.with_taiwan_vocabulary(enabled);
+        self
+    }
+}
+
+impl NormalizationStage for ScriptConversionStage {
+    fn name(&self) -> &str {
+        "script"
+    }
+
+    fn normalize(&self, text: &str) -> NormalizedText {
+        let detected_script = self.script_detector.detect(text);
+        let (normalized, changes, offset_map) =
+            self.script_converter
+                .convert_aligned(text, &self.target_script, &detected_script);
+
+        NormalizedText {
+            offset_map,
+            original: text.to_string(),
+            normalized,
+            changes,
+            detected_script,
+            processing_time_ms: 0,
+        }
+    }
+}
+
+/// Ordered sequence of normalization stages
+///
+/// Stages run in the order they were given; each one sees the output of the
+/// previous stage. Use this instead of [`TextNormalizer`](crate::TextNormalizer)
+/// to reorder the built-in stages or insert custom ones.
+pub struct NormalizationPipeline {
+    stages: Vec<Box<dyn NormalizationStage>>,
+    script_detector: ScriptDetector,
+}
+
+impl NormalizationPipeline {
+    /// Create a pipeline from an ordered list of stages
+    pub fn new(stages: Vec<Box<dyn NormalizationStage>>) -> Self {
+        Self {
+            stages,
+            script_detector: ScriptDetector::new(),
+        }
+    }
+
+    /// Create a pipeline with the built-in stages enabled by the configuration,
+    /// in the same order `TextNormalizer` runs them
+    pub fn from_config(config: &NormalizationConfig) -> Self {
+        let mut stages: Vec<Box<dyn NormalizationStage>> = Vec::new();
+        if config.unicode_normalization != UnicodeNormalization::None {
+            stages.push(Box::new(UnicodeNormalizer::with_form(
+                config.unicode_normalization.clone(),
+            )));
+        }
+        if config.normalize_kangxi_radicals {
+            stages.push(Box::new(KangxiNormalizer::new()));
+        }
+        if config.normalize_variants {
+            stages.push(Box::new(
+                VariantNormalizer::new().with_spoofing_variants(config.normalize_spoofing_variants),
+            ));
+        }
+        if config.normalize_compatibility {
+            stages.push(Box::new(CompatibilityNormalizer::new()));
+        }
+        if config.target_script != Script::Auto {
+            stages.push(Box::new(
+                ScriptConversionStage::new(config.target_script.clone())
+                    .with_taiwan_vocabulary(config.taiwan_vocabulary),
+            ));
+        }
+        if config.hanja_transliteration != HanjaTransliteration::None {
+            stages.push(Box::new(
+                HanjaTransliterator::new().with_mode(config.hanja_transliteration),
+            ));
+        }
+        Self::new(stages)
+    }
+
+    /// Append a stage to the end of the pipeline
+    pub fn push(&mut self, stage: Box<dyn NormalizationStage>) {
+        self.stages.push(stage);
+    }
+
+    /// Insert a stage at the given position
+    pub fn insert(&mut self, index: usize, stage: Box<dyn NormalizationStage>) {
+        self.stages.insert(index, stage);
+    }
+
+    /// Append a stage, builder style
+    pub fn with_stage(mut self, stage: Box<dyn NormalizationStage>) -> Self {
+        self.push(stage);
+        self
+    }
+
+    /// Names of the stages in execution order
+    pub fn stage_names(&self) -> Vec<&str> {
+        self.stages.iter().map(|stage| stage.name()).collect()
+    }
+
+    /// Run every stage in order over the given text
+    pub fn normalize(&self, text: &str) -> NormalizedText {
+        let start_time = Instant::now();
+        let detected_script = self.script_detector.detect(text);
+
+        let stages: Vec<&dyn NormalizationStage> =
+            self.stages.iter().map(|stage| stage.as_ref()).collect();
+        let (normalized, changes, offset_map) = run_stages(&stages, text);
+
+        NormalizedText {
+            offset_map,
+            original: text.to_string(),
+            normalized,
+            changes,
+            detected_script,
+            processing_time_ms: start_time.elapsed().as_millis() as u64,
+        }
+    }
+}
+
+/// Feed the text through each stage in turn, collecting all changes and
+/// chaining the stage alignments into one offset map
+pub(crate) fn run_stages(
+    stages: &[&dyn NormalizationStage],
+    text: &str,
+) -> (String, Vec<TextChange>, OffsetMap) {
+    let mut current = text.to_string();
+    let mut all_changes = Vec::new();
+    let mut offset_map = OffsetMap::identity(text);
+
+    for (index, stage) in stages.iter().enumerate() {
+        let result = stage.normalize(&current);
+        let stage_map = stage_offset_map(&current, result.offset_map, &result.normalized);
+        offset_map = offset_map.compose(&stage_map);
+        current = result.normalized;
+        all_changes.extend(result.changes.into_iter().map(|mut change| {
+            change.stage = index;
+            change
+        }));
+    }
+
+    (current, all_changes, offset_map)
+}
+
+/// Use the stage's own alignment if it fits, otherwise a best-effort guess
+/// (custom stages are not required to fill in `offset_map`)
+pub(crate) fn stage_offset_map(input: &str, offset_map: OffsetMap, output: &str) -> OffsetMap {
+    if offset_map.covers(input.chars().count(), output.chars().count()) {
+        offset_map
+    } else {
+        OffsetMap::one_to_one(input, output)
+    }
+}
+
+/// Record how many chars each stage received for a chunk of a longer text,
+/// returning the offsets at which the chunk starts in every stage's input
+pub(crate) fn advance_stage_offsets(
+    stage_offsets: &mut [usize],
+    chunk: &str,
+    changes: &[TextChange],
+) -> Vec<usize> {
+    let starts = stage_offsets.to_vec();
+    let mut input_len = chunk.chars().count() as isize;
+    for (stage, offset) in stage_offsets.iter_mut().enumerate() {
+        *offset += input_len as usize;
+        input_len += changes
+            .iter()
+            .filter(|change| change.stage == stage)
+            .map(|change| {
+                change.normalized_text.chars().count() as isize
+                    - change.original_text.chars().count() as isize
+            })
+            .sum::<isize>();
+    }
+    starts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::ChangeType;
+
+    /// Replaces a fixed character, standing in for a company glossary
+    struct GlossaryStage;
+
+    impl NormalizationStage for GlossaryStage {
+        fn name(&self) -> &str {
+            "glossary"
+        }
+
+        fn normalize(&self, text: &str) -> NormalizedText {
+            let mut changes = Vec::new();
+            let normalized: String = text
+                .chars()
+                .enumerate()
+                .map(|(pos, ch)| {
+                    if ch == '方' {
+                        changes.push(TextChange::new(
+                            pos,
+                            ch,
+                            '芳',
+                            ChangeType::Custom("glossary".to_string()),
+                            "Glossary entry 方 → 芳".to_string(),
+                        ));
+                        '芳'
+                    } else {
+                        ch
+                    }
+                })
+                .collect();
+
+            NormalizedText {
+                original: text.to_string(),
+                normalized,
+                changes,
+                detected_script: Script::Auto,
+                processing_time_ms: 0,
+                offset_map: OffsetMap::default(),
+            }
+        }
+    }
+
+    #[test]
+    fn test_from_config_matches_default_order() {
+        let pipeline = NormalizationPipeline::from_config(&NormalizationConfig::default());
+
+        assert_eq!(
+            pipeline.stage_names(),
+            vec!["unicode", "kangxi", "variant", "compatibility"]
+        );
+        assert_eq!(pipeline.normalize("⽅⾯問題").normalized, "方面問題");
+    }
+
+    #[test]
+    fn test_custom_stage_order() {
+        // The glossary only sees 方 if Kangxi normalization has already run
+        let before = NormalizationPipeline::new(vec![
+            Box::new(GlossaryStage),
+            Box::new(KangxiNormalizer::new()),
+        ]);
+        assert_eq!(before.normalize("⽅面").normalized, "方面");
+
+        let after = NormalizationPipeline::new(vec![
+            Box::new(KangxiNormalizer::new()),
+            Box::new(GlossaryStage),
+        ]);
+        let result = after.normalize("⽅面");
+        assert_eq!(result.normalized, "芳面");
+        assert_eq!(result.changes.len(), 2);
+        assert_eq!(
+            result.changes[1].change_type,
+            ChangeType::Custom("glossary".to_string())
+        );
+    }
+
+    #[test]
+    fn test_insert_stage() {
+        let mut pipeline = NormalizationPipeline::new(vec![Box::new(GlossaryStage)]);
+        pipeline.insert(0, Box::new(KangxiNormalizer::new()));
+
+        assert_eq!(pipeline.stage_names(), vec!["kangxi", "glossary"]);
+    }
+}
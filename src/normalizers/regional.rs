@@ -0,0 +1,477 @@
+//! Regional glyph standards for Traditional Chinese and Japanese Shinjitai
+//!
+//! Script conversion first produces OpenCC-standard Traditional characters;
+//! a regional target then rewrites them to the forms used in Taiwan, Hong Kong
+//! or Japan (e.g. 裏 → 裡 in Taiwan, 衛 → 衞 in Hong Kong, 國 → 国 in Japan).
+//! With OpenCC the matching configs (`s2tw`, `s2hk`, `t2jp`, ...) are used;
+//! otherwise the built-in tables below. The Shinjitai table also serves
+//! Japanese → Chinese conversion in reverse (国 → 國, 広 → 廣).
+
+use crate::types::Script;
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+/// Standard Traditional → Taiwan (OpenCC `TWVariants`)
+const TAIWAN_VARIANTS: &[(char, char)] = &[
+    ('僞', '偽'),
+    ('啓', '啟'),
+    ('喫', '吃'),
+    ('嫺', '嫻'),
+    ('嬀', '媯'),
+    ('峯', '峰'),
+    ('擡', '抬'),
+    ('棱', '稜'),
+    ('檐', '簷'),
+    ('污', '汙'),
+    ('泄', '洩'),
+    ('潙', '溈'),
+    ('爲', '為'),
+    ('牀', '床'),
+    ('痹', '痺'),
+    ('癡', '痴'),
+    ('皁', '皂'),
+    ('着', '著'),
+    ('睾', '睪'),
+    ('祕', '秘'),
+    ('竈', '灶'),
+    ('糉', '粽'),
+    ('繮', '韁'),
+    ('纔', '才'),
+    ('羣', '群'),
+    ('脣', '唇'),
+    ('衆', '眾'),
+    ('衞', '衛'),
+    ('裏', '裡'),
+    ('覈', '核'),
+    ('鉢', '缽'),
+    ('鍼', '針'),
+    ('鮎', '鯰'),
+    ('麪', '麵'),
+    ('齶', '顎'),
+];
+
+/// Standard Traditional → Hong Kong (OpenCC `HKVariants`)
+const HONG_KONG_VARIANTS: &[(char, char)] = &[
+    ('僞', '偽'),
+    ('兌', '兑'),
+    ('媼', '媪'),
+    ('嬀', '媯'),
+    ('悅', '悦'),
+    ('戶', '户'),
+    ('搵', '揾'),
+    ('擡', '抬'),
+    ('敘', '敍'),
+    ('棱', '稜'),
+    ('溫', '温'),
+    ('潙', '溈'),
+    ('爲', '為'),
+    ('癡', '痴'),
+    ('皁', '皂'),
+    ('祕', '秘'),
+    ('稅', '税'),
+    ('竈', '灶'),
+    ('糉', '糭'),
+    ('線', '綫'),
+    ('纔', '才'),
+    ('脣', '唇'),
+    ('蔥', '葱'),
+    ('蘊', '藴'),
+    ('衆', '眾'),
+    ('衛', '衞'),
+    ('裡', '裏'),
+    ('說', '説'),
+    ('醞', '醖'),
+    ('鉢', '缽'),
+    ('銳', '鋭'),
+    ('閱', '閲'),
+    ('顏', '顔'),
+    ('鷄', '雞'),
+];
+
+/// Traditional (Kyūjitai) → Japanese Shinjitai (OpenCC `JPVariants`)
+const SHINJITAI_VARIANTS: &[(char, char)] = &[
+    ('亞', '亜'),
+    ('惡', '悪'),
+    ('壓', '圧'),
+    ('圍', '囲'),
+    ('醫', '医'),
+    ('爲', '為'),
+    ('壹', '壱'),
+    ('隱', '隠'),
+    ('榮', '栄'),
+    ('營', '営'),
+    ('衞', '衛'),
+    ('驛', '駅'),
+    ('圓', '円'),
+    ('鹽', '塩'),
+    ('奧', '奥'),
+    ('應', '応'),
+    ('歐', '欧'),
+    ('櫻', '桜'),
+    ('假', '仮'),
+    ('價', '価'),
+    ('畫', '画'),
+    ('會', '会'),
+    ('學', '学'),
+    ('樂', '楽'),
+    ('氣', '気'),
+    ('國', '国'),
+    ('黑', '黒'),
+    ('實', '実'),
+    ('寫', '写'),
+    ('澤', '沢'),
+    ('壽', '寿'),
+    ('收', '収'),
+    ('從', '従'),
+    ('處', '処'),
+    ('將', '将'),
+    ('燒', '焼'),
+    ('條', '条'),
+    ('狀', '状'),
+    ('乘', '乗'),
+    ('淨', '浄'),
+    ('眞', '真'),
+    ('圖', '図'),
+    ('數', '数'),
+    ('聲', '声'),
+    ('專', '専'),
+    ('戰', '戦'),
+    ('錢', '銭'),
+    ('禪', '禅'),
+    ('雙', '双'),
+    ('總', '総'),
+    ('藏', '蔵'),
+    ('屬', '属'),
+    ('對', '対'),
+    ('臺', '台'),
+    ('團', '団'),
+    ('斷', '断'),
+    ('單', '単'),
+    ('遲', '遅'),
+    ('晝', '昼'),
+    ('鐵', '鉄'),
+    ('轉', '転'),
+    ('傳', '伝'),
+    ('當', '当'),
+    ('黨', '党'),
+    ('燈', '灯'),
+    ('讀', '読'),
+    ('獨', '独'),
+    ('發', '発'),
+    ('髮', '髪'),
+    ('拔', '抜'),
+    ('濱', '浜'),
+    ('佛', '仏'),
+    ('變', '変'),
+    ('邊', '辺'),
+    ('寶', '宝'),
+    ('豐', '豊'),
+    ('萬', '万'),
+    ('滿', '満'),
+    ('藥', '薬'),
+    ('譯', '訳'),
+    ('豫', '予'),
+    ('餘', '余'),
+    ('與', '与'),
+    ('樣', '様'),
+    ('來', '来'),
+    ('亂', '乱'),
+    ('覽', '覧'),
+    ('龍', '竜'),
+    ('兩', '両'),
+    ('綠', '緑'),
+    ('勞', '労'),
+    ('樓', '楼'),
+    ('灣', '湾'),
+    ('關', '関'),
+    ('觀', '観'),
+    ('歡', '歓'),
+    ('權', '権'),
+    ('驗', '験'),
+    ('險', '険'),
+    ('檢', '検'),
+    ('劍', '剣'),
+    ('經', '経'),
+    ('輕', '軽'),
+    ('廣', '広'),
+    ('擴', '拡'),
+    ('縣', '県'),
+    ('顯', '顕'),
+    ('齒', '歯'),
+    ('齡', '齢'),
+    ('兒', '児'),
+    ('辭', '辞'),
+    ('濕', '湿'),
+    ('稱', '称'),
+    ('證', '証'),
+    ('參', '参'),
+    ('賣', '売'),
+    ('續', '続'),
+    ('醉', '酔'),
+    ('靜', '静'),
+    ('爭', '争'),
+    ('淺', '浅'),
+    ('殘', '残'),
+    ('齊', '斉'),
+    ('濟', '済'),
+    ('舊', '旧'),
+    ('擧', '挙'),
+    ('區', '区'),
+    ('驅', '駆'),
+    ('戀', '恋'),
+    ('聽', '聴'),
+    ('廳', '庁'),
+    ('德', '徳'),
+    ('惠', '恵'),
+    ('稻', '稲'),
+    ('默', '黙'),
+    ('彈', '弾'),
+    ('戲', '戯'),
+    ('虛', '虚'),
+    ('蟲', '虫'),
+    ('觸', '触'),
+    ('獻', '献'),
+    ('嚴', '厳'),
+    ('卷', '巻'),
+    ('圈', '圏'),
+    ('勸', '勧'),
+    ('歸', '帰'),
+    ('僞', '偽'),
+    ('釋', '釈'),
+    ('擇', '択'),
+    ('廢', '廃'),
+    ('隨', '随'),
+    ('擔', '担'),
+    ('膽', '胆'),
+    ('壞', '壊'),
+    ('懷', '懐'),
+    ('繪', '絵'),
+    ('效', '効'),
+    ('曉', '暁'),
+    ('穩', '穏'),
+    ('峯', '峰'),
+    ('體', '体'),
+    ('藝', '芸'),
+    ('號', '号'),
+    ('齋', '斎'),
+    ('劑', '剤'),
+    ('雜', '雑'),
+    ('盜', '盗'),
+    ('拜', '拝'),
+    ('拂', '払'),
+    ('辯', '弁'),
+    ('辨', '弁'),
+    ('瓣', '弁'),
+    ('譽', '誉'),
+    ('搖', '揺'),
+    ('謠', '謡'),
+    ('獵', '猟'),
+    ('壘', '塁'),
+    ('鑄', '鋳'),
+    ('瀧', '滝'),
+    ('點', '点'),
+    ('鬪', '闘'),
+    ('惱', '悩'),
+    ('腦', '脳'),
+    ('麥', '麦'),
+    ('蠻', '蛮'),
+];
+
+/// Shinjitai forms that are also standard Traditional characters, so Japanese
+/// → Traditional keeps them (峰, not 峯)
+const SHARED_FORMS: &[char] = &['衛', '峰', '為', '真', '台'];
+
+/// Shinjitai forms Chinese also writes as characters of their own (给予, 武弁),
+/// so they are no sign of Japanese text
+const CHINESE_FORMS: &[char] = &['予', '弁'];
+
+/// Shinjitai forms standing mostly for Chinese characters other than their
+/// Kyūjitai forms: 予 abbreviates 豫 (猶予), but 予定 and 予約 are 預定 and 預約
+const CHINESE_EQUIVALENTS: &[(char, &[char])] = &[('予', &['預', '豫'])];
+
+/// Japanese-only characters (kokuji) with no Chinese equivalent, left unchanged
+const JAPANESE_ONLY: &[char] = &[
+    '働', '畑', '畠', '峠', '込', '枠', '匂', '凪', '凧', '辻', '榊', '躾', '噺', '鰯', '栃', '笹',
+    '颪', '裃', '鴫', '椛', '樫', '糀', '粁', '粍', '籾', '匁',
+];
+
+/// Simplified → Taiwan vocabulary (OpenCC `TWPhrases`)
+const TAIWAN_PHRASES: &[(&str, &str)] = &[
+    ("软件", "軟體"),
+    ("硬件", "硬體"),
+    ("内存", "記憶體"),
+    ("信息", "資訊"),
+    ("网络", "網路"),
+    ("互联网", "網際網路"),
+    ("程序", "程式"),
+    ("打印机", "印表機"),
+    ("鼠标", "滑鼠"),
+    ("硬盘", "硬碟"),
+    ("光盘", "光碟"),
+    ("数据库", "資料庫"),
+    ("服务器", "伺服器"),
+    ("操作系统", "作業系統"),
+    ("文件夹", "資料夾"),
+    ("默认", "預設"),
+    ("屏幕", "螢幕"),
+    ("菜单", "選單"),
+    ("接口", "介面"),
+    ("在线", "線上"),
+    ("数码", "數位"),
+    ("博客", "部落格"),
+    ("短信", "簡訊"),
+    ("激光", "雷射"),
+    ("出租车", "計程車"),
+    ("自行车", "腳踏車"),
+];
+
+static TAIWAN: LazyLock<HashMap<char, char>> =
+    LazyLock::new(|| TAIWAN_VARIANTS.iter().copied().collect());
+static HONG_KONG: LazyLock<HashMap<char, char>> =
+    LazyLock::new(|| HONG_KONG_VARIANTS.iter().copied().collect());
+static SHINJITAI: LazyLock<HashMap<char, char>> =
+    LazyLock::new(|| SHINJITAI_VARIANTS.iter().copied().collect());
+/// Shinjitai → Traditional candidates, in table order
+static KYUJITAI: LazyLock<HashMap<char, Vec<char>>> = LazyLock::new(|| {
+    let mut kyujitai: HashMap<char, Vec<char>> = HashMap::new();
+    for &(traditional, shinjitai) in SHINJITAI_VARIANTS {
+        if !SHARED_FORMS.contains(&shinjitai) {
+            kyujitai.entry(shinjitai).or_default().push(traditional);
+        }
+    }
+    kyujitai
+});
+static PHRASES: LazyLock<HashMap<&'static str, &'static str>> =
+    LazyLock::new(|| TAIWAN_PHRASES.iter().copied().collect());
+
+/// Regional standard a Traditional target follows
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Region {
+    Taiwan,
+    HongKong,
+    Japan,
+}
+
+impl Region {
+    /// Region of a target script, if it is a regional one (Japanese targets use Shinjitai)
+    pub fn of(script: &Script) -> Option<Self> {
+        match script {
+            Script::TraditionalTaiwan => Some(Region::Taiwan),
+            Script::TraditionalHongKong => Some(Region::HongKong),
+            Script::JapaneseShinjitai | Script::Japanese => Some(Region::Japan),
+            _ => None,
+        }
+    }
+
+    /// Name used in change reasons
+    pub fn name(self) -> &'static str {
+        match self {
+            Region::Taiwan => "Taiwan Traditional",
+            Region::HongKong => "Hong Kong Traditional",
+            Region::Japan => "Japanese Shinjitai",
+        }
+    }
+
+    /// Regional form of a standard Traditional character, if it differs
+    pub fn variant(self, ch: char) -> Option<char> {
+        let table = match self {
+            Region::Taiwan => &TAIWAN,
+            Region::HongKong => &HONG_KONG,
+            Region::Japan => &SHINJITAI,
+        };
+        table.get(&ch).copied()
+    }
+
+    /// Every character the regional table maps from or to
+    pub fn mapped_chars(self) -> impl Iterator<Item = char> {
+        let table = match self {
+            Region::Taiwan => TAIWAN_VARIANTS,
+            Region::HongKong => HONG_KONG_VARIANTS,
+            Region::Japan => SHINJITAI_VARIANTS,
+        };
+        table.iter().flat_map(|&(source, target)| [source, target])
+    }
+
+    /// OpenCC configs converting to this region, applied in order
+    ///
+    /// `from_simplified` selects the configs for Simplified input; otherwise the
+    /// input is standard Traditional.
+    pub fn opencc_configs(
+        self,
+        from_simplified: bool,
+        vocabulary: bool,
+    ) -> &'static [&'static str] {
+        match (self, from_simplified) {
+            (Region::Taiwan, true) if vocabulary => &["s2twp"],
+            (Region::Taiwan, true) => &["s2tw"],
+            (Region::Taiwan, false) => &["t2tw"],
+            (Region::HongKong, true) => &["s2hk"],
+            (Region::HongKong, false) => &["t2hk"],
+            (Region::Japan, true) => &["s2t", "t2jp"],
+            (Region::Japan, false) => &["t2jp"],
+        }
+    }
+}
+
+/// Traditional forms of a Japanese Shinjitai character, most common first; empty if it has none
+pub(crate) fn kyujitai(ch: char) -> &'static [char] {
+    KYUJITAI.get(&ch).map_or(&[], Vec::as_slice)
+}
+
+/// Chinese characters a Shinjitai form is written for, most common first; its
+/// Kyūjitai forms unless Chinese uses others
+pub(crate) fn chinese_equivalents(ch: char) -> &'static [char] {
+    CHINESE_EQUIVALENTS
+        .iter()
+        .find(|(shinjitai, _)| *shinjitai == ch)
+        .map_or_else(|| kyujitai(ch), |(_, chinese)| chinese)
+}
+
+/// Whether a Shinjitai form is also written as a Chinese character of its own
+pub(crate) fn is_chinese_form(ch: char) -> bool {
+    CHINESE_FORMS.contains(&ch)
+}
+
+/// Whether a character is a Japanese-only form (kokuji) with no Chinese equivalent
+pub(crate) fn is_japanese_only(ch: char) -> bool {
+    JAPANESE_ONLY.contains(&ch)
+}
+
+/// Taiwan form of a Simplified phrase, e.g. 软件 → 軟體
+pub(crate) fn taiwan_phrase(phrase: &str) -> Option<&'static str> {
+    PHRASES.get(phrase).copied()
+}
+
+/// Length in chars of the longest Taiwan vocabulary entry
+pub(crate) fn longest_taiwan_phrase() -> usize {
+    TAIWAN_PHRASES
+        .iter()
+        .map(|(phrase, _)| phrase.chars().count())
+        .max()
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_regional_glyph_standards() {
+        assert_eq!(Region::Taiwan.variant('裏'), Some('裡'));
+        assert_eq!(Region::Taiwan.variant('着'), Some('著'));
+        assert_eq!(Region::Taiwan.variant('峯'), Some('峰'));
+        assert_eq!(Region::HongKong.variant('裡'), Some('裏'));
+        assert_eq!(Region::HongKong.variant('着'), None);
+        assert_eq!(Region::Japan.variant('國'), Some('国'));
+        assert_eq!(Region::Japan.variant('峯'), Some('峰'));
+        assert_eq!(taiwan_phrase("内存"), Some("記憶體"));
+
+        assert_eq!(kyujitai('広'), ['廣']);
+        assert_eq!(kyujitai('弁'), ['辯', '辨', '瓣']);
+        assert_eq!(kyujitai('予'), ['豫']);
+        assert_eq!(chinese_equivalents('予'), ['預', '豫']);
+        assert_eq!(chinese_equivalents('弁'), ['辯', '辨', '瓣']);
+        assert!(kyujitai('峰').is_empty());
+        assert!(is_japanese_only('働'));
+    }
+}
@@ -1,242 +1,1018 @@
-//! Script conversion (Traditional ↔ Simplified Chinese)
+//! Script conversion (Traditional ↔ Simplified Chinese, and regional Traditional standards)
 
-use crate::types::{ChangeType, Script, ScriptMapping, TextChange};
+use crate::alignment::{OffsetMap, OffsetUnit};
+use crate::data::{DataFile, DataSource, DataStore, PhraseTable, ReadingTable, ScriptTable};
+use crate::diagnostics::LoadReport;
+use crate::dictionary::{DictionaryStage, Overrides, UserDictionary};
+use crate::error::Result;
+use crate::normalizers::regional::{self, Region};
+use crate::normalizers::script_detector::ScriptDetector;
+use crate::types::{
+    ChangeType, LossyConversion, Reading, RoundTripLoss, Script, ScriptMapping, TextChange,
+};
 use crate::utils::opencc_validator::OpenCCValidator;
-use serde_json;
 use std::collections::HashMap;
-use std::fs;
-use std::path::Path;
+use std::ops::Range;
+use std::sync::Arc;
+
+/// Characters that end a segment whose script is detected on its own
+pub(crate) const SEGMENT_ENDS: [char; 8] = ['。', '！', '？', '；', '!', '?', ';', '\n'];
 
 /// Converter for Traditional ↔ Simplified Chinese script conversion
+#[derive(Clone)]
 pub struct ScriptConverter {
-    traditional_to_simplified: HashMap<String, Vec<ScriptMapping>>,
-    simplified_to_traditional: HashMap<String, Vec<ScriptMapping>>,
-    opencc_validator: Option<OpenCCValidator>,
+    traditional_to_simplified: Arc<ScriptTable>,
+    simplified_to_traditional: Arc<ScriptTable>,
+    /// Tells which script a character or sentence is in
+    detector: ScriptDetector,
+    opencc_validator: Option<Arc<OpenCCValidator>>,
+    readings: Arc<ReadingTable>,
+    traditional_overrides: Arc<Overrides>,
+    simplified_overrides: Arc<Overrides>,
+    /// Length in chars of the longest key of each table
+    longest_traditional: usize,
+    longest_simplified: usize,
+    taiwan_vocabulary: bool,
+    lossy_conversion: LossyConversion,
 }
 
 impl ScriptConverter {
-    /// Create a new script converter
+    /// Create a new script converter backed by the shared data store
     pub fn new() -> Self {
-        let (traditional_to_simplified, simplified_to_traditional) =
-            Self::load_comprehensive_mappings();
+        Self::with_data(DataStore::shared())
+    }
+
+    /// Create a new script converter with the tables of the given data store
+    pub fn with_data(store: &DataStore) -> Self {
+        let (traditional_to_simplified, simplified_to_traditional) = store.script_tables();
+        let readings = store.readings();
 
-        // Try to initialize OpenCC validator
-        let opencc_validator = OpenCCValidator::new().ok();
+        // OpenCC validator, if it could be initialized
+        let opencc_validator = store.opencc();
 
         Self {
+            longest_traditional: longest_key(&traditional_to_simplified),
+            longest_simplified: longest_key(&simplified_to_traditional),
+            detector: ScriptDetector::with_tables(
+                &traditional_to_simplified,
+                &simplified_to_traditional,
+                &readings,
+            ),
             traditional_to_simplified,
             simplified_to_traditional,
             opencc_validator,
+            readings,
+            traditional_overrides: Arc::default(),
+            simplified_overrides: Arc::default(),
+            taiwan_vocabulary: false,
+            lossy_conversion: LossyConversion::Convert,
         }
     }
 
+    /// Layer a user dictionary over OpenCC and the built-in tables, builder style
+    pub fn with_dictionary(mut self, dictionary: &UserDictionary) -> Self {
+        Arc::make_mut(&mut self.traditional_overrides)
+            .layer(dictionary, DictionaryStage::TraditionalToSimplified);
+        Arc::make_mut(&mut self.simplified_overrides)
+            .layer(dictionary, DictionaryStage::SimplifiedToTraditional);
+        self
+    }
+
+    /// Convert Simplified vocabulary to Taiwan usage (软件 → 軟體) for Taiwan targets, builder style
+    pub fn with_taiwan_vocabulary(mut self, enabled: bool) -> Self {
+        self.taiwan_vocabulary = enabled;
+        self
+    }
+
+    /// Set what happens to characters that would not survive a round trip, builder style
+    pub fn with_lossy_conversion(mut self, mode: LossyConversion) -> Self {
+        self.lossy_conversion = mode;
+        self
+    }
+
+    /// Every single character the tables, regional standards and dictionaries map from or to
+    pub(crate) fn mapped_chars(&self) -> impl Iterator<Item = char> + '_ {
+        self.traditional_to_simplified
+            .values()
+            .chain(self.simplified_to_traditional.values())
+            .flatten()
+            .flat_map(|mapping| {
+                mapping
+                    .traditional
+                    .chars()
+                    .chain(mapping.simplified.chars())
+            })
+            .chain(self.traditional_overrides.chars())
+            .chain(self.simplified_overrides.chars())
+            .chain(
+                [Region::Taiwan, Region::HongKong, Region::Japan]
+                    .into_iter()
+                    .flat_map(Region::mapped_chars),
+            )
+    }
+
+    /// Length in chars of the longest phrase conversion may match
+    pub(crate) fn longest_phrase(&self) -> usize {
+        let taiwan = if self.taiwan_vocabulary {
+            regional::longest_taiwan_phrase()
+        } else {
+            0
+        };
+        self.longest_traditional
+            .max(self.longest_simplified)
+            .max(taiwan)
+    }
+
+    /// Whether a table or Taiwan vocabulary entry starting before char `cut`
+    /// of `chars` reaches past it
+    pub(crate) fn crosses_phrase(&self, chars: &[char], cut: usize) -> bool {
+        let longest = self.longest_phrase();
+        (cut.saturating_sub(longest.saturating_sub(1))..cut).any(|start| {
+            (cut - start + 1..=longest.min(chars.len() - start)).any(|len| {
+                let key: String = chars[start..start + len].iter().collect();
+                self.traditional_to_simplified.contains_key(&key)
+                    || self.simplified_to_traditional.contains_key(&key)
+                    || (self.taiwan_vocabulary && regional::taiwan_phrase(&key).is_some())
+            })
+        })
+    }
+
     /// Convert text between Traditional and Simplified Chinese
+    ///
+    /// Text is converted in runs rather than character by character, so
+    /// phrases decide one-to-many cases such as 头发 → 頭髮 vs 发展 → 發展:
+    /// OpenCC's phrase dictionaries if OpenCC is available, the phrase tables
+    /// by longest match otherwise. Outside a phrase each character takes its
+    /// top-ranked candidate and is flagged as ambiguous if it has others. A
+    /// change is recorded per converted character, or per phrase where the
+    /// conversion changes its length.
+    ///
+    /// Chinese text may mix scripts: each sentence is converted from its own
+    /// script, and characters the tables place in only one script are
+    /// converted from that script wherever they appear. `detected_script`
+    /// decides sentences without any such character.
     pub fn convert(
         &self,
         text: &str,
         target_script: Script,
         detected_script: Script,
     ) -> (String, Vec<TextChange>) {
+        let (result, changes, _) = self.convert_aligned(text, &target_script, &detected_script);
+        (result, changes)
+    }
+
+    /// Whether text detected as `detected_script` may need converting to reach `target_script`
+    pub(crate) fn converts(&self, detected_script: &Script, target_script: &Script) -> bool {
+        source_candidates(detected_script)
+            .iter()
+            .any(|source| Plan::of(source, target_script, self.taiwan_vocabulary).is_some())
+    }
+
+    /// Convert text, also returning the offset map of the conversion
+    pub(crate) fn convert_aligned(
+        &self,
+        text: &str,
+        target_script: &Script,
+        detected_script: &Script,
+    ) -> (String, Vec<TextChange>, OffsetMap) {
+        let sources = self.source_scripts(text, detected_script);
+        let plans = self.plans(&sources, target_script);
+        if plans.iter().all(Option::is_none) {
+            return (text.to_string(), Vec::new(), OffsetMap::identity(text));
+        }
+        let losses = match self.lossy_conversion {
+            LossyConversion::Convert => Vec::new(),
+            _ => self.round_trip_losses(text, target_script, detected_script),
+        };
+        self.convert_planned(text, &plans, &losses)
+    }
+
+    /// Spans of `text` whose conversion to `target_script` is not undone by
+    /// converting the result back to the script it came from, in text order
+    ///
+    /// Both conversions run as [`convert`](Self::convert) would run them; a
+    /// span is lost if the text it comes back as differs from the original.
+    pub fn round_trip_losses(
+        &self,
+        text: &str,
+        target_script: &Script,
+        detected_script: &Script,
+    ) -> Vec<RoundTripLoss> {
+        let sources = self.source_scripts(text, detected_script);
+        let plans = self.plans(&sources, target_script);
+        if plans.iter().all(Option::is_none) {
+            return Vec::new();
+        }
+        let converted_script = match target_script {
+            Script::TraditionalTaiwan | Script::TraditionalHongKong => Script::TraditionalChinese,
+            Script::JapaneseShinjitai => Script::Japanese,
+            script => script.clone(),
+        };
+        let back = |pos: usize| Plan::of(&converted_script, &sources[pos], false);
+
+        let (converted, _, forward) = self.convert_planned(text, &plans, &[]);
+        // Each converted span goes back to the script its source came from
+        let mut back_plans = Vec::with_capacity(converted.chars().count());
+        for segment in forward.segments() {
+            back_plans.extend(std::iter::repeat_n(
+                back(segment.original.start),
+                segment.normalized.len(),
+            ));
+        }
+        let (restored, _, backward) = self.convert_planned(&converted, &back_plans, &[]);
+        let chars: Vec<char> = text.chars().collect();
+        let converted_chars: Vec<char> = converted.chars().collect();
+        let restored_chars: Vec<char> = restored.chars().collect();
+
+        let mut losses = Vec::new();
+        for segment in forward.compose(&backward).segments() {
+            let original = &chars[segment.original.clone()];
+            let restored = &restored_chars[segment.normalized.clone()];
+            if original == restored {
+                continue;
+            }
+            let Some(back) = back(segment.original.start) else {
+                continue;
+            };
+            let converted: String = forward
+                .span_to_normalized(segment.original.clone(), OffsetUnit::Char)
+                .map(|span| converted_chars[span].iter().collect())
+                .unwrap_or_default();
+            losses.push(RoundTripLoss {
+                position: segment.original.start,
+                original: original.iter().collect(),
+                alternatives: self.candidates(&converted, back),
+                converted,
+                restored: restored.iter().collect(),
+            });
+        }
+        losses
+    }
+
+    /// Script each character of `text` is converted from
+    ///
+    /// A character written only in Shinjitai (発, 広, 気) is Japanese whatever
+    /// the text is detected as. Outside Chinese text every other character is
+    /// in `detected_script`. Otherwise a character the tables place in only one
+    /// script is in that script, and any other character is in the script its
+    /// sentence is detected as (`detected_script` if the sentence has no
+    /// evidence either way).
+    fn source_scripts(&self, text: &str, detected_script: &Script) -> Vec<Script> {
+        let chars: Vec<char> = text.chars().collect();
+        if matches!(
+            detected_script,
+            Script::Japanese | Script::JapaneseShinjitai
+        ) {
+            return vec![detected_script.clone(); chars.len()];
+        }
+        let shinjitai = |ch: char, script: Script| {
+            if self.is_shinjitai_only(ch) {
+                Script::Japanese
+            } else {
+                script
+            }
+        };
+        if !matches!(
+            detected_script,
+            Script::SimplifiedChinese | Script::TraditionalChinese
+        ) {
+            return chars
+                .iter()
+                .map(|&ch| shinjitai(ch, detected_script.clone()))
+                .collect();
+        }
+
+        let mut sources = Vec::with_capacity(chars.len());
+        for sentence in chars.split_inclusive(|ch| SEGMENT_ENDS.contains(ch)) {
+            let scores = self
+                .detector
+                .analyze(&sentence.iter().collect::<String>())
+                .scores;
+            let majority = match scores[&Script::TraditionalChinese]
+                .cmp(&scores[&Script::SimplifiedChinese])
+            {
+                std::cmp::Ordering::Greater => Script::TraditionalChinese,
+                std::cmp::Ordering::Less => Script::SimplifiedChinese,
+                std::cmp::Ordering::Equal => detected_script.clone(),
+            };
+            sources.extend(sentence.iter().map(|&ch| {
+                let script = self
+                    .detector
+                    .script_of(ch)
+                    .unwrap_or_else(|| majority.clone());
+                shinjitai(ch, script)
+            }));
+        }
+        sources
+    }
+
+    /// Whether `ch` is a Shinjitai form that Chinese does not write
+    ///
+    /// Forms the tables know (国, 学, 芸) are Chinese characters as well.
+    fn is_shinjitai_only(&self, ch: char) -> bool {
+        if regional::kyujitai(ch).is_empty() || regional::is_chinese_form(ch) {
+            return false;
+        }
+        let key = ch.to_string();
+        !self.simplified_to_traditional.contains_key(&key)
+            && !self.traditional_to_simplified.contains_key(&key)
+    }
+
+    /// Plan bringing each character from its source script to `target_script`
+    fn plans(&self, sources: &[Script], target_script: &Script) -> Vec<Option<Plan>> {
+        let mut plans: HashMap<&Script, Option<Plan>> = HashMap::new();
+        sources
+            .iter()
+            .map(|source| {
+                *plans
+                    .entry(source)
+                    .or_insert_with(|| Plan::of(source, target_script, self.taiwan_vocabulary))
+            })
+            .collect()
+    }
+
+    /// Convert text following the plan of each character, treating the spans
+    /// in `losses` as the converter's lossy conversion mode says
+    fn convert_planned(
+        &self,
+        text: &str,
+        plans: &[Option<Plan>],
+        losses: &[RoundTripLoss],
+    ) -> (String, Vec<TextChange>, OffsetMap) {
         let chars: Vec<char> = text.chars().collect();
-        let mut result = String::new();
-        let mut changes = Vec::new();
+        let mut conversion = Conversion::default();
+        let mut run_start = 0;
+        // Spans the conversion leaves as they are need no special treatment
+        let mut losses = losses
+            .iter()
+            .filter(|loss| loss.converted != loss.original)
+            .peekable();
 
         for (pos, &ch) in chars.iter().enumerate() {
-            let converted_char = match (detected_script.clone(), target_script.clone()) {
-                (Script::TraditionalChinese, Script::SimplifiedChinese) => {
-                    self.convert_to_simplified(ch, pos, &mut changes)
-                }
-                (Script::SimplifiedChinese, Script::TraditionalChinese) => {
-                    self.convert_to_traditional(ch, pos, &mut changes)
-                }
-                _ => ch, // No conversion needed
+            if pos < run_start {
+                continue;
+            }
+            // A change of plan ends the run
+            if plans[pos] != plans[run_start] {
+                self.convert_run(
+                    &chars[run_start..pos],
+                    run_start,
+                    plans[run_start],
+                    &mut conversion,
+                );
+                run_start = pos;
+            }
+            let Some(plan) = plans[pos] else {
+                continue;
             };
-            result.push(converted_char);
+            // Lost spans are kept or annotated and split the text into runs
+            if let Some(loss) = losses.next_if(|loss| loss.position == pos) {
+                self.convert_run(
+                    &chars[run_start..pos],
+                    run_start,
+                    Some(plan),
+                    &mut conversion,
+                );
+                let end = pos + loss.original.chars().count();
+                let (target, kept) = match self.lossy_conversion {
+                    LossyConversion::Annotate => (
+                        format!("{}({})", loss.converted, loss.original),
+                        "original kept in parentheses",
+                    ),
+                    _ => (loss.original.clone(), "original kept unconverted"),
+                };
+                conversion.push(pos, &chars[pos..end], &target, |from, _| {
+                    format!(
+                        "{}; converting back gives {}, {}",
+                        plan.describe(from, &loss.converted),
+                        loss.restored,
+                        kept
+                    )
+                });
+                run_start = end;
+                continue;
+            }
+            // User dictionaries take priority and split the text into runs
+            let overrides = match plan.direction {
+                Some(Direction::ToSimplified) => Some(&self.traditional_overrides),
+                Some(Direction::ToTraditional) => Some(&self.simplified_overrides),
+                None => None,
+            };
+            if let Some(entry) = overrides.and_then(|overrides| overrides.get(ch)) {
+                self.convert_run(
+                    &chars[run_start..pos],
+                    run_start,
+                    Some(plan),
+                    &mut conversion,
+                );
+                let target = entry.target.unwrap_or(ch);
+                conversion.push(pos, &[ch], &target.to_string(), |from, to| {
+                    entry.reason(plan.describe(from, to))
+                });
+                run_start = pos + 1;
+            }
+        }
+        if run_start < chars.len() {
+            self.convert_run(
+                &chars[run_start..],
+                run_start,
+                plans[run_start],
+                &mut conversion,
+            );
         }
 
-        (result, changes)
+        let offset_map = OffsetMap::from_lengths(text, &conversion.text, conversion.lengths);
+        (conversion.text, conversion.changes, offset_map)
     }
 
-    /// Convert a character to Simplified Chinese
-    fn convert_to_simplified(&self, ch: char, pos: usize, changes: &mut Vec<TextChange>) -> char {
-        // First try OpenCC if available
+    /// Convert a run of characters starting at char `start`, with OpenCC if
+    /// available; a run without a plan is kept as it is
+    fn convert_run(
+        &self,
+        run: &[char],
+        start: usize,
+        plan: Option<Plan>,
+        conversion: &mut Conversion,
+    ) {
+        if run.is_empty() {
+            return;
+        }
+        let Some(plan) = plan else {
+            conversion.keep(run);
+            return;
+        };
+
         if let Some(ref opencc) = self.opencc_validator {
-            if let Ok(converted) = opencc.traditional_to_simplified(&ch.to_string()) {
-                if let Some(simp_char) = converted.chars().next() {
-                    if simp_char != ch {
-                        changes.push(TextChange {
-                            position: pos,
-                            original_char: ch,
-                            normalized_char: simp_char,
-                            change_type: ChangeType::ScriptConversion,
-                            reason: format!(
-                                "Traditional {} → Simplified {} (OpenCC)",
-                                ch, simp_char
-                            ),
-                        });
-                        return simp_char;
-                    }
+            let text: String = run.iter().collect();
+            let converted = plan
+                .opencc_configs()
+                .iter()
+                .try_fold(text, |text, config| opencc.convert_with(config, &text));
+            if let Ok(converted) = converted {
+                let first = conversion.changes.len();
+                conversion.push(start, run, &converted, |from, to| {
+                    format!("{} (OpenCC)", plan.describe(from, to))
+                });
+                // Characters of a phrase the tables know are settled by it as well
+                let phrases = self.phrase_spans(run, start, plan);
+                for change in &mut conversion.changes[first..] {
+                    let settled = phrases.iter().any(|span| span.contains(&change.position));
+                    self.annotate(std::slice::from_mut(change), plan, settled);
                 }
+                return;
             }
         }
 
-        // Fallback to Unihan data
-        if let Some(mappings) = self.traditional_to_simplified.get(&ch.to_string()) {
-            if let Some(mapping) = mappings.first() {
-                let simp_char = mapping.simplified.chars().next().unwrap_or(ch);
-                if simp_char != ch {
-                    changes.push(TextChange {
-                        position: pos,
-                        original_char: ch,
-                        normalized_char: simp_char,
-                        change_type: ChangeType::ScriptConversion,
-                        reason: format!("Traditional {} → Simplified {} (Unihan)", ch, simp_char),
-                    });
-                    return simp_char;
-                }
-            }
+        // Fallback to the tables, longest key first, then the regional table
+        let mut pos = 0;
+        while pos < run.len() {
+            let rest = &run[pos..];
+            let found = if plan.from_japanese {
+                Some(self.find_japanese(rest[0], plan))
+            } else {
+                self.find_taiwan_phrase(rest, plan)
+                    .or_else(|| self.find_mapping(rest, plan))
+            };
+            let (len, target, source) = found.unwrap_or_else(|| (1, rest[0].to_string(), None));
+            let target = match plan.region {
+                Some(region) => target
+                    .chars()
+                    .map(|ch| region.variant(ch).unwrap_or(ch))
+                    .collect(),
+                None => target,
+            };
+
+            let first = conversion.changes.len();
+            conversion.push(start + pos, &rest[..len], &target, |from, to| {
+                let source = source
+                    .clone()
+                    .unwrap_or_else(|| "regional table".to_string());
+                format!("{} ({})", plan.describe(from, to), source)
+            });
+            // A phrase match has already settled its characters
+            self.annotate(&mut conversion.changes[first..], plan, len > 1);
+            pos += len;
         }
+    }
 
-        ch // No conversion
+    /// Char spans of the phrases the tables match in a run starting at char `start`
+    fn phrase_spans(&self, run: &[char], start: usize, plan: Plan) -> Vec<Range<usize>> {
+        let mut spans = Vec::new();
+        if plan.from_japanese {
+            return spans;
+        }
+        let mut pos = 0;
+        while pos < run.len() {
+            let rest = &run[pos..];
+            let len = self
+                .find_taiwan_phrase(rest, plan)
+                .or_else(|| self.find_mapping(rest, plan))
+                .map_or(1, |(len, _, _)| len);
+            if len > 1 {
+                spans.push(start + pos..start + pos + len);
+            }
+            pos += len;
+        }
+        spans
     }
 
-    /// Convert a character to Traditional Chinese
-    fn convert_to_traditional(&self, ch: char, pos: usize, changes: &mut Vec<TextChange>) -> char {
-        // First try OpenCC if available
-        if let Some(ref opencc) = self.opencc_validator {
-            if let Ok(converted) = opencc.simplified_to_traditional(&ch.to_string()) {
-                if let Some(trad_char) = converted.chars().next() {
-                    if trad_char != ch {
-                        changes.push(TextChange {
-                            position: pos,
-                            original_char: ch,
-                            normalized_char: trad_char,
-                            change_type: ChangeType::ScriptConversion,
-                            reason: format!(
-                                "Simplified {} → Traditional {} (OpenCC)",
-                                ch, trad_char
-                            ),
-                        });
-                        return trad_char;
-                    }
-                }
+    /// Record the reading of each change and, unless `settled`, the table's
+    /// candidates on changes whose source has more than one
+    fn annotate(&self, changes: &mut [TextChange], plan: Plan, settled: bool) {
+        for change in changes {
+            let reading = Reading::of(&change.normalized_text, &self.readings);
+            change.pinyin = reading.pinyin;
+            change.zhuyin = reading.zhuyin;
+            if settled {
+                continue;
+            }
+
+            let mut alternatives = self.candidates(&change.original_text, plan);
+            if alternatives.is_empty() {
+                continue;
+            }
+            if !alternatives.contains(&change.normalized_text) {
+                alternatives.insert(0, change.normalized_text.clone());
+            }
+            if alternatives.len() > 1 {
+                change.reason =
+                    format!("{}; ambiguous: {}", change.reason, alternatives.join(" / "));
+                change.ambiguous = true;
+                change.alternatives = alternatives;
             }
         }
+    }
 
-        // Fallback to Unihan data
-        if let Some(mappings) = self.simplified_to_traditional.get(&ch.to_string()) {
-            if let Some(mapping) = mappings.first() {
-                let trad_char = mapping.traditional.chars().next().unwrap_or(ch);
-                if trad_char != ch {
-                    changes.push(TextChange {
-                        position: pos,
-                        original_char: ch,
-                        normalized_char: trad_char,
-                        change_type: ChangeType::ScriptConversion,
-                        reason: format!("Simplified {} → Traditional {} (Unihan)", ch, trad_char),
-                    });
-                    return trad_char;
-                }
+    /// Every target the tables list for `source` in the plan's direction, in order of preference
+    fn candidates(&self, source: &str, plan: Plan) -> Vec<String> {
+        let targets = if plan.from_japanese {
+            let mut chars = source.chars();
+            let (Some(ch), None) = (chars.next(), chars.next()) else {
+                return Vec::new();
+            };
+            regional::chinese_equivalents(ch)
+                .iter()
+                .map(|&traditional| match plan.direction {
+                    Some(direction) => self
+                        .table_targets(&traditional.to_string(), direction)
+                        .into_iter()
+                        .next()
+                        .unwrap_or_else(|| ch.to_string()),
+                    None => traditional.to_string(),
+                })
+                .collect()
+        } else {
+            match plan.direction {
+                Some(direction) => self.table_targets(source, direction),
+                None => Vec::new(),
             }
+        };
+
+        let mut candidates: Vec<String> = Vec::new();
+        for target in targets {
+            let target: String = match plan.region {
+                Some(region) => target
+                    .chars()
+                    .map(|ch| region.variant(ch).unwrap_or(ch))
+                    .collect(),
+                None => target,
+            };
+            if !target.is_empty() && !candidates.contains(&target) {
+                candidates.push(target);
+            }
+        }
+        candidates
+    }
+
+    /// Targets of a table entry in the given direction, ranked
+    fn table_targets(&self, source: &str, direction: Direction) -> Vec<String> {
+        let table = match direction {
+            Direction::ToSimplified => &self.traditional_to_simplified,
+            Direction::ToTraditional => &self.simplified_to_traditional,
+        };
+        table
+            .get(source)
+            .into_iter()
+            .flatten()
+            .map(|mapping| match direction {
+                Direction::ToSimplified => mapping.simplified.clone(),
+                Direction::ToTraditional => mapping.traditional.clone(),
+            })
+            .collect()
+    }
+
+    /// Chinese form of a Japanese character: its Traditional form, usually the
+    /// Kyūjitai form (広 → 廣) but 預 for 予, converted on to Simplified if the
+    /// plan asks for it
+    ///
+    /// Japanese-only characters (働, 畑, 込) have no Chinese form and are kept.
+    /// So is a character whose Kyūjitai form has no Simplified mapping when
+    /// converting to Simplified: Shinjitai often already is the Simplified
+    /// form (国), and the Kyūjitai form (國) never is.
+    fn find_japanese(&self, ch: char, plan: Plan) -> (usize, String, Option<String>) {
+        if regional::is_japanese_only(ch) {
+            return (1, ch.to_string(), None);
         }
+        let kyujitai = regional::chinese_equivalents(ch).first().copied();
+        let traditional = kyujitai.unwrap_or(ch);
+        let simplified = self.find_mapping(&[traditional], plan);
+        let source = match (kyujitai, &simplified) {
+            (Some(_), Some(_)) => "Shinjitai table, Unihan",
+            (Some(_), None) => "Shinjitai table",
+            (None, _) => "Unihan",
+        };
+        let target = match (simplified, plan.direction) {
+            (Some((_, simplified, _)), _) => simplified,
+            (None, Some(Direction::ToSimplified)) => return (1, ch.to_string(), None),
+            (None, _) => traditional.to_string(),
+        };
+        (1, target, Some(source.to_string()))
+    }
 
-        ch // No conversion
+    /// Longest Taiwan vocabulary entry at the start of `text`, if the plan uses them
+    fn find_taiwan_phrase(
+        &self,
+        text: &[char],
+        plan: Plan,
+    ) -> Option<(usize, String, Option<String>)> {
+        if !plan.vocabulary {
+            return None;
+        }
+        (2..=regional::longest_taiwan_phrase().min(text.len()))
+            .rev()
+            .find_map(|len| {
+                let key: String = text[..len].iter().collect();
+                let target = regional::taiwan_phrase(&key)?;
+                Some((
+                    len,
+                    target.to_string(),
+                    Some(format!("Taiwan phrase {}", key)),
+                ))
+            })
+    }
+
+    /// Longest table entry at the start of `text` in the plan's direction
+    ///
+    /// Keys longer than one character come from the phrase tables.
+    fn find_mapping(&self, text: &[char], plan: Plan) -> Option<(usize, String, Option<String>)> {
+        let (table, longest_key) = match plan.direction? {
+            Direction::ToSimplified => (&self.traditional_to_simplified, self.longest_traditional),
+            Direction::ToTraditional => (&self.simplified_to_traditional, self.longest_simplified),
+        };
+        (1..=longest_key.min(text.len())).rev().find_map(|len| {
+            let key: String = text[..len].iter().collect();
+            let mapping = table.get(&key)?.first()?;
+            let target = match plan.direction? {
+                Direction::ToSimplified => &mapping.simplified,
+                Direction::ToTraditional => &mapping.traditional,
+            };
+            if target.is_empty() {
+                return None;
+            }
+            let source = if len == 1 {
+                "Unihan".to_string()
+            } else {
+                format!("phrase {}", key)
+            };
+            Some((len, target.clone(), Some(source)))
+        })
     }
 
     /// Load comprehensive mappings from the new clean data structure
-    fn load_comprehensive_mappings() -> (
-        HashMap<String, Vec<ScriptMapping>>,
-        HashMap<String, Vec<ScriptMapping>>,
-    ) {
+    ///
+    /// Each mapping carries the reading and frequency of its target, and the
+    /// candidates of a source are ranked by that frequency (unknown last, ties
+    /// in the order of the data file). A source listed among its own
+    /// candidates (面 → 麵, 面) is kept as it is if it is more frequent than
+    /// the others, and ranks after any candidate as frequent as itself
+    /// (从 → 從, 从). Phrases are added as entries of their own, so that
+    /// conversion matches them before their characters.
+    pub(crate) fn load_comprehensive_mappings(
+        source: &DataSource,
+        readings: &ReadingTable,
+        phrases: &(Arc<PhraseTable>, Arc<PhraseTable>),
+        report: &mut LoadReport,
+    ) -> Result<(ScriptTable, ScriptTable)> {
+        let mut traditional_to_simplified: ScriptTable = HashMap::new();
+        let mut simplified_to_traditional: ScriptTable = HashMap::new();
+
+        // Load Traditional → Simplified mappings
+        for (trad, simp) in source.load_candidates(DataFile::TraditionalToSimplified, report)? {
+            let mapping = ScriptMapping::with_reading(trad.clone(), simp.clone(), &simp, readings);
+            traditional_to_simplified
+                .entry(trad)
+                .or_default()
+                .push(mapping);
+        }
+
+        // Load Simplified → Traditional mappings
+        for (simp, trad) in source.load_candidates(DataFile::SimplifiedToTraditional, report)? {
+            let mapping = ScriptMapping::with_reading(trad.clone(), simp.clone(), &trad, readings);
+            simplified_to_traditional
+                .entry(simp)
+                .or_default()
+                .push(mapping);
+        }
+
+        for candidates in traditional_to_simplified
+            .values_mut()
+            .chain(simplified_to_traditional.values_mut())
+        {
+            candidates.sort_by_key(|mapping| {
+                let frequency = match mapping.frequency {
+                    0 => u32::MAX,
+                    frequency => frequency,
+                };
+                (frequency, mapping.traditional == mapping.simplified)
+            });
+        }
+
+        // A phrase has one conversion, which takes the place of any table entry
+        for (trad, simp) in phrases.0.iter() {
+            let mapping = ScriptMapping::with_reading(trad.clone(), simp.clone(), simp, readings);
+            traditional_to_simplified.insert(trad.clone(), vec![mapping]);
+        }
+        for (simp, trad) in phrases.1.iter() {
+            let mapping = ScriptMapping::with_reading(trad.clone(), simp.clone(), trad, readings);
+            simplified_to_traditional.insert(simp.clone(), vec![mapping]);
+        }
+
+        Ok((traditional_to_simplified, simplified_to_traditional))
+    }
+
+    /// Load the Traditional → Simplified and Simplified → Traditional phrase tables
+    ///
+    /// A phrase may convert to itself (皇后 stays 皇后, not 皇後); if an entry
+    /// lists several forms the first is used.
+    pub(crate) fn load_phrases(
+        source: &DataSource,
+        report: &mut LoadReport,
+    ) -> Result<(PhraseTable, PhraseTable)> {
+        let mut load = |file| -> Result<PhraseTable> {
+            let mut table = PhraseTable::new();
+            for (phrase, target) in source.load_candidates(file, report)? {
+                table.entry(phrase).or_insert(target);
+            }
+            Ok(table)
+        };
+        Ok((
+            load(DataFile::TraditionalToSimplifiedPhrases)?,
+            load(DataFile::SimplifiedToTraditionalPhrases)?,
+        ))
+    }
+
+    /// Minimal hardcoded tables used when the script mappings cannot be loaded
+    pub(crate) fn fallback_mappings() -> (ScriptTable, ScriptTable) {
         let mut traditional_to_simplified = HashMap::new();
         let mut simplified_to_traditional = HashMap::new();
 
-        // Load Traditional → Simplified mappings
-        let t2s_path = Path::new("data/processed/script_conversion/traditional_to_simplified.json");
-        if let Ok(contents) = fs::read_to_string(t2s_path) {
-            if let Ok(t2s_mappings) = serde_json::from_str::<HashMap<String, String>>(&contents) {
-                for (trad, simp) in t2s_mappings {
-                    let mapping = ScriptMapping {
-                        traditional: trad.clone(),
-                        simplified: simp.clone(),
-                        pinyin: String::new(),
-                        zhuyin: String::new(),
-                        frequency: 1,
-                    };
-                    traditional_to_simplified
-                        .entry(trad)
-                        .or_insert_with(Vec::new)
-                        .push(mapping);
-                }
+        let fallback_mappings = [
+            ("書".to_string(), "书".to_string()),
+            ("說".to_string(), "说".to_string()),
+            ("這".to_string(), "这".to_string()),
+            ("個".to_string(), "个".to_string()),
+            ("為".to_string(), "为".to_string()),
+            ("國".to_string(), "国".to_string()),
+            ("語".to_string(), "语".to_string()),
+            ("學".to_string(), "学".to_string()),
+            ("員".to_string(), "员".to_string()),
+            ("參".to_string(), "参".to_string()),
+        ];
+
+        for (trad, simp) in fallback_mappings {
+            let mapping = ScriptMapping {
+                traditional: trad.clone(),
+                simplified: simp.clone(),
+                pinyin: String::new(),
+                zhuyin: String::new(),
+                frequency: 0,
+            };
+
+            traditional_to_simplified
+                .entry(trad)
+                .or_insert_with(Vec::new)
+                .push(mapping.clone());
+
+            simplified_to_traditional
+                .entry(simp)
+                .or_insert_with(Vec::new)
+                .push(mapping);
+        }
+
+        (traditional_to_simplified, simplified_to_traditional)
+    }
+}
+
+/// Length in chars of the longest key of a table
+fn longest_key(table: &ScriptTable) -> usize {
+    table
+        .keys()
+        .map(|key| key.chars().count())
+        .max()
+        .unwrap_or(0)
+}
+
+/// Scripts text detected as `detected` may be converted from
+fn source_candidates(detected: &Script) -> Vec<Script> {
+    match detected {
+        Script::Japanese | Script::JapaneseShinjitai => vec![detected.clone()],
+        // Shinjitai-only characters are converted as Japanese in any text
+        Script::SimplifiedChinese | Script::TraditionalChinese => vec![
+            Script::SimplifiedChinese,
+            Script::TraditionalChinese,
+            Script::Japanese,
+        ],
+        script => vec![script.clone(), Script::Japanese],
+    }
+}
+
+/// Direction of a Traditional ↔ Simplified conversion
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    ToSimplified,
+    ToTraditional,
+}
+
+impl Direction {
+    /// Conversion needed to bring text in `detected` script to `target`, if any
+    fn of(detected: &Script, target: &Script) -> Option<Self> {
+        match (detected, target) {
+            (Script::TraditionalChinese, Script::SimplifiedChinese) => {
+                Some(Direction::ToSimplified)
+            }
+            (Script::SimplifiedChinese, Script::TraditionalChinese) => {
+                Some(Direction::ToTraditional)
             }
+            _ => None,
         }
+    }
+}
 
-        // Load Simplified → Traditional mappings
-        let s2t_path = Path::new("data/processed/script_conversion/simplified_to_traditional.json");
-        if let Ok(contents) = fs::read_to_string(s2t_path) {
-            if let Ok(s2t_mappings) = serde_json::from_str::<HashMap<String, String>>(&contents) {
-                for (simp, trad) in s2t_mappings {
-                    let mapping = ScriptMapping {
-                        traditional: trad.clone(),
-                        simplified: simp.clone(),
-                        pinyin: String::new(),
-                        zhuyin: String::new(),
-                        frequency: 1,
-                    };
-                    simplified_to_traditional
-                        .entry(simp)
-                        .or_insert_with(Vec::new)
-                        .push(mapping);
-                }
+/// How to bring text from its detected script to a target
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Plan {
+    /// Traditional ↔ Simplified conversion, if the base scripts differ
+    direction: Option<Direction>,
+    /// Regional standard the Traditional result follows
+    region: Option<Region>,
+    /// Convert Simplified vocabulary to Taiwan usage
+    vocabulary: bool,
+    /// The text is Japanese and is first brought from Shinjitai to standard Traditional
+    from_japanese: bool,
+}
+
+impl Plan {
+    /// Conversion from `detected` to `target`, if any is needed
+    fn of(detected: &Script, target: &Script, taiwan_vocabulary: bool) -> Option<Self> {
+        let region = Region::of(target);
+        if matches!(detected, Script::Japanese | Script::JapaneseShinjitai) {
+            let direction = match target {
+                Script::SimplifiedChinese => Some(Direction::ToSimplified),
+                Script::TraditionalChinese => None,
+                _ if region.is_some() && region != Some(Region::Japan) => None,
+                _ => return None,
+            };
+            return Some(Plan {
+                direction,
+                region,
+                vocabulary: false,
+                from_japanese: true,
+            });
+        }
+
+        let base = if region.is_some() {
+            &Script::TraditionalChinese
+        } else {
+            target
+        };
+        let direction = Direction::of(detected, base);
+        let region = region.filter(|_| {
+            matches!(
+                detected,
+                Script::SimplifiedChinese | Script::TraditionalChinese
+            )
+        });
+        if direction.is_none() && region.is_none() {
+            return None;
+        }
+        Some(Plan {
+            direction,
+            region,
+            vocabulary: taiwan_vocabulary
+                && region == Some(Region::Taiwan)
+                && direction == Some(Direction::ToTraditional),
+            from_japanese: false,
+        })
+    }
+
+    /// Description of converting `from` to `to`, e.g. "Traditional 書 → Simplified 书"
+    fn describe(self, from: &str, to: &str) -> String {
+        let source = match self.direction {
+            _ if self.from_japanese => "Japanese",
+            Some(Direction::ToTraditional) => "Simplified",
+            _ => "Traditional",
+        };
+        let target = match (self.region, self.direction) {
+            (Some(region), _) => region.name(),
+            (None, Some(Direction::ToSimplified)) => "Simplified",
+            (None, _) => "Traditional",
+        };
+        format!("{} {} → {} {}", source, from, target, to)
+    }
+
+    /// OpenCC configs implementing the plan, applied in order
+    fn opencc_configs(self) -> &'static [&'static str] {
+        if self.from_japanese {
+            return match (self.region, self.direction) {
+                (Some(Region::Taiwan), _) => &["jp2t", "t2tw"],
+                (Some(Region::HongKong), _) => &["jp2t", "t2hk"],
+                (_, Some(Direction::ToSimplified)) => &["jp2t", "t2s"],
+                _ => &["jp2t"],
+            };
+        }
+        match (self.region, self.direction) {
+            (Some(region), direction) => {
+                region.opencc_configs(direction == Some(Direction::ToTraditional), self.vocabulary)
             }
+            (None, Some(Direction::ToSimplified)) => &["t2s"],
+            (None, _) => &["s2t"],
         }
+    }
+}
 
-        // Fallback to hardcoded mappings if file doesn't exist
-        if traditional_to_simplified.is_empty() {
-            println!("Warning: No script mappings found, using fallback mappings");
-            // Add some basic fallback mappings
-            let fallback_mappings = vec![
-                ("書".to_string(), "书".to_string()),
-                ("說".to_string(), "说".to_string()),
-                ("這".to_string(), "这".to_string()),
-                ("個".to_string(), "个".to_string()),
-                ("為".to_string(), "为".to_string()),
-                ("國".to_string(), "国".to_string()),
-                ("語".to_string(), "语".to_string()),
-                ("學".to_string(), "学".to_string()),
-                ("員".to_string(), "员".to_string()),
-                ("參".to_string(), "参".to_string()),
-            ];
-
-            for (trad, simp) in fallback_mappings {
-                let mapping = ScriptMapping {
-                    traditional: trad.clone(),
-                    simplified: simp.clone(),
-                    pinyin: String::new(),
-                    zhuyin: String::new(),
-                    frequency: 1,
-                };
+/// Converted text built up run by run
+#[derive(Default)]
+struct Conversion {
+    text: String,
+    changes: Vec<TextChange>,
+    /// `(original, converted)` segment lengths in chars
+    lengths: Vec<(usize, usize)>,
+}
 
-                traditional_to_simplified
-                    .entry(trad)
-                    .or_insert_with(Vec::new)
-                    .push(mapping.clone());
+impl Conversion {
+    /// Append `source` unchanged
+    fn keep(&mut self, source: &[char]) {
+        self.text.extend(source);
+        self.lengths
+            .extend(std::iter::repeat_n((1, 1), source.len()));
+    }
+
+    /// Append `source`, found at char `position`, converted to `target`
+    ///
+    /// If the lengths match, a change is recorded for every character that
+    /// differs; otherwise one change covers the span between the common prefix
+    /// and suffix.
+    fn push(
+        &mut self,
+        position: usize,
+        source: &[char],
+        target: &str,
+        reason: impl Fn(&str, &str) -> String,
+    ) {
+        let target: Vec<char> = target.chars().collect();
+        self.text.extend(target.iter());
 
-                simplified_to_traditional
-                    .entry(simp)
-                    .or_insert_with(Vec::new)
-                    .push(mapping);
+        if source.len() == target.len() {
+            for (offset, (&from, &to)) in source.iter().zip(&target).enumerate() {
+                if from != to {
+                    self.changes.push(TextChange::new(
+                        position + offset,
+                        from,
+                        to,
+                        ChangeType::ScriptConversion,
+                        reason(&from.to_string(), &to.to_string()),
+                    ));
+                }
+                self.lengths.push((1, 1));
             }
+            return;
         }
 
-        println!(
-            "Loaded {} comprehensive script mappings (traditional->simplified: {}, simplified->traditional: {})",
-            traditional_to_simplified.len() + simplified_to_traditional.len(),
-            traditional_to_simplified.len(),
-            simplified_to_traditional.len()
-        );
+        let prefix = source
+            .iter()
+            .zip(&target)
+            .take_while(|(from, to)| from == to)
+            .count();
+        let suffix = source[prefix..]
+            .iter()
+            .rev()
+            .zip(target[prefix..].iter().rev())
+            .take_while(|(from, to)| from == to)
+            .count();
+        let from: String = source[prefix..source.len() - suffix].iter().collect();
+        let to: String = target[prefix..target.len() - suffix].iter().collect();
 
-        (traditional_to_simplified, simplified_to_traditional)
+        self.lengths.extend(std::iter::repeat_n((1, 1), prefix));
+        self.lengths
+            .push((from.chars().count(), to.chars().count()));
+        self.lengths.extend(std::iter::repeat_n((1, 1), suffix));
+        self.changes.push(TextChange::replacement(
+            position + prefix,
+            from.clone(),
+            to.clone(),
+            ChangeType::ScriptConversion,
+            reason(&from, &to),
+        ));
     }
 }
 
@@ -250,6 +1026,21 @@ impl Default for ScriptConverter {
 mod tests {
     use super::*;
 
+    /// Converter over the given Traditional → Simplified and Simplified → Traditional tables
+    fn converter(t2s: &str, s2t: &str) -> ScriptConverter {
+        converter_with_readings(t2s, s2t, "{}")
+    }
+
+    /// Converter over the given tables, ranking candidates by the given readings
+    fn converter_with_readings(t2s: &str, s2t: &str, readings: &str) -> ScriptConverter {
+        let store = DataStore::new(DataSource::memory([
+            (DataFile::TraditionalToSimplified, t2s.as_bytes().to_vec()),
+            (DataFile::SimplifiedToTraditional, s2t.as_bytes().to_vec()),
+            (DataFile::Readings, readings.as_bytes().to_vec()),
+        ]));
+        ScriptConverter::with_data(&store)
+    }
+
     #[test]
     fn test_traditional_to_simplified() {
         let converter = ScriptConverter::new();
@@ -278,6 +1069,367 @@ mod tests {
         assert_ne!(result, "荣耀归于乌克兰");
     }
 
+    #[test]
+    fn test_longest_phrase_wins() {
+        let converter = converter("{}", r#"{"头发": "頭髮", "发": "發", "头": "頭"}"#);
+        let (result, changes) = converter.convert(
+            "头发发展",
+            Script::TraditionalChinese,
+            Script::SimplifiedChinese,
+        );
+
+        assert_eq!(result, "頭髮發展");
+        let positions: Vec<usize> = changes.iter().map(|change| change.position).collect();
+        assert_eq!(positions, [0, 1, 2]);
+    }
+
+    #[test]
+    fn test_shipped_phrase_tables() {
+        // The shipped tables, without OpenCC
+        let mut converter = ScriptConverter::new();
+        converter.opencc_validator = None;
+        let to_traditional = |text: &str| {
+            converter.convert(text, Script::TraditionalChinese, Script::SimplifiedChinese)
+        };
+
+        let (result, changes) = to_traditional("头发和发展");
+        assert_eq!(result, "頭髮和發展");
+        assert!(changes[..2].iter().all(|change| !change.ambiguous));
+        assert!(changes[1].reason.contains("phrase 头发"));
+        assert_eq!(to_traditional("干净").0, "乾淨");
+        assert_eq!(to_traditional("面条和里面").0, "麵條和裏面");
+        assert_eq!(to_traditional("皇后").0, "皇后");
+
+        let (result, _) = converter.convert(
+            "著名的乾隆",
+            Script::SimplifiedChinese,
+            Script::TraditionalChinese,
+        );
+        assert_eq!(result, "著名的乾隆");
+    }
+
+    #[test]
+    #[cfg(not(feature = "opencc"))]
+    fn test_built_in_opencc() {
+        // Without libopencc the native engine runs over the shipped tables
+        let converter = ScriptConverter::new();
+        let (result, changes) = converter.convert(
+            "头发和发",
+            Script::TraditionalChinese,
+            Script::SimplifiedChinese,
+        );
+
+        assert_eq!(result, "頭髮和發");
+        assert!(changes[1].reason.ends_with("(OpenCC)"));
+        // 发 is settled by the phrase 头发 but not on its own
+        assert!(!changes[1].ambiguous);
+        assert!(changes[2].ambiguous);
+    }
+
+    #[test]
+    fn test_ambiguous_conversions_list_alternatives() {
+        let converter = converter("{}", r#"{"发": ["發", "髮"], "头发": "頭髮", "头": "頭"}"#);
+        let (result, changes) = converter.convert(
+            "头发发",
+            Script::TraditionalChinese,
+            Script::SimplifiedChinese,
+        );
+
+        assert_eq!(result, "頭髮發");
+        let ambiguous: Vec<&TextChange> =
+            changes.iter().filter(|change| change.ambiguous).collect();
+        assert_eq!(ambiguous.len(), 1);
+        assert_eq!(ambiguous[0].position, 2);
+        assert_eq!(ambiguous[0].normalized_text, "發");
+        assert_eq!(ambiguous[0].alternatives, ["發", "髮"]);
+    }
+
+    #[test]
+    fn test_round_trip_losses() {
+        let converter = converter(
+            r#"{"頭": "头", "髮": "发", "發": "发"}"#,
+            r#"{"头": "頭", "发": ["發", "髮"]}"#,
+        );
+        let (target, source) = (Script::SimplifiedChinese, Script::TraditionalChinese);
+
+        let losses = converter.round_trip_losses("頭髮發", &target, &source);
+        assert_eq!(losses.len(), 1);
+        assert_eq!(losses[0].position, 1);
+        assert_eq!(
+            (
+                losses[0].original.as_str(),
+                losses[0].converted.as_str(),
+                losses[0].restored.as_str()
+            ),
+            ("髮", "发", "發")
+        );
+        assert_eq!(losses[0].alternatives, ["發", "髮"]);
+
+        let convert = |mode: LossyConversion| {
+            let converter = converter.clone().with_lossy_conversion(mode);
+            converter.convert("頭髮發", target.clone(), source.clone())
+        };
+        assert_eq!(convert(LossyConversion::Convert).0, "头发发");
+        let (result, changes) = convert(LossyConversion::Keep);
+        assert_eq!(result, "头髮发");
+        assert_eq!(changes.len(), 2);
+        let (result, changes) = convert(LossyConversion::Annotate);
+        assert_eq!(result, "头发(髮)发");
+        assert_eq!(changes[1].normalized_text, "发(髮)");
+        assert!(changes[1]
+            .reason
+            .ends_with("converting back gives 發, original kept in parentheses"));
+    }
+
+    #[test]
+    fn test_mixed_script_text() {
+        let converter = converter(
+            r#"{"這": "这", "國": "国", "說": "说"}"#,
+            r#"{"这": "這", "国": "國", "说": "說"}"#,
+        );
+
+        // A Simplified document quoting a Traditional sentence
+        let (result, changes) = converter.convert(
+            "他说：这是国家。這是國家。",
+            Script::SimplifiedChinese,
+            Script::SimplifiedChinese,
+        );
+        assert_eq!(result, "他说：这是国家。这是国家。");
+        let positions: Vec<usize> = changes.iter().map(|change| change.position).collect();
+        assert_eq!(positions, [8, 10]);
+        assert!(changes[0]
+            .reason
+            .starts_with("Traditional 這 → Simplified 这"));
+
+        // A Traditional document with one Simplified sentence, and a stray
+        // Simplified character inside a Traditional one
+        let (result, _) = converter.convert(
+            "這是國家。这是国家。他說这個",
+            Script::TraditionalChinese,
+            Script::TraditionalChinese,
+        );
+        assert_eq!(result, "這是國家。這是國家。他說這個");
+
+        let losses = converter.round_trip_losses(
+            "這是國家。这是国家。",
+            &Script::SimplifiedChinese,
+            &Script::TraditionalChinese,
+        );
+        assert!(losses.is_empty());
+    }
+
+    #[test]
+    fn test_identity_candidates() {
+        // The shipped tables, without OpenCC
+        let mut converter = ScriptConverter::new();
+        converter.opencc_validator = None;
+        let convert = |text: &str| {
+            converter.convert(text, Script::TraditionalChinese, Script::SimplifiedChinese)
+        };
+
+        // 才 and 面 are more common than 纔 and 麵, and stay as they are
+        let (result, changes) = convert("我才来");
+        assert_eq!(result, "我才來");
+        assert_eq!(changes.len(), 1);
+        let (result, _) = convert("面");
+        assert_eq!(result, "面");
+
+        // 后 ties with 後 and converts, but is flagged as possibly meant as is
+        let (result, changes) = convert("后");
+        assert_eq!(result, "後");
+        assert!(changes[0].ambiguous);
+        assert_eq!(changes[0].alternatives, ["後", "后"]);
+
+        // 这 and 从 are not written in Traditional text, so nothing is flagged
+        let (result, changes) = convert("这从");
+        assert_eq!(result, "這從");
+        assert!(changes.iter().all(|change| !change.ambiguous));
+    }
+
+    #[test]
+    fn test_candidates_ranked_by_frequency() {
+        let converter = converter_with_readings(
+            "{}",
+            r#"{"发": ["髮", "發"]}"#,
+            r#"{"發": {"pinyin": "fā", "frequency": 1}, "髮": {"pinyin": "fà", "frequency": 3}}"#,
+        );
+        let (result, changes) =
+            converter.convert("发", Script::TraditionalChinese, Script::SimplifiedChinese);
+
+        assert_eq!(result, "發");
+        assert_eq!(changes[0].alternatives, ["發", "髮"]);
+        assert_eq!(
+            (changes[0].pinyin.as_str(), changes[0].zhuyin.as_str()),
+            ("fā", "ㄈㄚ")
+        );
+        let candidates = &converter.simplified_to_traditional["发"];
+        assert_eq!(candidates[1].pinyin, "fà");
+        assert_eq!(candidates[1].frequency, 3);
+    }
+
+    #[test]
+    fn test_japanese_conversion() {
+        let converter = converter(
+            r#"{"國": "国", "廣": "广", "氣": "气", "預": "预", "藝": "艺"}"#,
+            r#"{"国": "國", "广": "廣", "气": "氣", "预": "預", "艺": "藝"}"#,
+        );
+        let convert = |text: &str, target: Script, detected: Script| {
+            converter.convert(text, target, detected).0
+        };
+
+        assert_eq!(
+            convert("国の広い気", Script::TraditionalChinese, Script::Japanese),
+            "國の廣い氣"
+        );
+        assert_eq!(
+            convert("国の広い気", Script::SimplifiedChinese, Script::Japanese),
+            "国の广い气"
+        );
+        assert_eq!(
+            convert("國廣氣", Script::Japanese, Script::TraditionalChinese),
+            "国広気"
+        );
+        assert_eq!(
+            convert("国广气", Script::Japanese, Script::SimplifiedChinese),
+            "国広気"
+        );
+
+        // Japanese-only characters have no Chinese form
+        let (result, changes) =
+            converter.convert("働く", Script::SimplifiedChinese, Script::Japanese);
+        assert_eq!(result, "働く");
+        assert!(changes.is_empty());
+
+        // 予 is written for 預 far more often than for its Kyūjitai form 豫
+        let (result, changes) =
+            converter.convert("予定です", Script::TraditionalChinese, Script::Japanese);
+        assert_eq!(result, "預定です");
+        assert_eq!(changes[0].alternatives, ["預", "豫"]);
+        assert_eq!(
+            convert("予定です", Script::SimplifiedChinese, Script::Japanese),
+            "预定です"
+        );
+        assert_eq!(
+            convert("芸です", Script::SimplifiedChinese, Script::Japanese),
+            "艺です"
+        );
+
+        // 弁 stands for three Traditional characters
+        let (result, changes) =
+            converter.convert("弁", Script::TraditionalChinese, Script::Japanese);
+        assert_eq!(result, "辯");
+        assert!(changes[0].ambiguous);
+        assert_eq!(changes[0].alternatives, ["辯", "辨", "瓣"]);
+    }
+
+    #[test]
+    fn test_shinjitai_only_characters() {
+        let converter = converter(
+            r#"{"國": "国", "發": "发", "廣": "广", "氣": "气"}"#,
+            r#"{"国": "國", "发": "發", "广": "廣", "气": "氣"}"#,
+        );
+        let convert = |text: &str, target: Script, detected: Script| {
+            converter.convert(text, target, detected).0
+        };
+
+        // Kanji-only Japanese text is detected as Chinese
+        assert_eq!(
+            convert(
+                "国広気",
+                Script::TraditionalChinese,
+                Script::SimplifiedChinese
+            ),
+            "國廣氣"
+        );
+        assert_eq!(
+            convert(
+                "発表",
+                Script::TraditionalChinese,
+                Script::SimplifiedChinese
+            ),
+            "發表"
+        );
+        assert_eq!(
+            convert(
+                "発表",
+                Script::SimplifiedChinese,
+                Script::TraditionalChinese
+            ),
+            "发表"
+        );
+        // Shinjitai forms Chinese writes as well are left to the Chinese tables
+        assert_eq!(
+            convert(
+                "给予",
+                Script::TraditionalChinese,
+                Script::SimplifiedChinese
+            ),
+            "给予"
+        );
+    }
+
+    #[test]
+    fn test_japanese_to_simplified_without_kyujitai_entry() {
+        let converter = converter(r#"{"廣": "广"}"#, "{}");
+
+        // 國 has no Simplified mapping, and 国 is already Simplified
+        let (result, changes) =
+            converter.convert("国の広い", Script::SimplifiedChinese, Script::Japanese);
+        assert_eq!(result, "国の广い");
+        assert_eq!(changes.len(), 1);
+    }
+
+    #[test]
+    fn test_regional_targets() {
+        let converter = converter("{}", r#"{"这": "這", "里": "裏", "软": "軟"}"#);
+        let convert = |text: &str, target: Script, detected: Script| {
+            converter.convert(text, target, detected).0
+        };
+
+        assert_eq!(
+            convert("这里", Script::TraditionalTaiwan, Script::SimplifiedChinese),
+            "這裡"
+        );
+        assert_eq!(
+            convert(
+                "这里",
+                Script::TraditionalHongKong,
+                Script::SimplifiedChinese
+            ),
+            "這裏"
+        );
+        assert_eq!(
+            convert("國", Script::JapaneseShinjitai, Script::TraditionalChinese),
+            "国"
+        );
+
+        let converter = converter.with_taiwan_vocabulary(true);
+        let (result, changes) = converter.convert(
+            "软件和内存",
+            Script::TraditionalTaiwan,
+            Script::SimplifiedChinese,
+        );
+        assert_eq!(result, "軟體和記憶體");
+        let last = changes.last().unwrap();
+        assert_eq!((last.position, last.original_text.as_str()), (3, "内存"));
+        assert_eq!(last.normalized_text, "記憶體");
+    }
+
+    #[test]
+    fn test_length_changing_conversion_is_one_change() {
+        let mut conversion = Conversion::default();
+        conversion.push(3, &['甲', '乙', '丙'], "甲丁戊丙", |from, to| {
+            format!("{} → {}", from, to)
+        });
+
+        assert_eq!(conversion.text, "甲丁戊丙");
+        assert_eq!(conversion.lengths, [(1, 1), (1, 2), (1, 1)]);
+        assert_eq!(conversion.changes.len(), 1);
+        assert_eq!(conversion.changes[0].position, 4);
+        assert_eq!(conversion.changes[0].original_text, "乙");
+        assert_eq!(conversion.changes[0].normalized_text, "丁戊");
+    }
+
     #[test]
     fn test_no_conversion_needed() {
         let converter = ScriptConverter::new();
@@ -1,29 +1,88 @@
 //! Script detection for CJK text
+//!
+//! Simplified and Traditional Chinese are told apart by the characters the
+//! script conversion tables place on only one side (这 / 這). Each counts
+//! by how common it is, from its Unihan `kFrequency`, so a rare character
+//! listed on one side by accident carries little weight.
 
-use crate::types::Script;
-use std::collections::HashMap;
+use crate::data::{DataStore, ReadingTable, ScriptTable};
+use crate::types::{Script, ScriptDetection};
+use crate::utils::unicode_utils::is_cjk_unified_ideograph;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+/// Weight of a one-sided character without a frequency
+const UNRANKED_WEIGHT: u32 = 1;
 
 /// Script detector that identifies the script of input text
+#[derive(Clone)]
 pub struct ScriptDetector {
-    simplified_indicators: HashMap<char, u32>,
-    traditional_indicators: HashMap<char, u32>,
+    simplified_indicators: Arc<HashMap<char, u32>>,
+    traditional_indicators: Arc<HashMap<char, u32>>,
 }
 
 impl ScriptDetector {
-    /// Create a new script detector
+    /// Create a new script detector backed by the shared data store
     pub fn new() -> Self {
+        Self::with_data(DataStore::shared())
+    }
+
+    /// Create a script detector with the tables of the given data store
+    pub fn with_data(store: &DataStore) -> Self {
+        let (traditional_to_simplified, simplified_to_traditional) = store.script_tables();
+        Self::with_tables(
+            &traditional_to_simplified,
+            &simplified_to_traditional,
+            &store.readings(),
+        )
+    }
+
+    /// Build the indicator sets from script conversion tables
+    pub(crate) fn with_tables(
+        traditional_to_simplified: &ScriptTable,
+        simplified_to_traditional: &ScriptTable,
+        readings: &ReadingTable,
+    ) -> Self {
+        let mut traditional = HashSet::new();
+        let mut simplified = HashSet::new();
+        for mapping in traditional_to_simplified
+            .values()
+            .chain(simplified_to_traditional.values())
+            .flatten()
+            // An identity candidate only lets conversion keep the character
+            .filter(|mapping| mapping.traditional != mapping.simplified)
+        {
+            traditional.extend(mapping.traditional.chars());
+            simplified.extend(mapping.simplified.chars());
+        }
+
+        let weigh = |only: &HashSet<char>, other: &HashSet<char>| -> HashMap<char, u32> {
+            only.difference(other)
+                .map(|&ch| (ch, weight(ch, readings)))
+                .collect()
+        };
         Self {
-            simplified_indicators: Self::build_simplified_indicators(),
-            traditional_indicators: Self::build_traditional_indicators(),
+            simplified_indicators: Arc::new(weigh(&simplified, &traditional)),
+            traditional_indicators: Arc::new(weigh(&traditional, &simplified)),
         }
     }
 
     /// Detect the script of the given text
     pub fn detect(&self, text: &str) -> Script {
+        self.analyze(text).script
+    }
+
+    /// Detect the script of the given text, with the evidence for each script
+    ///
+    /// Any kana makes the text Japanese and any Hangul makes it Korean;
+    /// otherwise the Chinese script with the higher score wins. Without any
+    /// evidence the script is Simplified Chinese with a confidence of 0.
+    pub fn analyze(&self, text: &str) -> ScriptDetection {
         let mut simplified_score = 0;
         let mut traditional_score = 0;
         let mut japanese_score = 0;
         let mut korean_score = 0;
+        let mut cjk_char_count = 0;
 
         for ch in text.chars() {
             let code_point = ch as u32;
@@ -45,8 +104,8 @@ impl ScriptDetector {
             }
 
             // Check for Chinese characters
-            if (0x4E00..=0x9FFF).contains(&code_point) {
-                // CJK Unified Ideographs
+            if is_cjk_unified_ideograph(ch) {
+                cjk_char_count += 1;
                 if let Some(&weight) = self.simplified_indicators.get(&ch) {
                     simplified_score += weight;
                 }
@@ -56,102 +115,58 @@ impl ScriptDetector {
             }
         }
 
-        // Return the script with highest score
-        if japanese_score > 0 {
+        let scores = HashMap::from([
+            (Script::SimplifiedChinese, simplified_score),
+            (Script::TraditionalChinese, traditional_score),
+            (Script::Japanese, japanese_score),
+            (Script::Korean, korean_score),
+        ]);
+        let script = Self::script_for(&scores);
+        let total: u32 = scores.values().sum();
+        let confidence = match total {
+            0 => 0.0,
+            total => f64::from(scores[&script]) / f64::from(total),
+        };
+        ScriptDetection {
+            script,
+            confidence,
+            scores,
+            cjk_char_count,
+        }
+    }
+
+    /// Script with the highest score in evidence as [`analyze`](Self::analyze) weighs it
+    pub(crate) fn script_for(scores: &HashMap<Script, u32>) -> Script {
+        let score = |script: &Script| scores.get(script).copied().unwrap_or(0);
+        if score(&Script::Japanese) > 0 {
             Script::Japanese
-        } else if korean_score > 0 {
+        } else if score(&Script::Korean) > 0 {
             Script::Korean
-        } else if traditional_score > simplified_score {
+        } else if score(&Script::TraditionalChinese) > score(&Script::SimplifiedChinese) {
             Script::TraditionalChinese
         } else {
             Script::SimplifiedChinese
         }
     }
 
-    /// Build simplified Chinese indicator characters with weights
-    fn build_simplified_indicators() -> HashMap<char, u32> {
-        let mut map = HashMap::new();
-
-        // High-frequency simplified characters (weight 3)
-        map.insert('国', 3); // Country
-        map.insert('学', 3); // Study
-        map.insert('为', 3); // For
-        map.insert('这', 3); // This
-        map.insert('个', 3); // Individual
-        map.insert('说', 3); // Say
-        map.insert('话', 3); // Speech
-
-        // Medium-frequency simplified characters (weight 2)
-        map.insert('发', 2); // Send
-        map.insert('现', 2); // Appear
-        map.insert('实', 2); // Real
-        map.insert('时', 2); // Time
-        map.insert('间', 2); // Between
-        map.insert('进', 2); // Enter
-        map.insert('出', 2); // Exit
-
-        // Lower-frequency simplified characters (weight 1)
-        map.insert('东', 1); // East
-        map.insert('西', 1); // West
-        map.insert('南', 1); // South
-        map.insert('北', 1); // North
-        map.insert('车', 1); // Vehicle
-        map.insert('马', 1); // Horse
-        map.insert('鸟', 1); // Bird
-
-        map
-    }
-
-    /// Build traditional Chinese indicator characters with weights
-    fn build_traditional_indicators() -> HashMap<char, u32> {
-        let mut map = HashMap::new();
-
-        // High-frequency traditional characters (weight 3)
-        map.insert('國', 3); // Country
-        map.insert('學', 3); // Study
-        map.insert('為', 3); // For
-        map.insert('這', 3); // This
-        map.insert('個', 3); // Individual
-        map.insert('說', 3); // Say
-        map.insert('話', 3); // Speech
-
-        // Medium-frequency traditional characters (weight 2)
-        map.insert('發', 2); // Send
-        map.insert('現', 2); // Appear
-        map.insert('實', 2); // Real
-        map.insert('時', 2); // Time
-        map.insert('間', 2); // Between
-        map.insert('進', 2); // Enter
-        map.insert('出', 2); // Exit
-
-        // Lower-frequency traditional characters (weight 1)
-        map.insert('東', 1); // East
-        map.insert('西', 1); // West
-        map.insert('南', 1); // South
-        map.insert('北', 1); // North
-        map.insert('車', 1); // Vehicle
-        map.insert('馬', 1); // Horse
-        map.insert('鳥', 1); // Bird
-
-        // Additional traditional characters from our test cases
-        map.insert('榮', 2); // Glory
-        map.insert('歸', 2); // Return
-        map.insert('於', 2); // At
-        map.insert('烏', 1); // Crow
-        map.insert('蘭', 1); // Orchid
-        map.insert('語', 2); // Language
-        map.insert('現', 2); // Appear
-        map.insert('書', 2); // Book
-        map.insert('說', 2); // Speak
-        map.insert('規', 1); // Rule
-        map.insert('論', 2); // Theory
-        map.insert('著', 2); // Author
-        map.insert('輔', 1); // Assist
-        map.insert('員', 1); // Member
-        map.insert('參', 1); // Participate
-        map.insert('例', 1); // Example
-
-        map
+    /// The only Chinese script `ch` is written in, if the tables tell
+    pub(crate) fn script_of(&self, ch: char) -> Option<Script> {
+        if self.traditional_indicators.contains_key(&ch) {
+            Some(Script::TraditionalChinese)
+        } else if self.simplified_indicators.contains_key(&ch) {
+            Some(Script::SimplifiedChinese)
+        } else {
+            None
+        }
+    }
+}
+
+/// Weight of a one-sided character: 5 for the most frequent (`kFrequency` 1)
+/// down to 1 for the least frequent and for characters without a frequency
+fn weight(ch: char, readings: &ReadingTable) -> u32 {
+    match readings.get(&ch).map(|reading| reading.frequency) {
+        Some(frequency @ 1..=5) => 6 - frequency,
+        _ => UNRANKED_WEIGHT,
     }
 }
 
@@ -164,6 +179,17 @@ impl Default for ScriptDetector {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::data::{DataFile, DataSource};
+
+    /// Detector over the given script tables and readings
+    fn detector(t2s: &str, s2t: &str, readings: &str) -> ScriptDetector {
+        let store = DataStore::new(DataSource::memory([
+            (DataFile::TraditionalToSimplified, t2s.as_bytes().to_vec()),
+            (DataFile::SimplifiedToTraditional, s2t.as_bytes().to_vec()),
+            (DataFile::Readings, readings.as_bytes().to_vec()),
+        ]));
+        ScriptDetector::with_data(&store)
+    }
 
     #[test]
     fn test_simplified_chinese_detection() {
@@ -221,4 +247,32 @@ mod tests {
         let result = detector.detect("한국어와 중국어");
         assert!(matches!(result, Script::Korean));
     }
+
+    #[test]
+    fn test_indicators_come_from_the_tables() {
+        let detector = detector(
+            r#"{"臺": "台", "颱": "台", "鬱": "郁", "這": "这", "这": "这"}"#,
+            r#"{"台": ["臺", "颱"], "郁": "鬱", "这": ["這", "这"]}"#,
+            r#"{"臺": {"frequency": 2}, "郁": {"frequency": 5}}"#,
+        );
+
+        // 臺 (weight 4) outweighs 郁 (weight 1)
+        let detection = detector.analyze("臺郁中文");
+        assert_eq!(detection.script, Script::TraditionalChinese);
+        assert_eq!(detection.scores[&Script::TraditionalChinese], 4);
+        assert_eq!(detection.scores[&Script::SimplifiedChinese], 1);
+        assert_eq!(detection.confidence, 0.8);
+        assert_eq!(detection.cjk_char_count, 4);
+
+        // Characters on neither side or both are no evidence
+        let detection = detector.analyze("中文 text");
+        assert_eq!(detection.script, Script::SimplifiedChinese);
+        assert_eq!(detection.confidence, 0.0);
+        assert_eq!(detection.cjk_char_count, 2);
+
+        // An identity candidate does not put 这 on the traditional side
+        let detection = detector.analyze("这");
+        assert_eq!(detection.script, Script::SimplifiedChinese);
+        assert_eq!(detection.scores[&Script::SimplifiedChinese], 1);
+    }
 }
@@ -0,0 +1,352 @@
+//! Streaming normalization over `io::BufRead` / `io::Write`
+
+use crate::normalizers::pipeline::advance_stage_offsets;
+use crate::normalizers::script_detector::ScriptDetector;
+use crate::normalizers::text_normalizer::TextNormalizer;
+use crate::types::{Script, TextChange};
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+
+/// Default amount of decoded text normalized at once (64 KiB)
+pub const DEFAULT_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Bytes an opening marker or code fence protects while its closer is
+/// still unread (64 KiB); past that it is read as plain text
+pub const MAX_OPEN_SPAN: usize = 64 * 1024;
+
+/// Summary of a streaming run
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StreamSummary {
+    pub bytes_read: usize,
+    pub bytes_written: usize,
+    pub chunks: usize,
+    pub changes: usize,
+    /// Script the input was read as: the one set with
+    /// [`StreamingNormalizer::with_detected_script`], else the one detected
+    /// from the whole input
+    pub detected_script: Script,
+}
+
+/// Normalizer that reads from any `BufRead` and writes to any `Write`
+///
+/// Input is decoded incrementally and cut into chunks only where the result
+/// cannot depend on what follows: after a sentence end or newline that lies
+/// outside protected spans, before a character that starts a new
+/// normalization run, and with no conversion phrase crossing the cut. Text
+/// without such a point is buffered until one arrives, so UTF-8 sequences,
+/// combining marks, phrases and protected spans that straddle reads are
+/// handled like in-memory input. The one bound is [`MAX_OPEN_SPAN`]: an
+/// opener whose closer is further away than that is read as plain text,
+/// where the in-memory path would protect everything up to the closer.
+///
+/// Changes are passed to a callback with the positions the in-memory path
+/// would report; they arrive chunk by chunk, so sort them by stage and
+/// position before handing them to [`crate::apply_inverse`].
+///
+/// The in-memory path detects the script from the whole input, which a
+/// stream cannot see in advance. If the script is known, set it with
+/// [`with_detected_script`](Self::with_detected_script) and the output is
+/// the in-memory output for that script. Otherwise the script of each chunk
+/// is detected from all text read so far, so a chunk read before the input
+/// shows its script can be converted differently than in memory; as
+/// conversion detects the script of each sentence on its own, this only
+/// affects sentences that show no script of their own.
+pub struct StreamingNormalizer<'a> {
+    normalizer: &'a TextNormalizer,
+    target_script: Option<Script>,
+    detected_script: Option<Script>,
+    chunk_size: usize,
+}
+
+impl<'a> StreamingNormalizer<'a> {
+    /// Create a streaming normalizer that uses the given normalizer's configuration
+    pub fn new(normalizer: &'a TextNormalizer) -> Self {
+        Self {
+            normalizer,
+            target_script: None,
+            detected_script: None,
+            chunk_size: DEFAULT_CHUNK_SIZE,
+        }
+    }
+
+    /// Convert to the given script (overrides the configured target)
+    pub fn with_target_script(mut self, target_script: Script) -> Self {
+        self.target_script = Some(target_script);
+        self
+    }
+
+    /// Read the input as written in the given script instead of detecting it
+    ///
+    /// Pass the script [`TextNormalizer::normalize`] detects for the whole
+    /// input (e.g. from an earlier pass over it) to get the same output.
+    pub fn with_detected_script(mut self, detected_script: Script) -> Self {
+        self.detected_script = Some(detected_script);
+        self
+    }
+
+    /// Set the amount of decoded text, in bytes, to normalize at once
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    /// Normalize everything from `reader` into `writer`, reporting each change to `on_change`
+    pub fn normalize<R, W, F>(
+        &self,
+        mut reader: R,
+        mut writer: W,
+        mut on_change: F,
+    ) -> io::Result<StreamSummary>
+    where
+        R: BufRead,
+        W: Write,
+        F: FnMut(TextChange),
+    {
+        let target_script = self.normalizer.resolve_target(self.target_script.clone());
+
+        let mut summary = StreamSummary {
+            bytes_read: 0,
+            bytes_written: 0,
+            chunks: 0,
+            changes: 0,
+            detected_script: Script::Auto,
+        };
+        // Script evidence of all text read so far
+        let mut evidence: HashMap<Script, u32> = HashMap::new();
+        // Chars each stage has received so far, to turn chunk positions into stream positions
+        let mut stage_offsets = vec![0; self.normalizer.stage_count()];
+        let mut undecoded: Vec<u8> = Vec::new();
+        let mut text = String::new();
+        // Start of the buffered text that may still hold a cut
+        let mut scan_from = 0;
+
+        loop {
+            let buffer = reader.fill_buf()?;
+            let at_eof = buffer.is_empty();
+            undecoded.extend_from_slice(buffer);
+            let consumed = buffer.len();
+            reader.consume(consumed);
+            summary.bytes_read += consumed;
+
+            decode_utf8(&mut undecoded, &mut text, at_eof)?;
+
+            while text.len() >= self.chunk_size || (at_eof && !text.is_empty()) {
+                let cut = if at_eof {
+                    text.len()
+                } else {
+                    match self
+                        .normalizer
+                        .find_cut(&text, &mut scan_from, MAX_OPEN_SPAN)
+                    {
+                        Some(cut) => cut,
+                        None => break, // no safe cut yet, read more
+                    }
+                };
+                let chunk: String = text.drain(..cut).collect();
+                scan_from = 0;
+
+                summary.detected_script = match &self.detected_script {
+                    Some(script) => script.clone(),
+                    None => {
+                        for (script, score) in self.normalizer.analyze_script(&chunk).scores {
+                            *evidence.entry(script).or_insert(0) += score;
+                        }
+                        ScriptDetector::script_for(&evidence)
+                    }
+                };
+                let (normalized, changes, _) = self.normalizer.run_detected(
+                    &chunk,
+                    target_script.as_ref(),
+                    &summary.detected_script,
+                );
+
+                let chunk_offsets = advance_stage_offsets(&mut stage_offsets, &chunk, &changes);
+                for mut change in changes {
+                    change.position += chunk_offsets[change.stage];
+                    on_change(change);
+                    summary.changes += 1;
+                }
+
+                writer.write_all(normalized.as_bytes())?;
+                summary.bytes_written += normalized.len();
+                summary.chunks += 1;
+            }
+
+            if at_eof {
+                break;
+            }
+        }
+
+        writer.flush()?;
+        Ok(summary)
+    }
+}
+
+/// Move the valid UTF-8 prefix of `bytes` into `text`, keeping an incomplete
+/// trailing sequence for the next read
+fn decode_utf8(bytes: &mut Vec<u8>, text: &mut String, at_eof: bool) -> io::Result<()> {
+    let valid = match std::str::from_utf8(bytes) {
+        Ok(_) => bytes.len(),
+        Err(error) if error.error_len().is_none() && !at_eof => error.valid_up_to(),
+        Err(error) => return Err(io::Error::new(io::ErrorKind::InvalidData, error)),
+    };
+    let decoded: Vec<u8> = bytes.drain(..valid).collect();
+    text.push_str(std::str::from_utf8(&decoded).expect("prefix was validated"));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protection::ProtectionRule;
+    use crate::types::NormalizationConfig;
+    use std::io::BufReader;
+
+    fn stream(text: &str, chunk_size: usize, target: Option<Script>) -> (String, Vec<TextChange>) {
+        stream_with(&TextNormalizer::new(), text, chunk_size, 1, target)
+    }
+
+    fn stream_with(
+        normalizer: &TextNormalizer,
+        text: &str,
+        chunk_size: usize,
+        capacity: usize,
+        target: Option<Script>,
+    ) -> (String, Vec<TextChange>) {
+        let mut streaming = StreamingNormalizer::new(normalizer).with_chunk_size(chunk_size);
+        if let Some(target) = target {
+            streaming = streaming.with_target_script(target);
+        }
+
+        // A one-byte buffer splits every multi-byte sequence across reads
+        let reader = BufReader::with_capacity(capacity, text.as_bytes());
+        let mut output = Vec::new();
+        let mut changes = Vec::new();
+        streaming
+            .normalize(reader, &mut output, |change| changes.push(change))
+            .unwrap();
+
+        (String::from_utf8(output).unwrap(), changes)
+    }
+
+    #[test]
+    fn test_matches_in_memory_path() {
+        let normalizer = TextNormalizer::new();
+        let text = "⽅⾯問題\n硏究敎育e\u{0301}\u{0323}x⽅a\u{0308}\n這個藥";
+        assert_streams_like_in_memory(&normalizer, text, None, &[1, 3, 7, 64]);
+
+        // A long line without a newline, with Taiwan phrases and a URL that
+        // must not be cut
+        let config = NormalizationConfig::default().with_taiwan_vocabulary(true);
+        let normalizer = TextNormalizer::with_config(config).with_protection(ProtectionRule::Urls);
+        let text = "软件和内存软件和内存。看https://example.com/這個書頁說?q=1！软件和内存";
+        assert_streams_like_in_memory(
+            &normalizer,
+            text,
+            Some(Script::TraditionalTaiwan),
+            &[1, 4, 8, 64],
+        );
+
+        // Sentence ends inside a fence or marked span whose closer has not
+        // been read yet
+        let normalizer = TextNormalizer::new()
+            .with_protection(ProtectionRule::InlineCode)
+            .with_protection(ProtectionRule::marked("⟦", "⟧"));
+        let text =
+            "看：\n```\n⽅⾯。⽅⾯。⽅⾯。⽅⾯。\n```\n⽅⾯。⟦⽅⾯。⽅⾯。⽅⾯。⽅⾯。⟧⽅⾯。";
+        assert_streams_like_in_memory(&normalizer, text, None, &[4, 32]);
+    }
+
+    fn assert_streams_like_in_memory(
+        normalizer: &TextNormalizer,
+        text: &str,
+        target: Option<Script>,
+        chunk_sizes: &[usize],
+    ) {
+        let expected = normalizer.normalize(text, target.clone());
+        let expected_positions: Vec<_> = expected
+            .changes
+            .iter()
+            .map(|c| (c.stage, c.position))
+            .collect();
+
+        for &chunk_size in chunk_sizes {
+            for capacity in [1, 8] {
+                let (output, changes) =
+                    stream_with(normalizer, text, chunk_size, capacity, target.clone());
+                let case = format!("chunk size {}, capacity {}", chunk_size, capacity);
+
+                assert_eq!(output, expected.normalized, "{}", case);
+                let mut positions: Vec<_> = changes.iter().map(|c| (c.stage, c.position)).collect();
+                positions.sort();
+                assert_eq!(positions, expected_positions, "{}", case);
+            }
+        }
+    }
+
+    #[test]
+    fn test_script_conversion() {
+        let normalizer = TextNormalizer::new();
+        let text = "這個藥一天喫四回";
+        let expected = normalizer.normalize(text, Some(Script::SimplifiedChinese));
+        let (output, changes) = stream(text, 4, Some(Script::SimplifiedChinese));
+
+        assert_eq!(output, expected.normalized);
+        assert_eq!(changes.len(), expected.changes.len());
+    }
+
+    #[test]
+    fn test_unclosed_fence_stops_protecting() {
+        let normalizer = TextNormalizer::new().with_protection(ProtectionRule::InlineCode);
+        let text = format!("```\n{}", "⽅⾯。".repeat(MAX_OPEN_SPAN / 4));
+        let expected = normalizer.normalize(&text, None);
+
+        let reader = BufReader::with_capacity(4096, text.as_bytes());
+        let mut output = Vec::new();
+        let summary = StreamingNormalizer::new(&normalizer)
+            .normalize(reader, &mut output, |_| {})
+            .unwrap();
+
+        // The fence never closes, so the input is not held back to its end
+        assert_eq!(String::from_utf8(output).unwrap(), expected.normalized);
+        assert!(summary.chunks > 1);
+    }
+
+    #[test]
+    fn test_detected_script() {
+        let normalizer = TextNormalizer::new();
+        // The kana that make the text Japanese come after the first sentence
+        let text = "國學。これは本です。國學。";
+        let expected = normalizer.normalize(text, Some(Script::JapaneseShinjitai));
+
+        let streaming = StreamingNormalizer::new(&normalizer)
+            .with_target_script(Script::JapaneseShinjitai)
+            .with_detected_script(expected.detected_script.clone())
+            .with_chunk_size(1);
+        let reader = BufReader::with_capacity(1, text.as_bytes());
+        let mut output = Vec::new();
+        let summary = streaming.normalize(reader, &mut output, |_| {}).unwrap();
+
+        assert_eq!(String::from_utf8(output).unwrap(), expected.normalized);
+        assert_eq!(summary.detected_script, Script::Japanese);
+        assert!(summary.chunks > 1);
+    }
+
+    #[test]
+    fn test_invalid_utf8() {
+        let normalizer = TextNormalizer::new();
+        let streaming = StreamingNormalizer::new(&normalizer);
+        let result = streaming.normalize(&b"ab\xFFcd"[..], Vec::new(), |_| {});
+
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_truncated_utf8_at_end() {
+        let normalizer = TextNormalizer::new();
+        let streaming = StreamingNormalizer::new(&normalizer);
+        let result = streaming.normalize(&"方".as_bytes()[..2], Vec::new(), |_| {});
+
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+}
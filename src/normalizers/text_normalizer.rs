@@ -1,136 +1,641 @@
 //! Main text normalizer that orchestrates all normalization steps
 
+use crate::alignment::{OffsetMap, OffsetUnit};
+use crate::analysis::FixedPointTable;
+use crate::data::DataStore;
+use crate::dictionary::UserDictionary;
+use crate::error::Result;
 use crate::normalizers::{
-    compatibility_normalizer::CompatibilityNormalizer, kangxi_normalizer::KangxiNormalizer,
-    script_converter::ScriptConverter, script_detector::ScriptDetector,
-    unicode_normalizer::UnicodeNormalizer, variant_normalizer::VariantNormalizer,
+    compatibility_normalizer::CompatibilityNormalizer,
+    hanja_transliterator::HanjaTransliterator,
+    kangxi_normalizer::KangxiNormalizer,
+    pipeline::{advance_stage_offsets, run_stages, NormalizationStage},
+    script_converter::{ScriptConverter, SEGMENT_ENDS},
+    script_detector::ScriptDetector,
+    unicode_normalizer::{is_boundary_before, UnicodeNormalizer},
+    variant_normalizer::VariantNormalizer,
 };
-use crate::types::{NormalizedText, Script, UnicodeNormalization};
+use crate::protection::{open_span_start, protected_spans, ProtectionRule};
+use crate::types::{
+    HanjaTransliteration, LossyConversion, NormalizationConfig, NormalizedText, RoundTripLoss,
+    Script, ScriptDetection, TextChange, UnicodeNormalization,
+};
+use std::collections::{BTreeSet, HashMap};
+use std::ops::Range;
+use std::sync::{Arc, Mutex};
 use std::time::Instant;
 
+/// Fixed-point tables per `(target, source)` script conversion, built on first use
+type CollapsedTables = Mutex<HashMap<Option<(Script, Script)>, Arc<FixedPointTable>>>;
+
 /// Main text normalizer that orchestrates all normalization steps
+///
+/// The mapping tables are loaded once per process and shared, so cloning a
+/// normalizer is cheap and one instance can be used from many threads.
+#[derive(Clone)]
 pub struct TextNormalizer {
+    config: NormalizationConfig,
     script_detector: ScriptDetector,
     script_converter: ScriptConverter,
     kangxi_normalizer: KangxiNormalizer,
     variant_normalizer: VariantNormalizer,
     compatibility_normalizer: CompatibilityNormalizer,
     unicode_normalizer: UnicodeNormalizer,
+    hanja_transliterator: HanjaTransliterator,
+    protection: Vec<ProtectionRule>,
+    collapsed: Arc<CollapsedTables>,
 }
 
 impl TextNormalizer {
-    /// Create a new text normalizer
+    /// Create a new text normalizer with the default configuration
     pub fn new() -> Self {
+        Self::with_config(NormalizationConfig::default())
+    }
+
+    /// Create a new text normalizer that follows the given configuration
+    ///
+    /// # Panics
+    ///
+    /// In strict mode, panics if the data it needs cannot be loaded; use
+    /// [`try_with_config`](Self::try_with_config) to handle that case.
+    pub fn with_config(config: NormalizationConfig) -> Self {
+        Self::with_data(config, DataStore::shared())
+    }
+
+    /// Create a new text normalizer that uses the tables of the given data store
+    ///
+    /// Use a [`DataStore`] built from a [`DataSource`](crate::data::DataSource)
+    /// to load curated tables instead of the default ones. Tables that cannot be
+    /// loaded are treated as empty unless the configuration is strict.
+    ///
+    /// # Panics
+    ///
+    /// In strict mode, panics if the data it needs cannot be loaded; use
+    /// [`try_with_data`](Self::try_with_data) to handle that case.
+    pub fn with_data(config: NormalizationConfig, store: &DataStore) -> Self {
+        if config.strict {
+            if let Err(error) = Self::check_data(&config, store) {
+                panic!("strict normalizer cannot be created: {}", error);
+            }
+        }
         Self {
-            script_detector: ScriptDetector::new(),
-            script_converter: ScriptConverter::new(),
-            kangxi_normalizer: KangxiNormalizer::new(),
-            variant_normalizer: VariantNormalizer::new(),
-            compatibility_normalizer: CompatibilityNormalizer::new(),
-            unicode_normalizer: UnicodeNormalizer::new(),
+            unicode_normalizer: UnicodeNormalizer::with_form(config.unicode_normalization.clone()),
+            script_converter: ScriptConverter::with_data(store)
+                .with_taiwan_vocabulary(config.taiwan_vocabulary)
+                .with_lossy_conversion(match config.lossy_conversion {
+                    // An annotation cannot be part of a fixed-point character mapping
+                    LossyConversion::Annotate if config.collapse_tables => LossyConversion::Keep,
+                    mode => mode,
+                }),
+            hanja_transliterator: HanjaTransliterator::with_data(store)
+                .with_mode(config.hanja_transliteration),
+            variant_normalizer: VariantNormalizer::with_data(store)
+                .with_spoofing_variants(config.normalize_spoofing_variants),
+            config,
+            script_detector: ScriptDetector::with_data(store),
+            kangxi_normalizer: KangxiNormalizer::with_data(store),
+            compatibility_normalizer: CompatibilityNormalizer::with_data(store),
+            protection: Vec::new(),
+            collapsed: Arc::default(),
         }
     }
 
+    /// Create a new text normalizer with the default configuration, failing if its data cannot be loaded
+    pub fn try_new() -> Result<Self> {
+        Self::try_with_config(NormalizationConfig::default())
+    }
+
+    /// Create a new text normalizer that follows the given configuration, failing if its data cannot be loaded
+    pub fn try_with_config(config: NormalizationConfig) -> Result<Self> {
+        Self::try_with_data(config, DataStore::shared())
+    }
+
+    /// Create a new text normalizer from the given data store, failing if its data cannot be loaded
+    ///
+    /// Every table used by an enabled stage and by script conversion must load.
+    /// In strict mode OpenCC must be available as well.
+    pub fn try_with_data(config: NormalizationConfig, store: &DataStore) -> Result<Self> {
+        Self::check_data(&config, store)?;
+        Ok(Self::with_data(config, store))
+    }
+
+    /// Load every table the configuration needs, returning the first failure
+    fn check_data(config: &NormalizationConfig, store: &DataStore) -> Result<()> {
+        if config.normalize_kangxi_radicals {
+            store.try_kangxi()?;
+        }
+        if config.normalize_variants {
+            store.try_variants()?;
+            if config.normalize_spoofing_variants {
+                store.try_spoofing_variants()?;
+            }
+        }
+        if config.normalize_compatibility {
+            store.try_compatibility()?;
+        }
+        if config.hanja_transliteration != HanjaTransliteration::None {
+            store.try_hangul_readings()?;
+        }
+        store.try_script_tables()?;
+        store.try_phrases()?;
+        if config.strict {
+            store.try_opencc()?;
+        }
+        Ok(())
+    }
+
+    /// Leave spans matched by the rule untouched, builder style
+    ///
+    /// Protected spans go through every stage unchanged and produce no changes.
+    pub fn with_protection(mut self, rule: ProtectionRule) -> Self {
+        self.protection.push(rule);
+        self
+    }
+
+    /// Layer a user dictionary over the built-in data of every stage, builder style
+    ///
+    /// Dictionaries added later take priority over earlier ones.
+    pub fn with_dictionary(mut self, dictionary: &UserDictionary) -> Self {
+        self.kangxi_normalizer = self.kangxi_normalizer.with_dictionary(dictionary);
+        self.variant_normalizer = self.variant_normalizer.with_dictionary(dictionary);
+        self.compatibility_normalizer = self.compatibility_normalizer.with_dictionary(dictionary);
+        self.script_converter = self.script_converter.with_dictionary(dictionary);
+        self.collapsed = Arc::default();
+        self
+    }
+
+    /// Rules selecting the spans this normalizer leaves untouched
+    pub fn protection(&self) -> &[ProtectionRule] {
+        &self.protection
+    }
+
+    /// Get the configuration this normalizer runs with
+    pub fn config(&self) -> &NormalizationConfig {
+        &self.config
+    }
+
     /// Normalize text with the specified target script
+    ///
+    /// An explicit `target_script` takes precedence over `config.target_script`;
+    /// `Script::Auto` in the configuration means no script conversion.
     pub fn normalize(&self, text: &str, target_script: Option<Script>) -> NormalizedText {
+        let target_script = self.resolve_target(target_script);
+        self.run(text, target_script)
+    }
+
+    /// Normalize text, also leaving the given byte ranges untouched
+    ///
+    /// Ranges are clipped to the text and widened to char boundaries; they add
+    /// to the spans matched by the normalizer's protection rules.
+    pub fn normalize_protected(
+        &self,
+        text: &str,
+        target_script: Option<Script>,
+        protected: &[Range<usize>],
+    ) -> NormalizedText {
+        let target_script = self.resolve_target(target_script);
+        self.run_protected(text, target_script, protected)
+    }
+
+    /// Spans of `text` that converting to the target script and back would not restore
+    ///
+    /// The target is resolved as in [`normalize`](Self::normalize). The check runs on
+    /// the text the script conversion receives between the protected spans, and
+    /// the spans are mapped back to `text`; an empty list means the conversion
+    /// can be undone.
+    pub fn round_trip_losses(
+        &self,
+        text: &str,
+        target_script: Option<Script>,
+    ) -> Vec<RoundTripLoss> {
+        let Some(target_script) = self.resolve_target(target_script) else {
+            return Vec::new();
+        };
+        let detected_script = self.script_detector.detect(text);
+        let stages = self.stages(false);
+        let spans = protected_spans(&self.protection, text, &[]);
+
+        let mut losses = Vec::new();
+        let mut free_start = 0;
+        let mut char_start = 0;
+        let end = text.len()..text.len();
+        for span in spans.iter().chain(std::iter::once(&end)) {
+            let free = &text[free_start..span.start];
+            let (current, _, offset_map) = run_stages(&stages, free);
+            let chars: Vec<char> = free.chars().collect();
+            losses.extend(
+                self.script_converter
+                    .round_trip_losses(&current, &target_script, &detected_script)
+                    .into_iter()
+                    .filter_map(|mut loss| {
+                        let span = loss.position..loss.position + loss.original.chars().count();
+                        let span = offset_map.span_to_original(span, OffsetUnit::Char)?;
+                        loss.original = chars[span.clone()].iter().collect();
+                        loss.position = char_start + span.start;
+                        Some(loss)
+                    }),
+            );
+            char_start += chars.len() + text[span.clone()].chars().count();
+            free_start = span.end;
+        }
+        losses
+    }
+
+    /// Validate text without performing conversions (for analysis)
+    pub fn validate(&self, text: &str) -> NormalizedText {
+        self.run(text, None)
+    }
+
+    /// Target script to convert to: the explicit one, else the configured one
+    pub(crate) fn resolve_target(&self, target_script: Option<Script>) -> Option<Script> {
+        target_script.or_else(|| match self.config.target_script {
+            Script::Auto => None,
+            ref script => Some(script.clone()),
+        })
+    }
+
+    /// Detect the script of the given text, with the evidence for each script
+    pub fn analyze_script(&self, text: &str) -> ScriptDetection {
+        self.script_detector.analyze(text)
+    }
+
+    /// Number of stages a change can be attributed to (enabled stages, script
+    /// conversion and hanja transliteration if enabled)
+    pub(crate) fn stage_count(&self) -> usize {
+        self.enabled_stages().len() + 1 + usize::from(self.transliterates_hanja())
+    }
+
+    fn transliterates_hanja(&self) -> bool {
+        self.config.hanja_transliteration != HanjaTransliteration::None
+    }
+
+    /// Built-in stages switched on by the configuration, in pipeline order
+    ///
+    /// With collapsed tables only Unicode normalization runs as a stage; the
+    /// tables of the others are applied by the final stage.
+    fn enabled_stages(&self) -> Vec<&dyn NormalizationStage> {
+        self.stages(self.config.collapse_tables)
+    }
+
+    fn stages(&self, collapsed: bool) -> Vec<&dyn NormalizationStage> {
+        let mut stages: Vec<&dyn NormalizationStage> = Vec::new();
+        if self.config.unicode_normalization != UnicodeNormalization::None {
+            stages.push(&self.unicode_normalizer);
+        }
+        if collapsed {
+            return stages;
+        }
+        if self.config.normalize_kangxi_radicals {
+            stages.push(&self.kangxi_normalizer);
+        }
+        if self.config.normalize_variants {
+            stages.push(&self.variant_normalizer);
+        }
+        if self.config.normalize_compatibility {
+            stages.push(&self.compatibility_normalizer);
+        }
+        stages
+    }
+
+    /// Every character the tables of the enabled stages and script conversion map from or to
+    pub(crate) fn mapped_chars(&self) -> BTreeSet<char> {
+        let mut chars: BTreeSet<char> = self.script_converter.mapped_chars().collect();
+        if self.config.normalize_kangxi_radicals {
+            chars.extend(self.kangxi_normalizer.mapped_chars());
+        }
+        if self.config.normalize_variants {
+            chars.extend(self.variant_normalizer.mapped_chars());
+        }
+        if self.config.normalize_compatibility {
+            chars.extend(self.compatibility_normalizer.mapped_chars());
+        }
+        chars
+    }
+
+    /// Output of each uncollapsed stage and of the `(target, source)` script conversion applied to `ch` alone
+    pub(crate) fn stage_outputs(
+        &self,
+        ch: char,
+        conversion: Option<&(Script, Script)>,
+    ) -> Vec<String> {
+        let text = ch.to_string();
+        let mut outputs: Vec<String> = self
+            .stages(false)
+            .iter()
+            .map(|stage| stage.normalize(&text).normalized)
+            .collect();
+        if let Some((target, source)) = conversion {
+            let (converted, _, _) = self.script_converter.convert_aligned(&text, target, source);
+            outputs.push(converted);
+        }
+        outputs
+    }
+
+    /// One pass of the uncollapsed stages, then the `(target, source)` script conversion
+    pub(crate) fn single_pass(
+        &self,
+        text: &str,
+        conversion: Option<&(Script, Script)>,
+    ) -> (String, Vec<TextChange>) {
+        let stages = self.stages(false);
+        let (mut current, mut changes, _) = run_stages(&stages, text);
+        if let Some((target, source)) = conversion {
+            let (converted, script_changes, _) = self
+                .script_converter
+                .convert_aligned(&current, target, source);
+            current = converted;
+            changes.extend(script_changes);
+        }
+        (current, changes)
+    }
+
+    /// Conversion to `target_script` from the script it converts from: Traditional for
+    /// Simplified, Simplified for Traditional and regional targets
+    pub(crate) fn forced_conversion(target_script: Option<&Script>) -> Option<(Script, Script)> {
+        match target_script? {
+            Script::SimplifiedChinese => {
+                Some((Script::SimplifiedChinese, Script::TraditionalChinese))
+            }
+            target @ (Script::TraditionalChinese
+            | Script::TraditionalTaiwan
+            | Script::TraditionalHongKong
+            | Script::JapaneseShinjitai
+            | Script::Japanese) => Some((target.clone(), Script::SimplifiedChinese)),
+            _ => None,
+        }
+    }
+
+    /// Fixed-point table for text in `detected_script` converted to `target_script`
+    fn collapsed_table(
+        &self,
+        target_script: Option<&Script>,
+        detected_script: &Script,
+    ) -> Arc<FixedPointTable> {
+        let conversion = target_script
+            .filter(|target| self.script_converter.converts(detected_script, target))
+            .map(|target| (target.clone(), detected_script.clone()));
+        let mut tables = self
+            .collapsed
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        tables
+            .entry(conversion.clone())
+            .or_insert_with(|| Arc::new(FixedPointTable::converting(self, conversion.as_ref())))
+            .clone()
+    }
+
+    /// Run the enabled normalization steps, converting script only if a target is given
+    fn run(&self, text: &str, target_script: Option<Script>) -> NormalizedText {
+        self.run_protected(text, target_script, &[])
+    }
+
+    /// Run the enabled normalization steps, leaving `protected` byte ranges untouched
+    fn run_protected(
+        &self,
+        text: &str,
+        target_script: Option<Script>,
+        protected: &[Range<usize>],
+    ) -> NormalizedText {
         let start_time = Instant::now();
 
         // Step 1: Detect script
         let detected_script = self.script_detector.detect(text);
 
-        // Step 2: Unicode normalization (NFC)
-        let unicode_result = self
-            .unicode_normalizer
-            .normalize(text, UnicodeNormalization::NFC);
-        let mut all_changes = unicode_result.changes;
-
-        // Step 3: Kangxi radical normalization
-        let kangxi_result = self.kangxi_normalizer.normalize(&unicode_result.normalized);
-        all_changes.extend(kangxi_result.changes);
-
-        // Step 4: Character variant normalization
-        let variant_result = self.variant_normalizer.normalize(&kangxi_result.normalized);
-        all_changes.extend(variant_result.changes);
-
-        // Step 5: Compatibility form normalization
-        let compatibility_result = self
-            .compatibility_normalizer
-            .normalize(&variant_result.normalized);
-        all_changes.extend(compatibility_result.changes);
-
-        // Step 6: Script conversion (if target script is specified and different from detected)
-        let final_text = if let Some(target) = target_script {
-            if detected_script != target {
-                let (converted_text, script_changes) = self.script_converter.convert(
-                    &compatibility_result.normalized,
-                    target,
-                    detected_script.clone(),
-                );
-                all_changes.extend(script_changes);
-                converted_text
-            } else {
-                compatibility_result.normalized
-            }
-        } else {
-            compatibility_result.normalized
-        };
+        // Steps 2-7
+        let spans = protected_spans(&self.protection, text, protected);
+        let (normalized, changes, offset_map) =
+            self.run_around(text, &spans, target_script.as_ref(), &detected_script);
 
         let processing_time = start_time.elapsed().as_millis() as u64;
 
+        // The offset map aligns to the original and goes with it
+        let (original, offset_map) = if self.config.preserve_original {
+            (text.to_string(), offset_map)
+        } else {
+            (String::new(), OffsetMap::default())
+        };
         NormalizedText {
-            original: text.to_string(),
-            normalized: final_text,
-            changes: all_changes,
+            original,
+            normalized,
+            changes,
             detected_script,
             processing_time_ms: processing_time,
+            offset_map,
         }
     }
 
-    /// Validate text without performing conversions (for analysis)
-    pub fn validate(&self, text: &str) -> NormalizedText {
-        let start_time = Instant::now();
+    /// Byte index after the last sentence end at which `text`, the start of a
+    /// longer input, can be split without changing the result
+    ///
+    /// The cut must not touch a protected span or follow an opener whose
+    /// closer has not arrived yet, must come before a character that starts
+    /// a new Unicode normalization run, and must be followed by at least the
+    /// longest conversion phrase, none of which crosses it. An opener more
+    /// than `max_open_span` bytes before the end of `text` protects nothing.
+    ///
+    /// Only cuts after `from`, a byte index outside protected spans, are
+    /// looked for. Without a cut, `from` is moved to where the next call,
+    /// on `text` with more input appended, has to start looking.
+    pub(crate) fn find_cut(
+        &self,
+        text: &str,
+        from: &mut usize,
+        max_open_span: usize,
+    ) -> Option<usize> {
+        let form = &self.config.unicode_normalization;
+        let lookahead = self.script_converter.longest_phrase().max(1);
+        let scanned = &text[*from..];
+        let spans: Vec<Range<usize>> = protected_spans(&self.protection, scanned, &[])
+            .into_iter()
+            .map(|span| span.start + *from..span.end + *from)
+            .collect();
+        let expired = text
+            .len()
+            .saturating_sub(max_open_span)
+            .saturating_sub(*from);
+        let open = open_span_start(&self.protection, scanned, expired)
+            .map_or(text.len(), |start| *from + start);
 
-        // Step 1: Detect script
-        let detected_script = self.script_detector.detect(text);
+        // Phrases crossing a cut may start up to `lookahead` chars before `from`
+        let back = text[..*from]
+            .char_indices()
+            .rev()
+            .nth(lookahead - 1)
+            .map_or(0, |(index, _)| index);
+        let indices: Vec<(usize, char)> = text[back..]
+            .char_indices()
+            .map(|(index, ch)| (back + index, ch))
+            .collect();
+        let chars: Vec<char> = indices.iter().map(|&(_, ch)| ch).collect();
 
-        // Step 2: Unicode normalization (NFC)
-        let unicode_result = self
-            .unicode_normalizer
-            .normalize(text, UnicodeNormalization::NFC);
-        let mut all_changes = unicode_result.changes;
+        let cut = (1..indices.len())
+            .rev()
+            .take_while(|&cut| indices[cut].0 > *from)
+            .find_map(|cut| {
+                let (end, end_char) = indices[cut - 1];
+                let (index, next) = indices[cut];
+                let safe = SEGMENT_ENDS.contains(&end_char)
+                    && index <= open
+                    && chars.len() - cut >= lookahead
+                    && is_boundary_before(next, form)
+                    && !spans
+                        .iter()
+                        .any(|span| span.start < index && span.end >= end)
+                    && !self.script_converter.crosses_phrase(&chars, cut);
+                safe.then_some(index)
+            });
+        if cut.is_none() {
+            // Cuts before the open opener, the text still waiting for its
+            // lookahead and the span holding either stay ruled out
+            let waiting = chars
+                .len()
+                .checked_sub(lookahead)
+                .map_or(*from, |cut| indices[cut].0);
+            let mut next = waiting.min(open).max(*from);
+            if let Some(span) = spans
+                .iter()
+                .find(|span| span.start < next && next < span.end)
+            {
+                next = span.start;
+            }
+            *from = next;
+        }
+        cut
+    }
 
-        // Step 3: Kangxi radical normalization (validation only)
-        let kangxi_result = self.kangxi_normalizer.normalize(&unicode_result.normalized);
-        all_changes.extend(kangxi_result.changes);
+    /// Run the normalization steps on text whose script is already known,
+    /// leaving the spans matched by the protection rules untouched
+    pub(crate) fn run_detected(
+        &self,
+        text: &str,
+        target_script: Option<&Script>,
+        detected_script: &Script,
+    ) -> (String, Vec<TextChange>, OffsetMap) {
+        let spans = protected_spans(&self.protection, text, &[]);
+        self.run_around(text, &spans, target_script, detected_script)
+    }
 
-        // Step 4: Character variant normalization (validation only)
-        let variant_result = self.variant_normalizer.normalize(&kangxi_result.normalized);
-        all_changes.extend(variant_result.changes);
+    /// Normalize the text between the protected byte spans and copy the spans as they are
+    ///
+    /// Change positions are shifted to the full text each stage received.
+    fn run_around(
+        &self,
+        text: &str,
+        spans: &[Range<usize>],
+        target_script: Option<&Script>,
+        detected_script: &Script,
+    ) -> (String, Vec<TextChange>, OffsetMap) {
+        if spans.is_empty() {
+            return self.run_unprotected(text, target_script, detected_script);
+        }
 
-        // Step 5: Compatibility form normalization (validation only)
-        let compatibility_result = self
-            .compatibility_normalizer
-            .normalize(&variant_result.normalized);
-        all_changes.extend(compatibility_result.changes);
+        let mut normalized = String::with_capacity(text.len());
+        let mut all_changes = Vec::new();
+        let mut lengths = Vec::new();
+        let mut stage_offsets = vec![0; self.stage_count()];
+        let mut free_start = 0;
 
-        // No script conversion in validation mode
-        let final_text = compatibility_result.normalized;
+        let end = text.len()..text.len();
+        for span in spans.iter().chain(std::iter::once(&end)) {
+            let free = &text[free_start..span.start];
+            if !free.is_empty() {
+                let (output, changes, offset_map) =
+                    self.run_unprotected(free, target_script, detected_script);
+                let starts = advance_stage_offsets(&mut stage_offsets, free, &changes);
+                all_changes.extend(changes.into_iter().map(|mut change| {
+                    change.position += starts[change.stage];
+                    change
+                }));
+                lengths.extend(
+                    offset_map
+                        .segments()
+                        .iter()
+                        .map(|segment| (segment.original.len(), segment.normalized.len())),
+                );
+                normalized.push_str(&output);
+            }
 
-        let processing_time = start_time.elapsed().as_millis() as u64;
+            let kept = &text[span.clone()];
+            let kept_chars = kept.chars().count();
+            for offset in stage_offsets.iter_mut() {
+                *offset += kept_chars;
+            }
+            lengths.extend(std::iter::repeat_n((1, 1), kept_chars));
+            normalized.push_str(kept);
+            free_start = span.end;
+        }
 
-        NormalizedText {
-            original: text.to_string(),
-            normalized: final_text,
-            changes: all_changes,
-            detected_script,
-            processing_time_ms: processing_time,
+        // Segments report their stages in turn; inverting needs them grouped by stage
+        all_changes.sort_by_key(|change| (change.stage, change.position));
+        let offset_map = OffsetMap::from_lengths(text, &normalized, lengths);
+        (normalized, all_changes, offset_map)
+    }
+
+    /// Run the normalization steps on the whole text
+    fn run_unprotected(
+        &self,
+        text: &str,
+        target_script: Option<&Script>,
+        detected_script: &Script,
+    ) -> (String, Vec<TextChange>, OffsetMap) {
+        // Steps 2-5: Unicode, Kangxi radical, variant and compatibility normalization
+        let stages = self.enabled_stages();
+        let (mut current, mut all_changes, mut offset_map) = run_stages(&stages, text);
+
+        if self.config.collapse_tables {
+            // Steps 3-6 at once, through the fixed-point table
+            let table = self.collapsed_table(target_script, detected_script);
+            let (collapsed, changes, collapsed_map) = table.apply(&current);
+            offset_map = offset_map.compose(&collapsed_map);
+            current = collapsed;
+            all_changes.extend(changes.into_iter().map(|mut change| {
+                change.stage = stages.len();
+                change
+            }));
+        } else if let Some(target) = target_script {
+            // Step 6: Script conversion, sentence by sentence for mixed-script text
+            let (converted_text, script_changes, script_map) = self
+                .script_converter
+                .convert_aligned(&current, target, detected_script);
+            offset_map = offset_map.compose(&script_map);
+            current = converted_text;
+            all_changes.extend(script_changes.into_iter().map(|mut change| {
+                change.stage = stages.len();
+                change
+            }));
+        }
+
+        // Step 7: Hanja → Hangul transliteration
+        if self.transliterates_hanja() {
+            let result = self.hanja_transliterator.normalize(&current);
+            offset_map = offset_map.compose(&result.offset_map);
+            current = result.normalized;
+            all_changes.extend(result.changes.into_iter().map(|mut change| {
+                change.stage = stages.len() + 1;
+                change
+            }));
         }
+
+        (current, all_changes, offset_map)
+    }
+}
+
+// A normalizer is meant to be shared between threads
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<TextNormalizer>();
+};
+
+impl Default for TextNormalizer {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::alignment::OffsetUnit;
 
     #[test]
     fn test_basic_normalization() {
@@ -174,4 +679,351 @@ mod tests {
         assert_eq!(result.normalized, "方面問題");
         assert!(!result.changes.is_empty());
     }
+
+    #[test]
+    fn test_offset_map_projects_to_original() {
+        let normalizer = TextNormalizer::new();
+        let original = "e\u{0301}⽅⾯問題";
+        let result = normalizer.normalize(original, None);
+        assert_eq!(result.normalized, "é方面問題");
+
+        // "問" is at byte 8 of the normalized text
+        let span = result.offset_map.to_original(8, OffsetUnit::Byte).unwrap();
+        assert_eq!(&original[span], "問");
+
+        // "é" maps back to both chars it was composed from
+        let span = result
+            .offset_map
+            .span_to_original(0..1, OffsetUnit::Utf16)
+            .unwrap();
+        assert_eq!(span, 0..2);
+
+        let span = result
+            .offset_map
+            .to_normalized(3, OffsetUnit::Byte)
+            .unwrap();
+        assert_eq!(&result.normalized[span], "方");
+    }
+
+    #[test]
+    fn test_config_disables_stages() {
+        let config = NormalizationConfig::default().with_kangxi_radicals(false);
+        let normalizer = TextNormalizer::with_config(config);
+        let result = normalizer.normalize("⽅⾯問題", None);
+
+        assert_eq!(result.normalized, "⽅⾯問題");
+        assert!(result.changes.is_empty());
+    }
+
+    #[test]
+    fn test_config_unicode_form() {
+        let config =
+            NormalizationConfig::default().with_unicode_normalization(UnicodeNormalization::NFD);
+        let normalizer = TextNormalizer::with_config(config);
+        let result = normalizer.normalize("é", None);
+
+        assert_eq!(result.normalized, "e\u{0301}");
+
+        let config =
+            NormalizationConfig::default().with_unicode_normalization(UnicodeNormalization::None);
+        let normalizer = TextNormalizer::with_config(config);
+        let result = normalizer.normalize("e\u{0301}", None);
+
+        assert_eq!(result.normalized, "e\u{0301}");
+    }
+
+    #[test]
+    fn test_config_target_script() {
+        let config = NormalizationConfig::default().with_target_script(Script::SimplifiedChinese);
+        let normalizer = TextNormalizer::with_config(config);
+
+        let result = normalizer.normalize("這個藥", None);
+        assert_ne!(result.normalized, "這個藥");
+
+        // An explicit target overrides the configured one
+        let result = normalizer.normalize("這個藥", Some(Script::TraditionalChinese));
+        assert_eq!(result.normalized, "這個藥");
+    }
+
+    #[test]
+    fn test_hanja_transliteration_runs_last() {
+        use crate::data::{DataFile, DataSource};
+        use crate::reversible::apply_inverse;
+
+        let store = DataStore::new(DataSource::memory([
+            (
+                DataFile::CompatibilityVariants,
+                r#"{"\uF9E1": "李"}"#.as_bytes().to_vec(),
+            ),
+            (
+                DataFile::HangulReadings,
+                r#"{"李": "리", "舜": "순", "臣": "신"}"#.as_bytes().to_vec(),
+            ),
+        ]));
+        let config = NormalizationConfig::default()
+            .with_hanja_transliteration(HanjaTransliteration::HangulWithHanja);
+        let normalizer = TextNormalizer::with_data(config, &store);
+
+        // The compatibility ideograph is normalized before it is read
+        let original = "\u{F9E1}舜臣 장군";
+        let result = normalizer.normalize(original, None);
+        assert_eq!(result.normalized, "이순신(李舜臣) 장군");
+        assert_eq!(
+            apply_inverse(&result.normalized, &result.changes).unwrap(),
+            original
+        );
+    }
+
+    #[test]
+    fn test_round_trip_losses_refer_to_the_input() {
+        use crate::data::{DataFile, DataSource};
+
+        let store = DataStore::new(DataSource::memory([
+            (
+                DataFile::TraditionalToSimplified,
+                r#"{"髮": "发", "發": "发", "這": "这"}"#.as_bytes().to_vec(),
+            ),
+            (
+                DataFile::SimplifiedToTraditional,
+                r#"{"发": ["發", "髮"], "这": "這"}"#.as_bytes().to_vec(),
+            ),
+        ]));
+        let normalizer = TextNormalizer::with_data(NormalizationConfig::default(), &store);
+
+        // NFC composes "e\u{0301}" into one char before the conversion sees the text
+        let losses = normalizer.round_trip_losses("e\u{0301}這髮", Some(Script::SimplifiedChinese));
+        assert_eq!(losses.len(), 1);
+        assert_eq!((losses[0].position, losses[0].original.as_str()), (3, "髮"));
+
+        assert!(normalizer.round_trip_losses("這髮", None).is_empty());
+
+        // Protected spans are neither converted nor reported
+        let normalizer = normalizer.with_protection(ProtectionRule::Urls);
+        let losses = normalizer.round_trip_losses(
+            "髮 https://x.com/髮 e\u{0301}髮",
+            Some(Script::SimplifiedChinese),
+        );
+        let found: Vec<_> = losses
+            .iter()
+            .map(|loss| (loss.position, loss.original.as_str()))
+            .collect();
+        assert_eq!(found, [(0, "髮"), (20, "髮")]);
+    }
+
+    #[test]
+    fn test_mixed_script_document() {
+        use crate::data::{DataFile, DataSource};
+
+        let store = DataStore::new(DataSource::memory([
+            (
+                DataFile::TraditionalToSimplified,
+                r#"{"這": "这", "個": "个", "國": "国"}"#.as_bytes().to_vec(),
+            ),
+            (
+                DataFile::SimplifiedToTraditional,
+                r#"{"这": "這", "个": "個", "国": "國"}"#.as_bytes().to_vec(),
+            ),
+        ]));
+        let text = "這個國家很大。這個國家很老。这个国家很新。";
+
+        // Mostly Traditional, so the document is detected as Traditional
+        for collapse_tables in [false, true] {
+            let config = NormalizationConfig::default().with_collapsed_tables(collapse_tables);
+            let normalizer = TextNormalizer::with_data(config, &store);
+            let result = normalizer.normalize(text, Some(Script::TraditionalChinese));
+
+            assert_eq!(result.detected_script, Script::TraditionalChinese);
+            assert_eq!(
+                result.normalized,
+                "這個國家很大。這個國家很老。這個國家很新。"
+            );
+            assert_eq!(result.changes.len(), 3);
+            assert_eq!(result.changes[0].position, 14);
+        }
+    }
+
+    #[test]
+    fn test_custom_data_source() {
+        use crate::data::{DataFile, DataSource};
+
+        let store = DataStore::new(DataSource::memory([(
+            DataFile::KangxiRadicals,
+            r#"{"⾯": "麵"}"#.as_bytes().to_vec(),
+        )]));
+        let normalizer = TextNormalizer::with_data(NormalizationConfig::default(), &store);
+
+        assert_eq!(normalizer.normalize("⽅⾯", None).normalized, "⽅麵");
+    }
+
+    #[test]
+    fn test_try_with_data_reports_missing_table() {
+        use crate::data::{DataFile, DataSource};
+        use crate::error::Error;
+
+        let store = DataStore::new(DataSource::memory([(
+            DataFile::KangxiRadicals,
+            r#"{"⾯": "麵"}"#.as_bytes().to_vec(),
+        )]));
+
+        let result = TextNormalizer::try_with_data(NormalizationConfig::default(), &store);
+        assert!(matches!(
+            result,
+            Err(Error::DataNotFound {
+                file: DataFile::SemanticVariants,
+                ..
+            })
+        ));
+
+        // Disabled stages do not need their tables
+        let config = NormalizationConfig::default()
+            .with_variants(false)
+            .with_compatibility(false);
+        let result = TextNormalizer::try_with_data(config, &store);
+        assert!(matches!(
+            result,
+            Err(Error::DataNotFound {
+                file: DataFile::TraditionalToSimplified,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    #[should_panic(expected = "strict normalizer")]
+    fn test_strict_mode_refuses_degraded_data() {
+        let store = DataStore::new(crate::data::DataSource::directory("no/such/dir"));
+        let config = NormalizationConfig::default().with_strict(true);
+        TextNormalizer::with_data(config, &store);
+    }
+
+    #[test]
+    fn test_presets_produce_documented_change_types() {
+        use crate::types::{ChangeType, Preset};
+
+        let text = "Ｆｕｌｌ ⽅⾯ 敎育 這個藥 e\u{0301} \u{F900} 妺";
+        for preset in Preset::ALL {
+            let normalizer = TextNormalizer::with_config(preset.into());
+            for change in normalizer.normalize(text, None).changes {
+                assert!(
+                    preset.change_types().contains(&change.change_type),
+                    "{:?} produced {:?}",
+                    preset,
+                    change.change_type
+                );
+            }
+        }
+
+        let security = TextNormalizer::with_config(Preset::Security.into());
+        assert_eq!(security.normalize("Ｘ妺", None).normalized, "X妹");
+        let changes = security.normalize(" ｘ⽅", None).changes;
+        let changed: Vec<_> = changes
+            .iter()
+            .map(|change| (change.original_text.as_str(), change.change_type.clone()))
+            .collect();
+        assert_eq!(
+            changed,
+            [
+                ("ｘ", ChangeType::CompatibilityForm),
+                ("⽅", ChangeType::KangxiRadical)
+            ]
+        );
+
+        let archival = TextNormalizer::with_config(Preset::Archival.into());
+        assert_eq!(
+            archival.normalize("e\u{0301}⽅", None).normalized,
+            "e\u{0301}方"
+        );
+        assert_eq!(Preset::from_name("Search-Index"), Some(Preset::SearchIndex));
+    }
+
+    #[test]
+    fn test_protected_spans_are_untouched() {
+        let normalizer = TextNormalizer::new().with_protection(ProtectionRule::Urls);
+        let text = "⽅ https://⾯.example/⽅ ⾯";
+        let result = normalizer.normalize(text, None);
+
+        assert_eq!(result.normalized, "方 https://⾯.example/⽅ 面");
+        let positions: Vec<usize> = result
+            .changes
+            .iter()
+            .map(|change| change.position)
+            .collect();
+        assert_eq!(positions, [0, 22]);
+        assert!(result.verify_round_trip().is_ok());
+
+        let span = result.offset_map.to_original(22, OffsetUnit::Char).unwrap();
+        assert_eq!(span, 22..23);
+
+        // Explicit ranges add to the rules
+        let result = normalizer.normalize_protected(text, None, std::slice::from_ref(&(0..3)));
+        assert_eq!(result.normalized, "⽅ https://⾯.example/⽅ 面");
+        assert_eq!(result.changes.len(), 1);
+
+        // Composition shortens the text before the later stages on both sides
+        let text = "e\u{0301}⽅ https://x.com ⽅e\u{0301}⽅";
+        let result = normalizer.normalize(text, None);
+        assert_eq!(result.normalized, "é方 https://x.com 方é方");
+        assert!(result
+            .changes
+            .windows(2)
+            .all(|pair| (pair[0].stage, pair[0].position) <= (pair[1].stage, pair[1].position)));
+        assert_eq!(result.denormalize().unwrap(), text);
+    }
+
+    #[test]
+    fn test_user_dictionaries() {
+        use crate::dictionary::{DictionaryStage, UserDictionary};
+
+        let house = UserDictionary::new("house")
+            .with_mapping(DictionaryStage::Variant, '裏', '裡')
+            .with_suppressed(DictionaryStage::Kangxi, '⽅')
+            .with_mapping(DictionaryStage::TraditionalToSimplified, '書', 'X');
+        let normalizer = TextNormalizer::new().with_dictionary(&house);
+
+        let result = normalizer.normalize("⽅⾯裏", None);
+        assert_eq!(result.normalized, "⽅面裡");
+        assert!(result.changes[1].reason.ends_with("(dictionary: house)"));
+
+        let result = normalizer.normalize("這本書", Some(Script::SimplifiedChinese));
+        assert!(result.normalized.ends_with('X'));
+
+        // A later dictionary overrides an earlier one
+        let later = UserDictionary::new("later").with_mapping(DictionaryStage::Variant, '裏', '里');
+        let result = normalizer.with_dictionary(&later).normalize("裏", None);
+        assert_eq!(result.normalized, "里");
+    }
+
+    #[test]
+    fn test_shared_between_threads() {
+        let normalizer = TextNormalizer::new();
+        let results: Vec<String> = std::thread::scope(|scope| {
+            let handles: Vec<_> = ["⽅⾯問題", "硏究敎育"]
+                .into_iter()
+                .map(|text| {
+                    let normalizer = normalizer.clone();
+                    scope.spawn(move || normalizer.normalize(text, None).normalized)
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .collect()
+        });
+
+        assert_eq!(results, ["方面問題", "硏究教育"]);
+    }
+
+    #[test]
+    fn test_config_preserve_original() {
+        let config = NormalizationConfig::default().with_preserve_original(false);
+        let normalizer = TextNormalizer::with_config(config);
+        let result = normalizer.normalize("⽅⾯問題", None);
+
+        assert!(result.original.is_empty());
+        assert_eq!(result.normalized, "方面問題");
+        assert!(result.offset_map.is_empty());
+        let json = serde_json::to_value(&result).unwrap();
+        assert!(json.get("offset_map").is_none());
+        assert_eq!(result.denormalize().unwrap(), "⽅⾯問題");
+    }
 }
@@ -1,57 +1,99 @@
 //! Unicode normalization
 
+use crate::alignment::OffsetMap;
 use crate::types::{ChangeType, NormalizedText, TextChange, UnicodeNormalization};
+use unicode_normalization::char::canonical_combining_class;
 use unicode_normalization::UnicodeNormalization as UnicodeNorm;
+use unicode_normalization::{
+    is_nfc_quick, is_nfd_quick, is_nfkc_quick, is_nfkd_quick, IsNormalized,
+};
 
 /// Normalizer for Unicode normalization forms
-pub struct UnicodeNormalizer;
+#[derive(Clone)]
+pub struct UnicodeNormalizer {
+    form: UnicodeNormalization,
+}
 
 impl UnicodeNormalizer {
-    /// Create a new Unicode normalizer
+    /// Create a new Unicode normalizer (NFC when used as a pipeline stage)
     pub fn new() -> Self {
-        Self
+        Self::with_form(UnicodeNormalization::NFC)
+    }
+
+    /// Create a Unicode normalizer whose pipeline stage applies the given form
+    pub fn with_form(form: UnicodeNormalization) -> Self {
+        Self { form }
+    }
+
+    /// Get the form applied when used as a pipeline stage
+    pub fn form(&self) -> UnicodeNormalization {
+        self.form.clone()
     }
 
     /// Normalize text using the specified Unicode normalization form
     pub fn normalize(&self, text: &str, form: UnicodeNormalization) -> NormalizedText {
-        let normalized = match form {
-            UnicodeNormalization::NFC => text.nfc().collect::<String>(),
-            UnicodeNormalization::NFD => text.nfd().collect::<String>(),
-            UnicodeNormalization::NFKC => text.nfkc().collect::<String>(),
-            UnicodeNormalization::NFKD => text.nfkd().collect::<String>(),
-            UnicodeNormalization::None => text.to_string(),
-        };
-
-        let changes = if normalized != text {
-            // Calculate changes by comparing character by character
-            let mut changes = Vec::new();
-            let original_chars: Vec<char> = text.chars().collect();
-            let normalized_chars: Vec<char> = normalized.chars().collect();
-
-            let max_len = original_chars.len().max(normalized_chars.len());
-
-            for i in 0..max_len {
-                let original_char = original_chars.get(i).copied();
-                let normalized_char = normalized_chars.get(i).copied();
-
-                if original_char != normalized_char {
-                    if let (Some(orig), Some(norm)) = (original_char, normalized_char) {
-                        changes.push(TextChange {
-                            position: i,
-                            original_char: orig,
-                            normalized_char: norm,
-                            change_type: ChangeType::UnicodeNormalization,
-                            reason: format!("Unicode normalization {} → {}", orig, norm),
-                        });
-                    }
+        let normalized = apply_form(text, &form);
+
+        if normalized == text {
+            return NormalizedText {
+                offset_map: OffsetMap::identity(text),
+                original: text.to_string(),
+                normalized,
+                changes: Vec::new(),
+                detected_script: crate::types::Script::Auto,
+                processing_time_ms: 0,
+            };
+        }
+
+        // Compare run by run so that compositions and decompositions are
+        // recorded as whole spans at their position in the input; a run whose
+        // characters normalize on their own is recorded char by char
+        let mut changes = Vec::new();
+        let mut lengths = Vec::new();
+        let mut pos = 0;
+        for run in normalization_runs(text, &form) {
+            let normalized_run = apply_form(run, &form);
+            let run_len = run.chars().count();
+            if normalized_run == run {
+                lengths.push((run_len, run_len));
+                pos += run_len;
+                continue;
+            }
+
+            let normalized_chars: Vec<String> = run
+                .chars()
+                .map(|ch| apply_form(ch.encode_utf8(&mut [0; 4]), &form))
+                .collect();
+            if normalized_chars.concat() != normalized_run {
+                changes.push(TextChange::replacement(
+                    pos,
+                    run.to_string(),
+                    normalized_run.clone(),
+                    ChangeType::UnicodeNormalization,
+                    format!("Unicode normalization {} → {}", run, normalized_run),
+                ));
+                lengths.push((run_len, normalized_run.chars().count()));
+                pos += run_len;
+                continue;
+            }
+            for (ch, normalized_char) in run.chars().zip(normalized_chars) {
+                if !normalized_char.chars().eq(std::iter::once(ch)) {
+                    changes.push(TextChange::replacement(
+                        pos,
+                        ch.to_string(),
+                        normalized_char.clone(),
+                        change_type(ch, &form),
+                        format!("Unicode normalization {} → {}", ch, normalized_char),
+                    ));
                 }
+                lengths.push((1, normalized_char.chars().count()));
+                pos += 1;
             }
-            changes
-        } else {
-            Vec::new()
-        };
+        }
+        let offset_map = OffsetMap::from_lengths(text, &normalized, lengths);
 
         NormalizedText {
+            offset_map,
             original: text.to_string(),
             normalized,
             changes,
@@ -61,6 +103,74 @@ impl UnicodeNormalizer {
     }
 }
 
+/// Apply a normalization form to the text
+fn apply_form(text: &str, form: &UnicodeNormalization) -> String {
+    match form {
+        UnicodeNormalization::NFC => text.nfc().collect::<String>(),
+        UnicodeNormalization::NFD => text.nfd().collect::<String>(),
+        UnicodeNormalization::NFKC => text.nfkc().collect::<String>(),
+        UnicodeNormalization::NFKD => text.nfkd().collect::<String>(),
+        UnicodeNormalization::None => text.to_string(),
+    }
+}
+
+/// Kind of change a normalization form makes to `ch` on its own
+///
+/// The compatibility forms rewrite Kangxi radicals (⽅ → 方) and other
+/// compatibility characters (ｘ → x) that canonical normalization leaves alone.
+fn change_type(ch: char, form: &UnicodeNormalization) -> ChangeType {
+    let compatibility = matches!(
+        form,
+        UnicodeNormalization::NFKC | UnicodeNormalization::NFKD
+    ) && std::iter::once(ch).nfd().eq(std::iter::once(ch));
+    match ch {
+        '\u{2E80}'..='\u{2EFF}' | '\u{2F00}'..='\u{2FDF}' if compatibility => {
+            ChangeType::KangxiRadical
+        }
+        _ if compatibility => ChangeType::CompatibilityForm,
+        _ => ChangeType::UnicodeNormalization,
+    }
+}
+
+/// Whether text can be split right before `ch` without changing the result of
+/// normalizing it: `ch` is a starter that the form leaves alone and that never
+/// composes with a preceding character
+pub(crate) fn is_boundary_before(ch: char, form: &UnicodeNormalization) -> bool {
+    if canonical_combining_class(ch) != 0 {
+        return false;
+    }
+    let ch = std::iter::once(ch);
+    let quick_check = match form {
+        UnicodeNormalization::NFC => is_nfc_quick(ch),
+        UnicodeNormalization::NFD => is_nfd_quick(ch),
+        UnicodeNormalization::NFKC => is_nfkc_quick(ch),
+        UnicodeNormalization::NFKD => is_nfkd_quick(ch),
+        UnicodeNormalization::None => IsNormalized::Yes,
+    };
+    quick_check == IsNormalized::Yes
+}
+
+/// Split text into runs that normalize independently of each other
+fn normalization_runs<'a>(
+    text: &'a str,
+    form: &'a UnicodeNormalization,
+) -> impl Iterator<Item = &'a str> + 'a {
+    let mut rest = text;
+    std::iter::from_fn(move || {
+        if rest.is_empty() {
+            return None;
+        }
+        let split = rest
+            .char_indices()
+            .skip(1)
+            .find(|&(_, ch)| is_boundary_before(ch, form))
+            .map_or(rest.len(), |(index, _)| index);
+        let (run, tail) = rest.split_at(split);
+        rest = tail;
+        Some(run)
+    })
+}
+
 impl Default for UnicodeNormalizer {
     fn default() -> Self {
         Self::new()
@@ -70,6 +180,7 @@ impl Default for UnicodeNormalizer {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::alignment::OffsetUnit;
 
     #[test]
     fn test_nfc_normalization() {
@@ -77,7 +188,9 @@ mod tests {
         let result = normalizer.normalize("e\u{0301}", UnicodeNormalization::NFC);
 
         assert_eq!(result.normalized, "é");
-        assert!(!result.changes.is_empty());
+        assert_eq!(result.changes.len(), 1);
+        assert_eq!(result.changes[0].original_text, "e\u{0301}");
+        assert_eq!(result.changes[0].normalized_text, "é");
     }
 
     #[test]
@@ -89,6 +202,57 @@ mod tests {
         assert!(!result.changes.is_empty());
     }
 
+    #[test]
+    fn test_offset_map_after_composition() {
+        let normalizer = UnicodeNormalizer::new();
+        let result = normalizer.normalize("a\u{0301}b⽅", UnicodeNormalization::NFC);
+
+        assert_eq!(result.normalized, "áb⽅");
+        assert_eq!(
+            result.offset_map.to_original(0, OffsetUnit::Char),
+            Some(0..2)
+        );
+        assert_eq!(
+            result.offset_map.to_original(1, OffsetUnit::Char),
+            Some(2..3)
+        );
+        assert_eq!(
+            result.offset_map.to_normalized(4, OffsetUnit::Byte),
+            Some(3..6)
+        );
+    }
+
+    #[test]
+    fn test_compatibility_changes_per_character() {
+        let normalizer = UnicodeNormalizer::new();
+        let result = normalizer.normalize(" ｘ⽅e\u{0301}", UnicodeNormalization::NFKC);
+
+        assert_eq!(result.normalized, " x方é");
+        let changes: Vec<_> = result
+            .changes
+            .iter()
+            .map(|change| {
+                (
+                    change.position,
+                    change.original_text.as_str(),
+                    change.change_type.clone(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            changes,
+            [
+                (1, "ｘ", ChangeType::CompatibilityForm),
+                (2, "⽅", ChangeType::KangxiRadical),
+                (3, "e\u{0301}", ChangeType::UnicodeNormalization),
+            ]
+        );
+        assert_eq!(
+            result.offset_map.to_original(2, OffsetUnit::Char),
+            Some(2..3)
+        );
+    }
+
     #[test]
     fn test_no_normalization() {
         let normalizer = UnicodeNormalizer::new();
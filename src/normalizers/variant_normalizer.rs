@@ -1,26 +1,69 @@
 //! Character variant normalization
 
+use crate::alignment::OffsetMap;
+use crate::data::{CharTable, DataFile, DataSource, DataStore};
+use crate::diagnostics::LoadReport;
+use crate::dictionary::{DictionaryStage, Overrides, UserDictionary};
+use crate::error::Result;
 use crate::types::{
     ChangeType, NormalizedText, TextChange, VariantMapping, VariantMappings, VariantType,
 };
-use serde_json;
-use std::collections::HashMap;
-use std::fs;
-use std::path::Path;
+use std::sync::Arc;
 
 /// Enhanced normalizer for character variants with confidence-based selection
+#[derive(Clone)]
 pub struct VariantNormalizer {
-    variant_mappings: VariantMappings,
+    variant_mappings: Arc<VariantMappings>,
+    spoofing_mappings: Arc<CharTable>,
+    fold_spoofing: bool,
+    overrides: Arc<Overrides>,
 }
 
 impl VariantNormalizer {
-    /// Create a new variant normalizer with enhanced mappings
+    /// Create a new variant normalizer backed by the shared data store
     pub fn new() -> Self {
+        Self::with_data(DataStore::shared())
+    }
+
+    /// Create a new variant normalizer with the tables of the given data store
+    pub fn with_data(store: &DataStore) -> Self {
         Self {
-            variant_mappings: Self::load_enhanced_variant_mappings(),
+            variant_mappings: store.variants(),
+            spoofing_mappings: store.spoofing_variants(),
+            fold_spoofing: false,
+            overrides: Arc::default(),
         }
     }
 
+    /// Also fold look-alike characters (spoofing variants) to one form, builder style
+    pub fn with_spoofing_variants(mut self, enabled: bool) -> Self {
+        self.fold_spoofing = enabled;
+        self
+    }
+
+    /// Layer a user dictionary over the built-in mappings, builder style
+    ///
+    /// Dictionary entries are applied regardless of confidence.
+    pub fn with_dictionary(mut self, dictionary: &UserDictionary) -> Self {
+        Arc::make_mut(&mut self.overrides).layer(dictionary, DictionaryStage::Variant);
+        self
+    }
+
+    /// Every character the mappings and dictionaries map from or to
+    pub(crate) fn mapped_chars(&self) -> impl Iterator<Item = char> + '_ {
+        self.variant_mappings
+            .mappings
+            .iter()
+            .flat_map(|mapping| [mapping.source, mapping.target])
+            .chain(
+                self.spoofing_mappings
+                    .iter()
+                    .filter(|_| self.fold_spoofing)
+                    .flat_map(|(&source, &target)| [source, target]),
+            )
+            .chain(self.overrides.chars())
+    }
+
     /// Normalize character variants in the given text with confidence-based selection
     pub fn normalize(&self, text: &str) -> NormalizedText {
         let chars: Vec<char> = text.chars().collect();
@@ -28,7 +71,34 @@ impl VariantNormalizer {
         let mut changes = Vec::new();
 
         for (pos, &ch) in chars.iter().enumerate() {
-            if let Some(best_mapping) = self.variant_mappings.get_best_mapping(ch) {
+            if let Some(entry) = self.overrides.get(ch) {
+                match entry.target {
+                    Some(normalized_char) if normalized_char != ch => {
+                        result.push(normalized_char);
+                        changes.push(TextChange::new(
+                            pos,
+                            ch,
+                            normalized_char,
+                            ChangeType::SemanticVariant,
+                            entry.reason(format!("Variant {} → {}", ch, normalized_char)),
+                        ));
+                    }
+                    _ => result.push(ch),
+                }
+            } else if let Some(&normalized_char) = self
+                .spoofing_mappings
+                .get(&ch)
+                .filter(|_| self.fold_spoofing)
+            {
+                result.push(normalized_char);
+                changes.push(TextChange::new(
+                    pos,
+                    ch,
+                    normalized_char,
+                    ChangeType::SpoofingVariant,
+                    format!("Spoofing variant {} → {}", ch, normalized_char),
+                ));
+            } else if let Some(best_mapping) = self.variant_mappings.get_best_mapping(ch) {
                 // Apply smart confidence filtering
                 if self.should_apply_mapping(best_mapping) {
                     let normalized_char = best_mapping.target;
@@ -55,13 +125,13 @@ impl VariantNormalizer {
                         }
                     );
 
-                    changes.push(TextChange {
-                        position: pos,
-                        original_char: ch,
+                    changes.push(TextChange::new(
+                        pos,
+                        ch,
                         normalized_char,
                         change_type,
                         reason,
-                    });
+                    ));
                 } else {
                     result.push(ch);
                 }
@@ -71,6 +141,7 @@ impl VariantNormalizer {
         }
 
         NormalizedText {
+            offset_map: OffsetMap::one_to_one(text, &result),
             original: text.to_string(),
             normalized: result,
             changes,
@@ -80,38 +151,37 @@ impl VariantNormalizer {
     }
 
     /// Load variant mappings from the new clean normalization structure
-    fn load_enhanced_variant_mappings() -> VariantMappings {
+    pub(crate) fn load_enhanced_variant_mappings(
+        data_source: &DataSource,
+        report: &mut LoadReport,
+    ) -> Result<VariantMappings> {
         let mut variant_mappings = VariantMappings::new();
-        let mut total_loaded = 0;
 
         // Load semantic variants
-        let semantic_path = Path::new("data/processed/normalization/semantic_variants.json");
-        if let Ok(contents) = fs::read_to_string(semantic_path) {
-            if let Ok(semantic_map) = serde_json::from_str::<HashMap<String, String>>(&contents) {
-                for (source, target) in semantic_map {
-                    if let (Some(source_char), Some(target_char)) =
-                        (source.chars().next(), target.chars().next())
-                    {
-                        let mapping = VariantMapping {
-                            source: source_char,
-                            target: target_char,
-                            variant_type: crate::types::VariantType::Semantic,
-                            confidence: 0.9,
-                            bidirectional: false,
-                            source_info: "Unihan".to_string(),
-                        };
-                        variant_mappings.add_mapping(mapping);
-                        total_loaded += 1;
-                    }
-                }
-            }
+        for (source, target) in data_source.load_char_pairs(DataFile::SemanticVariants, report)? {
+            let mapping = VariantMapping {
+                source,
+                target,
+                variant_type: crate::types::VariantType::Semantic,
+                confidence: 0.9,
+                bidirectional: false,
+                source_info: "Unihan".to_string(),
+            };
+            variant_mappings.add_mapping(mapping);
         }
 
-        println!(
-            "Loaded {} semantic variant mappings from clean data",
-            total_loaded
-        );
-        variant_mappings
+        Ok(variant_mappings)
+    }
+
+    /// Load the look-alike character → folded form table
+    pub(crate) fn load_spoofing_variants(
+        data_source: &DataSource,
+        report: &mut LoadReport,
+    ) -> Result<CharTable> {
+        Ok(data_source
+            .load_char_pairs(DataFile::SpoofingVariants, report)?
+            .into_iter()
+            .collect())
     }
 
     /// Get all available mappings for a character (for debugging/analysis)
@@ -197,6 +267,16 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_spoofing_variants_fold_only_when_enabled() {
+        let normalizer = VariantNormalizer::new();
+        assert_eq!(normalizer.normalize("妺凐").normalized, "妺凐");
+
+        let result = normalizer.with_spoofing_variants(true).normalize("妺凐");
+        assert_eq!(result.normalized, "妹湮");
+        assert_eq!(result.changes[1].change_type, ChangeType::SpoofingVariant);
+    }
+
     #[test]
     fn test_no_change_for_unmapped_chars() {
         let normalizer = VariantNormalizer::new();
@@ -0,0 +1,285 @@
+//! Protected spans that normalization leaves untouched
+//!
+//! A [`TextNormalizer`](crate::TextNormalizer) given [`ProtectionRule`]s finds
+//! the matching spans of every input and passes them through all stages
+//! unchanged: they produce no [`TextChange`](crate::types::TextChange) and map
+//! one-to-one in the offset map. Explicit byte ranges can be protected per
+//! call with [`TextNormalizer::normalize_protected`](crate::TextNormalizer::normalize_protected).
+
+use regex::Regex;
+use std::fmt;
+use std::ops::Range;
+use std::sync::{Arc, LazyLock};
+
+/// URLs with a scheme or a `www.` prefix; hostnames and paths may be non-ASCII (IDN)
+static URL: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r#"(?i)(?:[a-z][a-z0-9+.\-]*://|www\.)[^\s<>"'，。、；：！？「」『』（）【】《》]*[^\s<>"'，。、；：！？「」『』（）【】《》.,;:!?)\]]"#,
+    )
+    .expect("valid URL pattern")
+});
+
+/// Email addresses with an ASCII local part and a possibly non-ASCII domain
+static EMAIL: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"[A-Za-z0-9._%+\-]+@[\p{L}\p{N}\-]+(?:\.[\p{L}\p{N}\-]+)+")
+        .expect("valid email pattern")
+});
+
+/// Fenced code blocks and inline code spans in backticks
+static CODE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?s)```.*?```|`[^`\n]+`").expect("valid code pattern"));
+
+/// Rule selecting spans of the input to leave untouched
+#[derive(Clone)]
+pub enum ProtectionRule {
+    /// URLs such as `https://例子.中国/路径` or `www.example.com`
+    Urls,
+    /// Email addresses such as `user@例子.中国`
+    Emails,
+    /// Markdown-style code: fenced blocks and inline spans in backticks
+    InlineCode,
+    /// Text between an opening and a closing marker, markers included
+    Marked { open: String, close: String },
+    /// Every match of a regular expression
+    Pattern(Regex),
+    /// Every run of characters the predicate accepts
+    Predicate(Arc<dyn Fn(char) -> bool + Send + Sync>),
+}
+
+impl ProtectionRule {
+    /// Protect text between `open` and `close` (e.g. `⟦` and `⟧`)
+    pub fn marked<S: Into<String>>(open: S, close: S) -> Self {
+        ProtectionRule::Marked {
+            open: open.into(),
+            close: close.into(),
+        }
+    }
+
+    /// Protect every run of characters for which `predicate` returns true
+    pub fn predicate<F>(predicate: F) -> Self
+    where
+        F: Fn(char) -> bool + Send + Sync + 'static,
+    {
+        ProtectionRule::Predicate(Arc::new(predicate))
+    }
+
+    /// Byte ranges of `text` matched by this rule
+    pub fn find(&self, text: &str) -> Vec<Range<usize>> {
+        match self {
+            ProtectionRule::Urls => regex_spans(&URL, text),
+            ProtectionRule::Emails => regex_spans(&EMAIL, text),
+            ProtectionRule::InlineCode => regex_spans(&CODE, text),
+            ProtectionRule::Pattern(regex) => regex_spans(regex, text),
+            ProtectionRule::Marked { open, close } => marked_spans(text, open, close),
+            ProtectionRule::Predicate(predicate) => predicate_spans(text, predicate.as_ref()),
+        }
+    }
+
+    /// Byte offset of the first opener at or after `after` in `text` that is
+    /// not closed yet but could be by text that follows
+    fn open_start(&self, text: &str, after: usize) -> Option<usize> {
+        match self {
+            ProtectionRule::InlineCode => open_code_start(text, after),
+            ProtectionRule::Marked { open, close } if !open.is_empty() && !close.is_empty() => {
+                // Openers after an unclosed one cannot be closed either
+                let from = marked_spans(text, open, close)
+                    .last()
+                    .map_or(0, |span| span.end)
+                    .max(after);
+                text.get(from..)?
+                    .find(open.as_str())
+                    .map(|index| from + index)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Debug for ProtectionRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProtectionRule::Urls => write!(f, "Urls"),
+            ProtectionRule::Emails => write!(f, "Emails"),
+            ProtectionRule::InlineCode => write!(f, "InlineCode"),
+            ProtectionRule::Marked { open, close } => f
+                .debug_struct("Marked")
+                .field("open", open)
+                .field("close", close)
+                .finish(),
+            ProtectionRule::Pattern(regex) => f.debug_tuple("Pattern").field(regex).finish(),
+            ProtectionRule::Predicate(_) => write!(f, "Predicate(..)"),
+        }
+    }
+}
+
+/// Sorted, non-overlapping byte ranges of `text` protected by the rules or by `extra`
+///
+/// Ranges are clipped to the text and widened to char boundaries.
+pub(crate) fn protected_spans(
+    rules: &[ProtectionRule],
+    text: &str,
+    extra: &[Range<usize>],
+) -> Vec<Range<usize>> {
+    let mut spans: Vec<Range<usize>> = rules
+        .iter()
+        .flat_map(|rule| rule.find(text))
+        .chain(extra.iter().cloned())
+        .map(|span| widen_to_chars(text, span))
+        .filter(|span| !span.is_empty())
+        .collect();
+    spans.sort_by_key(|span| span.start);
+
+    let mut merged: Vec<Range<usize>> = Vec::with_capacity(spans.len());
+    for span in spans {
+        match merged.last_mut() {
+            Some(last) if span.start <= last.end => last.end = last.end.max(span.end),
+            _ => merged.push(span),
+        }
+    }
+    merged
+}
+
+/// Byte offset of the first opener at or after `after` in `text` whose span
+/// the rules would close in text that follows
+///
+/// A prefix of streamed input cut after such an opener would leave the
+/// start of the span unprotected.
+pub(crate) fn open_span_start(rules: &[ProtectionRule], text: &str, after: usize) -> Option<usize> {
+    rules
+        .iter()
+        .filter_map(|rule| rule.open_start(text, after))
+        .min()
+}
+
+fn widen_to_chars(text: &str, span: Range<usize>) -> Range<usize> {
+    let mut start = span.start.min(text.len());
+    let mut end = span.end.min(text.len()).max(start);
+    while !text.is_char_boundary(start) {
+        start -= 1;
+    }
+    while !text.is_char_boundary(end) {
+        end += 1;
+    }
+    start..end
+}
+
+fn regex_spans(regex: &Regex, text: &str) -> Vec<Range<usize>> {
+    regex.find_iter(text).map(|found| found.range()).collect()
+}
+
+fn marked_spans(text: &str, open: &str, close: &str) -> Vec<Range<usize>> {
+    let mut spans = Vec::new();
+    if open.is_empty() || close.is_empty() {
+        return spans;
+    }
+    let mut from = 0;
+    while let Some(start) = text[from..].find(open).map(|index| from + index) {
+        let body = start + open.len();
+        match text[body..].find(close) {
+            Some(index) => {
+                let end = body + index + close.len();
+                spans.push(start..end);
+                from = end;
+            }
+            None => break,
+        }
+    }
+    spans
+}
+
+/// Start of the first backtick at or after `after` outside code spans that
+/// can still open one: a fence, or an inline span with no newline after it yet
+fn open_code_start(text: &str, after: usize) -> Option<usize> {
+    let mut from = 0;
+    let end = text.len()..text.len();
+    for span in regex_spans(&CODE, text)
+        .into_iter()
+        .chain(std::iter::once(end))
+    {
+        let open = text[from..span.start]
+            .match_indices('`')
+            .map(|(index, _)| from + index)
+            .filter(|&start| start >= after)
+            .find(|&start| text[start..].starts_with("```") || !text[start..].contains('\n'));
+        if open.is_some() {
+            return open;
+        }
+        from = span.end;
+    }
+    None
+}
+
+fn predicate_spans(text: &str, predicate: &dyn Fn(char) -> bool) -> Vec<Range<usize>> {
+    let mut spans: Vec<Range<usize>> = Vec::new();
+    for (index, ch) in text.char_indices() {
+        if !predicate(ch) {
+            continue;
+        }
+        let end = index + ch.len_utf8();
+        match spans.last_mut() {
+            Some(last) if last.end == index => last.end = end,
+            _ => spans.push(index..end),
+        }
+    }
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn found<'a>(rule: &ProtectionRule, text: &'a str) -> Vec<&'a str> {
+        rule.find(text)
+            .into_iter()
+            .map(|span| &text[span])
+            .collect()
+    }
+
+    #[test]
+    fn test_builtin_detectors() {
+        assert_eq!(
+            found(
+                &ProtectionRule::Urls,
+                "請看https://例子.中国/說明。或www.example.com."
+            ),
+            ["https://例子.中国/說明", "www.example.com"]
+        );
+        assert_eq!(
+            found(&ProtectionRule::Emails, "寫信給 user@例子.中国 吧"),
+            ["user@例子.中国"]
+        );
+        assert_eq!(
+            found(&ProtectionRule::InlineCode, "用 `說明()` 和\n```\n⽅\n```"),
+            ["`說明()`", "```\n⽅\n```"]
+        );
+    }
+
+    #[test]
+    fn test_marked_and_predicate_rules() {
+        let marked = ProtectionRule::marked("⟦", "⟧");
+        assert_eq!(found(&marked, "⟦⽅⟧⽅⟦⾯"), ["⟦⽅⟧"]);
+
+        let ascii = ProtectionRule::predicate(|ch| ch.is_ascii_alphabetic());
+        assert_eq!(found(&ascii, "⽅abc⾯d"), ["abc", "d"]);
+    }
+
+    #[test]
+    fn test_open_span_start() {
+        let rules = [ProtectionRule::InlineCode, ProtectionRule::marked("⟦", "⟧")];
+        assert_eq!(open_span_start(&rules, "`a` ```\n⽅。", 0), Some(4));
+        assert_eq!(open_span_start(&rules, "a ` b\n⽅。", 0), None);
+        assert_eq!(open_span_start(&rules, "a ` b⽅。", 0), Some(2));
+        assert_eq!(open_span_start(&rules, "⟦a⟧b⟦⽅。", 0), Some(8));
+        assert_eq!(open_span_start(&rules, "```\n⽅\n``` ⟦a⟧", 0), None);
+
+        // Openers before `after` are passed over
+        assert_eq!(open_span_start(&rules, "```\n`a", 1), Some(4));
+        assert_eq!(open_span_start(&rules, "⟦a⟦b", 3), Some(4));
+    }
+
+    #[test]
+    fn test_spans_are_merged_and_aligned() {
+        let spans = protected_spans(&[ProtectionRule::InlineCode], "`⽅` ⾯⾯", &[1..4, 7..8]);
+        assert_eq!(spans, [0..5, 6..9]);
+    }
+}
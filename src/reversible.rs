@@ -0,0 +1,235 @@
+//! Reconstruction of the original text from a normalized text and its change log
+//!
+//! Stages are undone in reverse order. Within a stage, each change's position
+//! refers to the text the stage received, so positions are shifted by the
+//! length differences of the earlier changes of the same stage before the
+//! normalized span is swapped back for the original one.
+
+use crate::types::{NormalizedText, TextChange};
+use std::fmt;
+
+/// Reasons why a change log cannot be inverted
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InverseError {
+    /// A change points past the end of the text it should apply to
+    OutOfBounds { stage: usize, position: usize },
+    /// The text at a change's position is not what the change produced
+    Mismatch {
+        stage: usize,
+        position: usize,
+        expected: String,
+        found: String,
+    },
+    /// Two changes of the same stage overlap or are out of order
+    Overlap { stage: usize, position: usize },
+    /// The rebuilt text differs from the preserved original
+    RoundTrip { reconstructed: String },
+    /// There is no preserved original to verify against
+    OriginalNotPreserved,
+}
+
+impl fmt::Display for InverseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InverseError::OutOfBounds { stage, position } => {
+                write!(f, "stage {} change at {} is out of bounds", stage, position)
+            }
+            InverseError::Mismatch {
+                stage,
+                position,
+                expected,
+                found,
+            } => write!(
+                f,
+                "stage {} change at {} expected {:?} but found {:?}",
+                stage, position, expected, found
+            ),
+            InverseError::Overlap { stage, position } => {
+                write!(
+                    f,
+                    "stage {} change at {} overlaps a previous change",
+                    stage, position
+                )
+            }
+            InverseError::RoundTrip { reconstructed } => {
+                write!(
+                    f,
+                    "reconstructed text {:?} differs from the original",
+                    reconstructed
+                )
+            }
+            InverseError::OriginalNotPreserved => {
+                write!(
+                    f,
+                    "original text was not preserved, nothing to verify against"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for InverseError {}
+
+/// Rebuild the text a pipeline started from, given its output and change log
+///
+/// Changes must be in the order the pipeline produced them (grouped by stage,
+/// ascending positions within a stage).
+pub fn apply_inverse(normalized: &str, changes: &[TextChange]) -> Result<String, InverseError> {
+    let mut text: Vec<char> = normalized.chars().collect();
+
+    for group in changes.chunk_by(|a, b| a.stage == b.stage).rev() {
+        text = invert_stage(&text, group)?;
+    }
+
+    Ok(text.into_iter().collect())
+}
+
+/// Undo the changes of a single stage
+fn invert_stage(text: &[char], changes: &[TextChange]) -> Result<Vec<char>, InverseError> {
+    let mut result = Vec::with_capacity(text.len());
+    let mut copied = 0; // chars of `text` already handled
+    let mut shift: isize = 0; // output length minus input length so far
+
+    for change in changes {
+        let (original, replaced) = change_spans(change);
+        let stage = change.stage;
+        let start = change.position as isize + shift;
+        if start < copied as isize {
+            return Err(InverseError::Overlap {
+                stage,
+                position: change.position,
+            });
+        }
+        let start = start as usize;
+        let end = start + replaced.len();
+        if end > text.len() {
+            return Err(InverseError::OutOfBounds {
+                stage,
+                position: change.position,
+            });
+        }
+        if text[start..end] != replaced[..] {
+            return Err(InverseError::Mismatch {
+                stage,
+                position: change.position,
+                expected: replaced.iter().collect(),
+                found: text[start..end].iter().collect(),
+            });
+        }
+
+        result.extend_from_slice(&text[copied..start]);
+        result.extend_from_slice(&original);
+        copied = end;
+        shift += replaced.len() as isize - original.len() as isize;
+    }
+
+    result.extend_from_slice(&text[copied..]);
+    Ok(result)
+}
+
+/// Original and normalized spans of a change, falling back to the single
+/// chars for change logs recorded before spans were tracked
+fn change_spans(change: &TextChange) -> (Vec<char>, Vec<char>) {
+    if change.original_text.is_empty() && change.normalized_text.is_empty() {
+        (vec![change.original_char], vec![change.normalized_char])
+    } else {
+        (
+            change.original_text.chars().collect(),
+            change.normalized_text.chars().collect(),
+        )
+    }
+}
+
+impl NormalizedText {
+    /// Rebuild the original text from `normalized` and `changes`
+    pub fn denormalize(&self) -> Result<String, InverseError> {
+        apply_inverse(&self.normalized, &self.changes)
+    }
+
+    /// Check that `denormalize` gives back exactly the preserved original,
+    /// proving the change log alone is enough to restore it
+    pub fn verify_round_trip(&self) -> Result<(), InverseError> {
+        if self.original.is_empty() && !self.normalized.is_empty() {
+            return Err(InverseError::OriginalNotPreserved);
+        }
+        let reconstructed = self.denormalize()?;
+        if reconstructed == self.original {
+            Ok(())
+        } else {
+            Err(InverseError::RoundTrip { reconstructed })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{ChangeType, Script};
+    use crate::TextNormalizer;
+
+    #[test]
+    fn test_round_trip_through_all_stages() {
+        let normalizer = TextNormalizer::new();
+        for text in [
+            "⽅⾯問題",
+            "硏究敎育",
+            "e\u{0301}⽅a\u{0308}\u{0323}",
+            "這個藥",
+        ] {
+            let result = normalizer.normalize(text, Some(Script::SimplifiedChinese));
+            assert_eq!(result.denormalize().unwrap(), text);
+            assert!(result.verify_round_trip().is_ok());
+        }
+    }
+
+    #[test]
+    fn test_multi_char_changes_across_stages() {
+        // Stage 0 composes two chars into one, stage 1 then expands one char into two
+        let changes = vec![
+            TextChange::replacement(
+                1,
+                "e\u{0301}".to_string(),
+                "é".to_string(),
+                ChangeType::UnicodeNormalization,
+                String::new(),
+            ),
+            TextChange {
+                stage: 1,
+                ..TextChange::replacement(
+                    0,
+                    "x".to_string(),
+                    "yz".to_string(),
+                    ChangeType::Custom("expand".to_string()),
+                    String::new(),
+                )
+            },
+        ];
+
+        assert_eq!(apply_inverse("yzé!", &changes).unwrap(), "xe\u{0301}!");
+    }
+
+    #[test]
+    fn test_detects_inconsistent_log() {
+        let normalizer = TextNormalizer::new();
+        let mut result = normalizer.normalize("⽅⾯問題", None);
+        result.normalized = "方麵問題".to_string();
+
+        assert!(matches!(
+            result.denormalize(),
+            Err(InverseError::Mismatch { position: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn test_verify_requires_original() {
+        let normalizer = TextNormalizer::new();
+        let mut result = normalizer.normalize("⽅⾯問題", None);
+        result.original.clear();
+
+        assert_eq!(
+            result.verify_round_trip(),
+            Err(InverseError::OriginalNotPreserved)
+        );
+        assert_eq!(result.denormalize().unwrap(), "⽅⾯問題");
+    }
+}
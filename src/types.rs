@@ -1,16 +1,33 @@
 //! Core types and data structures for text normalization
 
+use crate::alignment::OffsetMap;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
 /// Script types for CJK text
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Script {
     Auto,
     SimplifiedChinese,
     TraditionalChinese,
     Japanese,
     Korean,
+    /// Traditional Chinese with Taiwan glyph standards (裡, 著, 峰)
+    TraditionalTaiwan,
+    /// Traditional Chinese with Hong Kong glyph standards (裏, 衞, 綫)
+    TraditionalHongKong,
+    /// Japanese Shinjitai forms of Traditional characters (国, 発, 峰)
+    JapaneseShinjitai,
+}
+
+impl Script {
+    /// Whether this is a Traditional-based target with regional glyph standards
+    pub fn is_regional(&self) -> bool {
+        matches!(
+            self,
+            Script::TraditionalTaiwan | Script::TraditionalHongKong | Script::JapaneseShinjitai
+        )
+    }
 }
 
 /// Output format for the CLI
@@ -31,6 +48,30 @@ pub enum UnicodeNormalization {
     NFKD,
 }
 
+/// What script conversion does with characters that would not survive a round trip
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LossyConversion {
+    /// Convert them like every other character
+    #[default]
+    Convert,
+    /// Leave them unconverted
+    Keep,
+    /// Convert them and keep the original in parentheses after them (发(髮))
+    Annotate,
+}
+
+/// Transliteration of hanja in Korean text to Hangul
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HanjaTransliteration {
+    /// Hanja are left as they are
+    #[default]
+    None,
+    /// Hanja are replaced by their Hangul readings (大韓民國 → 대한민국)
+    Hangul,
+    /// Each run of hanja is replaced and kept in parentheses after it (대한민국(大韓民國))
+    HangulWithHanja,
+}
+
 /// Types of character variants
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum VariantType {
@@ -47,7 +88,7 @@ pub enum VariantType {
 }
 
 /// Types of text changes
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum ChangeType {
     ScriptConversion,
     KangxiRadical,
@@ -58,9 +99,17 @@ pub enum ChangeType {
     SpecializedVariant,
     CompatibilityForm,
     UnicodeNormalization,
+    /// Hanja replaced by its Sino-Korean Hangul reading
+    HanjaToHangul,
+    /// Change made by a user-defined pipeline stage
+    Custom(String),
 }
 
 /// Individual text change
+///
+/// `position` is a char index into the text the producing stage received.
+/// `original_text` and `normalized_text` hold the full replaced spans, which
+/// may be longer than one char (e.g. a base letter plus combining mark).
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TextChange {
     pub position: usize,
@@ -68,6 +117,57 @@ pub struct TextChange {
     pub normalized_char: char,
     pub change_type: ChangeType,
     pub reason: String,
+    #[serde(default)]
+    pub original_text: String,
+    #[serde(default)]
+    pub normalized_text: String,
+    /// Index of the pipeline stage that made the change
+    #[serde(default)]
+    pub stage: usize,
+    /// Every candidate the stage chose from, in order of preference, when there was more than one
+    #[serde(default)]
+    pub alternatives: Vec<String>,
+    /// Whether `normalized_text` is one of several candidates and may need review
+    #[serde(default)]
+    pub ambiguous: bool,
+    /// Mandarin reading of `normalized_text` in pinyin, if known (script conversion only)
+    #[serde(default)]
+    pub pinyin: String,
+    /// The same reading in Zhuyin
+    #[serde(default)]
+    pub zhuyin: String,
+}
+
+/// Span of a text whose script conversion is not undone by converting back
+///
+/// For a Traditional → Simplified conversion these are the characters where
+/// `s2t(t2s(x)) != x`, typically because several Traditional characters
+/// merge into one Simplified character (髮 → 发 → 發).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RoundTripLoss {
+    /// Char index of the span in the text
+    pub position: usize,
+    /// The span as it was
+    pub original: String,
+    /// What it is converted to
+    pub converted: String,
+    /// What converting back gives
+    pub restored: String,
+    /// Every form converting back could choose for `converted`, in order of preference
+    pub alternatives: Vec<String>,
+}
+
+/// Script of a text and the evidence it was decided on
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScriptDetection {
+    /// Most likely script (Simplified Chinese when there is no evidence)
+    pub script: Script,
+    /// Share of all evidence that points to `script`, 0 without any evidence
+    pub confidence: f64,
+    /// Weighted evidence found for each script
+    pub scores: HashMap<Script, u32>,
+    /// Number of CJK ideographs in the text
+    pub cjk_char_count: usize,
 }
 
 /// Enhanced variant mapping with metadata
@@ -82,12 +182,27 @@ pub struct VariantMapping {
 }
 
 /// Script mapping with additional metadata
+///
+/// The reading and frequency are those of the mapping's target (the
+/// simplified form in a Traditional → Simplified table and vice versa).
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ScriptMapping {
     pub traditional: String,
     pub simplified: String,
     pub pinyin: String,
     pub zhuyin: String,
+    /// Unihan kFrequency rank, 1 (most frequent) to 5; 0 if unknown
+    pub frequency: u32,
+}
+
+/// Mandarin reading and usage frequency of a character
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Reading {
+    /// Hanyu Pinyin with tone marks (Unihan kMandarin, else kHanyuPinyin)
+    pub pinyin: String,
+    /// Zhuyin (Bopomofo) derived from the pinyin
+    pub zhuyin: String,
+    /// Unihan kFrequency rank, 1 (most frequent) to 5; 0 if unknown
     pub frequency: u32,
 }
 
@@ -139,6 +254,159 @@ pub struct NormalizedText {
     pub changes: Vec<TextChange>,
     pub detected_script: Script,
     pub processing_time_ms: u64,
+    /// Alignment between `original` and `normalized`; empty, and left out
+    /// when serialized, if the original was not preserved
+    #[serde(default, skip_serializing_if = "OffsetMap::is_empty")]
+    pub offset_map: OffsetMap,
+}
+
+/// Aggregate statistics over a batch of normalized texts
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BatchStatistics {
+    pub texts: usize,
+    pub changed_texts: usize,
+    pub total_changes: usize,
+    pub changes_by_type: HashMap<ChangeType, usize>,
+    /// Sum of the per-text processing times
+    pub processing_time_ms: u64,
+}
+
+impl BatchStatistics {
+    /// Collect statistics over the given results
+    pub fn from_results<'a, I>(results: I) -> Self
+    where
+        I: IntoIterator<Item = &'a NormalizedText>,
+    {
+        let mut statistics = Self::default();
+        for result in results {
+            statistics.record(result);
+        }
+        statistics
+    }
+
+    /// Add one result to the statistics
+    pub fn record(&mut self, result: &NormalizedText) {
+        self.texts += 1;
+        if !result.changes.is_empty() {
+            self.changed_texts += 1;
+        }
+        self.total_changes += result.changes.len();
+        for change in &result.changes {
+            *self
+                .changes_by_type
+                .entry(change.change_type.clone())
+                .or_default() += 1;
+        }
+        self.processing_time_ms += result.processing_time_ms;
+    }
+}
+
+/// Named normalization configurations for common workloads
+///
+/// Each preset is a starting point: `NormalizationConfig::from(preset)` can be
+/// adjusted further with the `with_*` setters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Preset {
+    /// Aggressive folding for search keys: NFKC, Kangxi radicals, variants,
+    /// compatibility forms, then conversion to Simplified Chinese
+    ///
+    /// Produces `UnicodeNormalization`, `KangxiRadical`, `SemanticVariant`,
+    /// `CompatibilityForm` and `ScriptConversion` changes.
+    SearchIndex,
+    /// Conservative cleanup for text shown to readers: NFC, Kangxi radicals and
+    /// compatibility forms; variants chosen by the author and the script are kept
+    ///
+    /// Produces `UnicodeNormalization`, `KangxiRadical` and `CompatibilityForm` changes.
+    Display,
+    /// Folding of look-alike characters before comparing identifiers: NFKC
+    /// (fullwidth and other compatibility characters), Kangxi radicals,
+    /// variants, spoofing variants (妺 → 妹, 凐 → 湮) and compatibility forms,
+    /// without script conversion
+    ///
+    /// Produces `UnicodeNormalization`, `KangxiRadical`, `SpoofingVariant`,
+    /// `SemanticVariant` and `CompatibilityForm` changes.
+    Security,
+    /// Only encoding artifacts are fixed: Kangxi radicals and compatibility
+    /// ideographs; no Unicode normalization and no script conversion
+    ///
+    /// Produces `KangxiRadical` and `CompatibilityForm` changes.
+    Archival,
+}
+
+impl Preset {
+    /// All presets
+    pub const ALL: [Preset; 4] = [
+        Preset::SearchIndex,
+        Preset::Display,
+        Preset::Security,
+        Preset::Archival,
+    ];
+
+    /// Name of the preset as used on the command line (e.g. `search-index`)
+    pub fn name(self) -> &'static str {
+        match self {
+            Preset::SearchIndex => "search-index",
+            Preset::Display => "display",
+            Preset::Security => "security",
+            Preset::Archival => "archival",
+        }
+    }
+
+    /// Look a preset up by its name
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|preset| preset.name().eq_ignore_ascii_case(name))
+    }
+
+    /// Change types a normalizer built from this preset can produce
+    pub fn change_types(self) -> &'static [ChangeType] {
+        match self {
+            Preset::SearchIndex => &[
+                ChangeType::UnicodeNormalization,
+                ChangeType::KangxiRadical,
+                ChangeType::SemanticVariant,
+                ChangeType::CompatibilityForm,
+                ChangeType::ScriptConversion,
+            ],
+            Preset::Display => &[
+                ChangeType::UnicodeNormalization,
+                ChangeType::KangxiRadical,
+                ChangeType::CompatibilityForm,
+            ],
+            Preset::Security => &[
+                ChangeType::UnicodeNormalization,
+                ChangeType::KangxiRadical,
+                ChangeType::SpoofingVariant,
+                ChangeType::SemanticVariant,
+                ChangeType::CompatibilityForm,
+            ],
+            Preset::Archival => &[ChangeType::KangxiRadical, ChangeType::CompatibilityForm],
+        }
+    }
+
+    /// Configuration of the preset
+    pub fn config(self) -> NormalizationConfig {
+        let config = NormalizationConfig::default();
+        match self {
+            Preset::SearchIndex => config
+                .with_unicode_normalization(UnicodeNormalization::NFKC)
+                .with_target_script(Script::SimplifiedChinese),
+            Preset::Display => config.with_variants(false),
+            Preset::Security => config
+                .with_unicode_normalization(UnicodeNormalization::NFKC)
+                .with_spoofing_variants(true),
+            Preset::Archival => config
+                .with_unicode_normalization(UnicodeNormalization::None)
+                .with_variants(false),
+        }
+    }
+}
+
+impl From<Preset> for NormalizationConfig {
+    fn from(preset: Preset) -> Self {
+        preset.config()
+    }
 }
 
 /// Normalization configuration
@@ -148,8 +416,143 @@ pub struct NormalizationConfig {
     pub unicode_normalization: UnicodeNormalization,
     pub normalize_kangxi_radicals: bool,
     pub normalize_variants: bool,
+    /// Fold look-alike characters (Unihan spoofing variants) during variant normalization
+    #[serde(default)]
+    pub normalize_spoofing_variants: bool,
     pub normalize_compatibility: bool,
     pub preserve_original: bool,
+    /// Refuse to build a normalizer whose mapping data could not be fully loaded
+    #[serde(default)]
+    pub strict: bool,
+    /// Apply the Kangxi, variant, compatibility and script tables as one fixed-point mapping
+    #[serde(default)]
+    pub collapse_tables: bool,
+    /// Convert Simplified vocabulary to Taiwan usage (软件 → 軟體) when targeting Taiwan
+    #[serde(default)]
+    pub taiwan_vocabulary: bool,
+    /// Replace hanja with their Hangul readings after the other stages
+    #[serde(default)]
+    pub hanja_transliteration: HanjaTransliteration,
+    /// What script conversion does with characters that would not survive a round trip
+    ///
+    /// With collapsed tables `LossyConversion::Annotate` keeps them instead, as
+    /// an annotation is not a character mapping.
+    #[serde(default)]
+    pub lossy_conversion: LossyConversion,
+}
+
+impl TextChange {
+    /// Create a change that replaced a single char
+    pub fn new(
+        position: usize,
+        original_char: char,
+        normalized_char: char,
+        change_type: ChangeType,
+        reason: String,
+    ) -> Self {
+        Self {
+            position,
+            original_char,
+            normalized_char,
+            change_type,
+            reason,
+            original_text: original_char.to_string(),
+            normalized_text: normalized_char.to_string(),
+            stage: 0,
+            alternatives: Vec::new(),
+            ambiguous: false,
+            pinyin: String::new(),
+            zhuyin: String::new(),
+        }
+    }
+
+    /// Create a change that replaced a span of chars with another span
+    ///
+    /// `original_char` and `normalized_char` are set to the first char of each
+    /// span (`'\0'` for an empty span).
+    pub fn replacement(
+        position: usize,
+        original_text: String,
+        normalized_text: String,
+        change_type: ChangeType,
+        reason: String,
+    ) -> Self {
+        Self {
+            position,
+            original_char: original_text.chars().next().unwrap_or('\0'),
+            normalized_char: normalized_text.chars().next().unwrap_or('\0'),
+            change_type,
+            reason,
+            original_text,
+            normalized_text,
+            stage: 0,
+            alternatives: Vec::new(),
+            ambiguous: false,
+            pinyin: String::new(),
+            zhuyin: String::new(),
+        }
+    }
+
+    /// Record the candidates the change chose from, builder style
+    ///
+    /// The change is marked ambiguous if there is more than one.
+    pub fn with_alternatives(mut self, alternatives: Vec<String>) -> Self {
+        self.ambiguous = alternatives.len() > 1;
+        self.alternatives = alternatives;
+        self
+    }
+}
+
+impl ScriptMapping {
+    /// Create a mapping carrying the reading and frequency of `target` (one of the two forms)
+    pub fn with_reading(
+        traditional: String,
+        simplified: String,
+        target: &str,
+        readings: &HashMap<char, Reading>,
+    ) -> Self {
+        let reading = Reading::of(target, readings);
+        Self {
+            traditional,
+            simplified,
+            pinyin: reading.pinyin,
+            zhuyin: reading.zhuyin,
+            frequency: reading.frequency,
+        }
+    }
+}
+
+impl Reading {
+    /// Reading of a character or phrase, syllables separated by spaces
+    ///
+    /// Empty if any character has no reading. Only a single character has a frequency.
+    pub fn of(text: &str, readings: &HashMap<char, Reading>) -> Self {
+        let found: Option<Vec<&Reading>> = text.chars().map(|ch| readings.get(&ch)).collect();
+        match found.as_deref() {
+            Some([reading]) => (*reading).clone(),
+            Some(found) if !found.is_empty() => Reading {
+                pinyin: found
+                    .iter()
+                    .map(|reading| reading.pinyin.as_str())
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                zhuyin: found
+                    .iter()
+                    .map(|reading| reading.zhuyin.as_str())
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                frequency: 0,
+            },
+            _ => Reading::default(),
+        }
+    }
+}
+
+impl NormalizedText {
+    /// Changes that picked one of several candidates, for review
+    pub fn ambiguous_changes(&self) -> impl Iterator<Item = &TextChange> {
+        self.changes.iter().filter(|change| change.ambiguous)
+    }
 }
 
 impl VariantMapping {
@@ -178,6 +581,12 @@ impl VariantMapping {
     }
 }
 
+impl Default for VariantMappings {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl VariantMappings {
     /// Create a new empty variant mappings structure
     pub fn new() -> Self {
@@ -218,13 +627,13 @@ impl VariantMappings {
         // Add to by_type index
         self.by_type
             .entry(mapping.variant_type.clone())
-            .or_insert_with(Vec::new)
+            .or_default()
             .push(mapping.clone());
 
         // Add to lookup index
         self.lookup
             .entry(mapping.source)
-            .or_insert_with(Vec::new)
+            .or_default()
             .push(mapping.clone());
 
         // Add bidirectional mapping if specified
@@ -240,7 +649,7 @@ impl VariantMappings {
 
             self.lookup
                 .entry(reverse_mapping.source)
-                .or_insert_with(Vec::new)
+                .or_default()
                 .push(reverse_mapping.clone());
         }
 
@@ -270,8 +679,89 @@ impl Default for NormalizationConfig {
             unicode_normalization: UnicodeNormalization::NFC,
             normalize_kangxi_radicals: true,
             normalize_variants: true,
+            normalize_spoofing_variants: false,
             normalize_compatibility: true,
             preserve_original: true,
+            strict: false,
+            collapse_tables: false,
+            taiwan_vocabulary: false,
+            hanja_transliteration: HanjaTransliteration::None,
+            lossy_conversion: LossyConversion::Convert,
         }
     }
 }
+
+impl NormalizationConfig {
+    /// Set the target script (`Script::Auto` disables script conversion)
+    pub fn with_target_script(mut self, target_script: Script) -> Self {
+        self.target_script = target_script;
+        self
+    }
+
+    /// Set the Unicode normalization form (`UnicodeNormalization::None` disables it)
+    pub fn with_unicode_normalization(mut self, form: UnicodeNormalization) -> Self {
+        self.unicode_normalization = form;
+        self
+    }
+
+    /// Enable or disable Kangxi radical normalization
+    pub fn with_kangxi_radicals(mut self, enabled: bool) -> Self {
+        self.normalize_kangxi_radicals = enabled;
+        self
+    }
+
+    /// Enable or disable character variant normalization
+    pub fn with_variants(mut self, enabled: bool) -> Self {
+        self.normalize_variants = enabled;
+        self
+    }
+
+    /// Enable or disable folding of look-alike characters (takes effect with variant normalization)
+    pub fn with_spoofing_variants(mut self, enabled: bool) -> Self {
+        self.normalize_spoofing_variants = enabled;
+        self
+    }
+
+    /// Enable or disable compatibility form normalization
+    pub fn with_compatibility(mut self, enabled: bool) -> Self {
+        self.normalize_compatibility = enabled;
+        self
+    }
+
+    /// Keep or drop the input text in `NormalizedText::original`, and with it
+    /// the offset map aligning the result to it
+    pub fn with_preserve_original(mut self, preserve: bool) -> Self {
+        self.preserve_original = preserve;
+        self
+    }
+
+    /// Fail instead of degrading when mapping data is missing or malformed
+    pub fn with_strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Collapse the mapping tables into a single fixed-point mapping so one pass is idempotent
+    pub fn with_collapsed_tables(mut self, collapse_tables: bool) -> Self {
+        self.collapse_tables = collapse_tables;
+        self
+    }
+
+    /// Convert Simplified vocabulary to Taiwan usage when the target is `Script::TraditionalTaiwan`
+    pub fn with_taiwan_vocabulary(mut self, taiwan_vocabulary: bool) -> Self {
+        self.taiwan_vocabulary = taiwan_vocabulary;
+        self
+    }
+
+    /// Set what script conversion does with characters that would not survive a round trip
+    pub fn with_lossy_conversion(mut self, mode: LossyConversion) -> Self {
+        self.lossy_conversion = mode;
+        self
+    }
+
+    /// Set how hanja are transliterated to Hangul (`HanjaTransliteration::None` disables it)
+    pub fn with_hanja_transliteration(mut self, mode: HanjaTransliteration) -> Self {
+        self.hanja_transliteration = mode;
+        self
+    }
+}
@@ -1,32 +1,16 @@
 //! Data processor for Unihan database files
 
 // Note: ScriptMapping types removed as we now use simple HashMap<String, String> for clean data
+use crate::constants::paths;
+use crate::utils::hangul;
 use crate::utils::unicode_utils::code_point_to_char;
+use serde::Deserialize;
 use serde_json;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fs;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
 
-/// Data file path constants to avoid repetition
-mod paths {
-    pub const SCRIPT_DIR: &str = "data/processed/script_conversion";
-    pub const NORM_DIR: &str = "data/processed/normalization";
-
-    pub const T2S_MAPPINGS: &str =
-        "data/processed/script_conversion/traditional_to_simplified.json";
-    pub const S2T_MAPPINGS: &str =
-        "data/processed/script_conversion/simplified_to_traditional.json";
-    pub const SCRIPT_STATS: &str = "data/processed/script_conversion/script_conversion_stats.json";
-
-    pub const SEMANTIC_VARIANTS: &str = "data/processed/normalization/semantic_variants.json";
-    pub const COMPAT_VARIANTS: &str = "data/processed/normalization/compatibility_variants.json";
-    pub const KANGXI_RADICALS: &str = "data/processed/normalization/kangxi_radicals.json";
-    pub const NORM_STATS: &str = "data/processed/normalization/normalization_stats.json";
-
-    pub const UNIHAN_IRG: &str = "Unihan/Unihan_IRGSources.txt";
-}
-
 /// Processor for Unihan database files
 pub struct UnihanDataProcessor;
 
@@ -34,35 +18,50 @@ impl UnihanDataProcessor {
     /// Process all Unihan files and generate clean separated mappings
     pub fn process_all() -> Result<(), Box<dyn std::error::Error>> {
         let processor = Self;
-        println!("🚀 Starting clean data generation with proper separation...");
+        log::info!("generating mapping tables from Unihan");
 
         // Step 1: Process script conversion mappings (Traditional ↔ Simplified)
-        println!("\n📋 Step 1: Processing script conversion mappings...");
-        processor.process_script_conversion_mappings("Unihan/Unihan_Variants.txt")?;
+        log::info!("step 1: script conversion mappings");
+        processor.process_script_conversion_mappings(
+            paths::UNIHAN_VARIANTS,
+            paths::UNIHAN_OTHER_MAPPINGS,
+        )?;
+
+        // Step 2: Extract readings and frequencies of the script conversion characters
+        log::info!("step 2: readings");
+        processor.process_readings(paths::UNIHAN_READINGS, paths::UNIHAN_DICTIONARY_LIKE)?;
+
+        // Step 3: Extract the Sino-Korean Hangul readings of hanja
+        log::info!("step 3: Hangul readings");
+        processor.process_hangul_readings(paths::UNIHAN_READINGS)?;
 
-        // Step 2: Process normalization mappings (variants, compatibility, etc.)
+        // Step 4: Process normalization mappings (variants, compatibility, etc.)
         // EXCLUDING pairs that already exist in script conversion
-        println!("\n📋 Step 2: Processing normalization mappings...");
-        processor.process_normalization_mappings(
-            "Unihan/Unihan_Variants.txt",
-            "Unihan/Unihan_IRGSources.txt",
-        )?;
+        log::info!("step 4: normalization mappings");
+        processor.process_normalization_mappings(paths::UNIHAN_VARIANTS, paths::UNIHAN_IRG)?;
 
-        println!("\n✅ Clean data generation completed!");
+        log::info!("mapping tables generated");
         Ok(())
     }
 
     /// Process script conversion mappings (Traditional ↔ Simplified) from kSimplifiedVariant and kTraditionalVariant
+    ///
+    /// Every pair goes into both tables, whichever of the two fields lists
+    /// it, and candidates keep the order Unihan lists them in. The tables are
+    /// built from Unihan alone; identity pairs are kept only as
+    /// [`keeps_identity`] decides.
     fn process_script_conversion_mappings(
         &self,
-        path: &str,
+        variants_path: &str,
+        other_mappings_path: &str,
     ) -> Result<(), Box<dyn std::error::Error>> {
-        // Collect raw relationships
-        let mut t2s_mappings: HashMap<String, String> = HashMap::new();
-        let mut s2t_mappings: HashMap<String, String> = HashMap::new();
-        let mut processed_pairs: HashSet<(String, String)> = HashSet::new();
+        let mut t2s_mappings: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        let mut s2t_mappings: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        // Characters Unihan lists among their own simplified / traditional variants
+        let mut own_simplified: HashSet<char> = HashSet::new();
+        let mut own_traditional: HashSet<char> = HashSet::new();
 
-        let file = File::open(path)?;
+        let file = File::open(variants_path)?;
         let reader = BufReader::new(file);
 
         for line in reader.lines() {
@@ -83,58 +82,79 @@ impl UnihanDataProcessor {
             let Some(source_char) = code_point_to_char(source_cp) else {
                 continue;
             };
+            if kind != "kSimplifiedVariant" && kind != "kTraditionalVariant" {
+                continue;
+            }
 
-            match kind {
-                "kSimplifiedVariant" => {
-                    // A kSimplifiedVariant B => A (traditional) → B (simplified)
-                    if let Some(first) = targets_raw.split_whitespace().next() {
-                        if let Some(target_char) = code_point_to_char(first) {
-                            let trad = source_char.to_string();
-                            let simp = target_char.to_string();
-
-                            if !processed_pairs.contains(&(trad.clone(), simp.clone())) {
-                                t2s_mappings.insert(trad.clone(), simp.clone());
-                                processed_pairs.insert((trad, simp));
-                            }
-                        }
-                    }
-                }
-                "kTraditionalVariant" => {
-                    // A kTraditionalVariant B => A (simplified) → B (traditional)
-                    // Only take the first one to avoid ambiguity
-                    if let Some(first) = targets_raw.split_whitespace().next() {
-                        let clean = first.split('<').next().unwrap_or(first);
-                        if let Some(target_char) = code_point_to_char(clean) {
-                            let simp = source_char.to_string();
-                            let trad = target_char.to_string();
-
-                            if !processed_pairs.contains(&(trad.clone(), simp.clone())) {
-                                s2t_mappings.insert(simp.clone(), trad.clone());
-                                processed_pairs.insert((trad, simp));
-                            }
-                        }
+            for target in targets_raw.split_whitespace() {
+                let clean = target.split('<').next().unwrap_or(target);
+                let Some(target_char) = code_point_to_char(clean) else {
+                    continue;
+                };
+                if target_char == source_char {
+                    if kind == "kSimplifiedVariant" {
+                        own_simplified.insert(source_char);
+                    } else {
+                        own_traditional.insert(source_char);
                     }
+                    continue;
                 }
-                _ => {}
+                // A kSimplifiedVariant B => A (traditional) → B (simplified)
+                // A kTraditionalVariant B => A (simplified) → B (traditional)
+                let (trad, simp) = if kind == "kSimplifiedVariant" {
+                    (source_char.to_string(), target_char.to_string())
+                } else {
+                    (target_char.to_string(), source_char.to_string())
+                };
+                add_candidate(&mut t2s_mappings, &trad, &simp);
+                add_candidate(&mut s2t_mappings, &simp, &trad);
+            }
+        }
+
+        // A character becomes its own candidate after the others it has
+        let common_traditional = common_big5_chars(other_mappings_path)?;
+        for (trad, simps) in t2s_mappings.iter_mut() {
+            let ch = trad.chars().next().expect("non-empty key");
+            if keeps_identity(ch, &own_simplified, None) {
+                simps.push(trad.clone());
+            }
+        }
+        for (simp, trads) in s2t_mappings.iter_mut() {
+            let ch = simp.chars().next().expect("non-empty key");
+            if keeps_identity(ch, &own_traditional, Some(&common_traditional)) {
+                trads.push(simp.clone());
             }
         }
 
+        let processed_pairs: HashSet<(&String, &String)> = t2s_mappings
+            .iter()
+            .flat_map(|(trad, simps)| simps.iter().map(move |simp| (trad, simp)))
+            .collect();
+        let ambiguous = |mappings: &BTreeMap<String, Vec<String>>| {
+            mappings
+                .values()
+                .filter(|candidates| candidates.len() > 1)
+                .count()
+        };
+        let t2s_ambiguous = ambiguous(&t2s_mappings);
+        let s2t_ambiguous = ambiguous(&s2t_mappings);
+
         // Save Traditional → Simplified mappings
         let t2s_path = paths::T2S_MAPPINGS;
-        let t2s_json = serde_json::to_string_pretty(&t2s_mappings)?;
+        let t2s_json = serde_json::to_string_pretty(&candidates_json(&t2s_mappings))?;
         fs::write(t2s_path, t2s_json)?;
-        println!(
-            "✅ Saved {} Traditional→Simplified mappings to: {}",
+        log::info!(
+            "saved {} Traditional→Simplified mappings to {}",
             t2s_mappings.len(),
             t2s_path
         );
 
         // Save Simplified → Traditional mappings
         let s2t_path = paths::S2T_MAPPINGS;
-        let s2t_json = serde_json::to_string_pretty(&s2t_mappings)?;
+        let s2t_json = serde_json::to_string_pretty(&candidates_json(&s2t_mappings))?;
         fs::write(s2t_path, s2t_json)?;
-        println!(
-            "✅ Saved {} Simplified→Traditional mappings to: {}",
+        log::info!(
+            "saved {} Simplified→Traditional mappings to {}",
             s2t_mappings.len(),
             s2t_path
         );
@@ -143,12 +163,165 @@ impl UnihanDataProcessor {
         let stats = serde_json::json!({
             "traditional_to_simplified_count": t2s_mappings.len(),
             "simplified_to_traditional_count": s2t_mappings.len(),
+            "ambiguous_traditional_count": t2s_ambiguous,
+            "ambiguous_simplified_count": s2t_ambiguous,
             "total_script_conversion_pairs": processed_pairs.len(),
             "generation_timestamp": std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs()
         });
         let stats_path = paths::SCRIPT_STATS;
         fs::write(stats_path, serde_json::to_string_pretty(&stats)?)?;
-        println!("✅ Saved statistics to: {}", stats_path);
+        log::info!("saved statistics to {}", stats_path);
+
+        Ok(())
+    }
+
+    /// Extract the pinyin (kMandarin, else the first kHanyuPinyin reading) and
+    /// kFrequency of every character in the script conversion tables
+    fn process_readings(
+        &self,
+        readings_path: &str,
+        dictionary_like_path: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let chars: HashSet<char> = self
+            .load_script_conversion_pairs()?
+            .into_iter()
+            .flat_map(|(trad, simp)| trad.chars().chain(simp.chars()).collect::<Vec<_>>())
+            .collect();
+
+        let mut mandarin: HashMap<char, String> = HashMap::new();
+        let mut hanyu_pinyin: HashMap<char, String> = HashMap::new();
+        let mut frequency: HashMap<char, u32> = HashMap::new();
+        for path in [readings_path, dictionary_like_path] {
+            let reader = BufReader::new(File::open(path)?);
+            for line in reader.lines() {
+                let line = line?;
+                if line.starts_with('#') || line.trim().is_empty() {
+                    continue;
+                }
+                let parts: Vec<&str> = line.split('\t').collect();
+                if parts.len() < 3 {
+                    continue;
+                }
+                let Some(ch) = code_point_to_char(parts[0]).filter(|ch| chars.contains(ch)) else {
+                    continue;
+                };
+                match parts[1] {
+                    // "zhōng" or "hé hè": the first is the most common reading
+                    "kMandarin" => {
+                        if let Some(reading) = parts[2].split_whitespace().next() {
+                            mandarin.insert(ch, reading.to_string());
+                        }
+                    }
+                    // "10019.020:tiàn 10020.030:zhǔ,zhù"
+                    "kHanyuPinyin" => {
+                        let reading = parts[2]
+                            .split_whitespace()
+                            .next()
+                            .and_then(|entry| entry.split(':').nth(1))
+                            .and_then(|readings| readings.split(',').next());
+                        if let Some(reading) = reading {
+                            hanyu_pinyin.insert(ch, reading.to_string());
+                        }
+                    }
+                    "kFrequency" => {
+                        if let Ok(rank) = parts[2].trim().parse() {
+                            frequency.insert(ch, rank);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let mut readings = BTreeMap::new();
+        for ch in chars {
+            let pinyin = mandarin.get(&ch).or_else(|| hanyu_pinyin.get(&ch));
+            let rank = frequency.get(&ch).copied();
+            if pinyin.is_none() && rank.is_none() {
+                continue;
+            }
+            readings.insert(
+                ch.to_string(),
+                serde_json::json!({
+                    "pinyin": pinyin.cloned().unwrap_or_default(),
+                    "frequency": rank.unwrap_or(0),
+                }),
+            );
+        }
+
+        fs::write(paths::READINGS, serde_json::to_string_pretty(&readings)?)?;
+        log::info!(
+            "saved readings of {} characters to {}",
+            readings.len(),
+            paths::READINGS
+        );
+
+        Ok(())
+    }
+
+    /// Extract the Hangul readings of every hanja: kHangul, else kKorean
+    /// (Yale romanization) converted to Hangul
+    fn process_hangul_readings(
+        &self,
+        readings_path: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut hangul: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        let mut korean: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        let reader = BufReader::new(File::open(readings_path)?);
+        for line in reader.lines() {
+            let line = line?;
+            if line.starts_with('#') || line.trim().is_empty() {
+                continue;
+            }
+            let parts: Vec<&str> = line.split('\t').collect();
+            if parts.len() < 3 {
+                continue;
+            }
+            let Some(ch) = code_point_to_char(parts[0]) else {
+                continue;
+            };
+            match parts[1] {
+                // "리:0N 이:0E": a syllable and the sources attesting it
+                "kHangul" => {
+                    let readings = parts[2]
+                        .split_whitespace()
+                        .filter_map(|entry| entry.split(':').next())
+                        .filter(|reading| reading.chars().count() == 1)
+                        .map(str::to_string)
+                        .collect();
+                    hangul.insert(ch.to_string(), readings);
+                }
+                // "LI I"
+                "kKorean" => {
+                    let readings = parts[2]
+                        .split_whitespace()
+                        .filter_map(hangul::from_yale)
+                        .map(String::from)
+                        .collect();
+                    korean.insert(ch.to_string(), readings);
+                }
+                _ => {}
+            }
+        }
+
+        for (ch, readings) in korean {
+            hangul.entry(ch).or_insert(readings);
+        }
+        hangul.retain(|_, readings| !readings.is_empty());
+        for readings in hangul.values_mut() {
+            let mut seen = HashSet::new();
+            readings.retain(|reading| seen.insert(reading.clone()));
+        }
+
+        fs::write(
+            paths::HANGUL_READINGS,
+            serde_json::to_string_pretty(&candidates_json(&hangul))?,
+        )?;
+        log::info!(
+            "saved Hangul readings of {} hanja to {}",
+            hangul.len(),
+            paths::HANGUL_READINGS
+        );
 
         Ok(())
     }
@@ -161,8 +334,8 @@ impl UnihanDataProcessor {
     ) -> Result<(), Box<dyn std::error::Error>> {
         // Step 1: Load existing script conversion pairs to exclude them
         let script_pairs = self.load_script_conversion_pairs()?;
-        println!(
-            "📋 Loaded {} script conversion pairs to exclude",
+        log::info!(
+            "loaded {} script conversion pairs to exclude",
             script_pairs.len()
         );
 
@@ -177,18 +350,23 @@ impl UnihanDataProcessor {
         // Step 4: Process Kangxi radicals
         let kangxi_variants = self.process_kangxi_radicals_clean(&script_pairs)?;
 
-        // Step 5: Save normalization statistics
+        // Step 5: Process spoofing variants
+        let spoofing_variants =
+            self.process_spoofing_variants_clean(variants_path, &script_pairs)?;
+
+        // Step 6: Save normalization statistics
         let stats = serde_json::json!({
             "semantic_variants_count": semantic_variants.len(),
             "compatibility_variants_count": compatibility_variants.len(),
             "kangxi_radicals_count": kangxi_variants.len(),
-            "total_normalization_mappings": semantic_variants.len() + compatibility_variants.len() + kangxi_variants.len(),
+            "spoofing_variants_count": spoofing_variants.len(),
+            "total_normalization_mappings": semantic_variants.len() + compatibility_variants.len() + kangxi_variants.len() + spoofing_variants.len(),
             "excluded_script_pairs": script_pairs.len(),
             "generation_timestamp": std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs()
         });
-        let stats_path = "data/processed/normalization/normalization_stats.json";
+        let stats_path = paths::NORM_STATS;
         fs::write(stats_path, serde_json::to_string_pretty(&stats)?)?;
-        println!("✅ Saved normalization statistics to: {}", stats_path);
+        log::info!("saved normalization statistics to {}", stats_path);
 
         Ok(())
     }
@@ -200,23 +378,23 @@ impl UnihanDataProcessor {
         let mut pairs = HashSet::new();
 
         // Load Traditional → Simplified
-        if let Ok(contents) =
-            fs::read_to_string("data/processed/script_conversion/traditional_to_simplified.json")
-        {
-            if let Ok(mappings) = serde_json::from_str::<HashMap<String, String>>(&contents) {
-                for (trad, simp) in mappings {
-                    pairs.insert((trad, simp));
+        if let Ok(contents) = fs::read_to_string(paths::T2S_MAPPINGS) {
+            if let Ok(mappings) = serde_json::from_str::<HashMap<String, Candidates>>(&contents) {
+                for (trad, simps) in mappings {
+                    for simp in simps.into_vec() {
+                        pairs.insert((trad.clone(), simp));
+                    }
                 }
             }
         }
 
         // Load Simplified → Traditional
-        if let Ok(contents) =
-            fs::read_to_string("data/processed/script_conversion/simplified_to_traditional.json")
-        {
-            if let Ok(mappings) = serde_json::from_str::<HashMap<String, String>>(&contents) {
-                for (simp, trad) in mappings {
-                    pairs.insert((trad, simp));
+        if let Ok(contents) = fs::read_to_string(paths::S2T_MAPPINGS) {
+            if let Ok(mappings) = serde_json::from_str::<HashMap<String, Candidates>>(&contents) {
+                for (simp, trads) in mappings {
+                    for trad in trads.into_vec() {
+                        pairs.insert((trad, simp.clone()));
+                    }
                 }
             }
         }
@@ -277,11 +455,11 @@ impl UnihanDataProcessor {
         }
 
         // Save semantic variants
-        let path = "data/processed/normalization/semantic_variants.json";
+        let path = paths::SEMANTIC_VARIANTS;
         let json = serde_json::to_string_pretty(&semantic_mappings)?;
         fs::write(path, json)?;
-        println!(
-            "✅ Saved {} semantic variant mappings to: {}",
+        log::info!(
+            "saved {} semantic variant mappings to {}",
             semantic_mappings.len(),
             path
         );
@@ -289,14 +467,81 @@ impl UnihanDataProcessor {
         Ok(semantic_mappings)
     }
 
+    /// Fold each group of look-alike characters (kSpoofingVariant) to one
+    /// member, excluding script pairs
+    ///
+    /// The member with the most kIICore regions is kept, the lowest code
+    /// point on a tie.
+    fn process_spoofing_variants_clean(
+        &self,
+        path: &str,
+        script_pairs: &HashSet<(String, String)>,
+    ) -> Result<BTreeMap<String, String>, Box<dyn std::error::Error>> {
+        // Spoofing variants are listed in both directions; join them into groups
+        let mut groups: Vec<BTreeSet<char>> = Vec::new();
+        let reader = BufReader::new(File::open(path)?);
+        for line in reader.lines() {
+            let line = line?;
+            let parts: Vec<&str> = line.split('\t').collect();
+            if parts.len() < 3 || parts[1] != "kSpoofingVariant" {
+                continue;
+            }
+            let Some(source) = code_point_to_char(parts[0]) else {
+                continue;
+            };
+            for target in parts[2].split_whitespace() {
+                let clean = target.split('<').next().unwrap_or(target);
+                let Some(target) = code_point_to_char(clean) else {
+                    continue;
+                };
+                if source == target
+                    || script_pairs.contains(&(source.to_string(), target.to_string()))
+                    || script_pairs.contains(&(target.to_string(), source.to_string()))
+                {
+                    continue;
+                }
+                let (joined, rest): (Vec<_>, Vec<_>) = groups
+                    .drain(..)
+                    .partition(|group| group.contains(&source) || group.contains(&target));
+                let mut group: BTreeSet<char> = joined.into_iter().flatten().collect();
+                group.extend([source, target]);
+                groups = rest;
+                groups.push(group);
+            }
+        }
+
+        let mut spoofing_mappings = BTreeMap::new();
+        for group in groups {
+            let Some(&standard) = group
+                .iter()
+                .max_by_key(|&&ch| (self.get_iicore_count(ch), std::cmp::Reverse(ch)))
+            else {
+                continue;
+            };
+            for ch in group.into_iter().filter(|&ch| ch != standard) {
+                spoofing_mappings.insert(ch.to_string(), standard.to_string());
+            }
+        }
+
+        let path = paths::SPOOFING_VARIANTS;
+        fs::write(path, serde_json::to_string_pretty(&spoofing_mappings)?)?;
+        log::info!(
+            "saved {} spoofing variant mappings to {}",
+            spoofing_mappings.len(),
+            path
+        );
+
+        Ok(spoofing_mappings)
+    }
+
     /// Determine which character is the standard form
     fn determine_standard_form(&self, char1: char, char2: char) -> Option<(char, char)> {
         let code1 = char1 as u32;
         let code2 = char2 as u32;
 
         // Primary rule: Main CJK block (U+4E00-U+9FFF) is preferred over compatibility blocks
-        let is_main_1 = code1 >= 0x4E00 && code1 <= 0x9FFF;
-        let is_main_2 = code2 >= 0x4E00 && code2 <= 0x9FFF;
+        let is_main_1 = (0x4E00..=0x9FFF).contains(&code1);
+        let is_main_2 = (0x4E00..=0x9FFF).contains(&code2);
 
         match (is_main_1, is_main_2) {
             (true, false) => Some((char2, char1)), // compatibility → main
@@ -324,7 +569,7 @@ impl UnihanDataProcessor {
         let code_point = format!("U+{:04X}", ch as u32);
 
         // Try to read from Unihan IRG Sources file
-        if let Ok(contents) = fs::read_to_string("Unihan/Unihan_IRGSources.txt") {
+        if let Ok(contents) = fs::read_to_string(paths::UNIHAN_IRG) {
             for line in contents.lines() {
                 if line.starts_with(&code_point) && line.contains("kIICore") {
                     // Extract kIICore value: "U+4E00  kIICore AGTJHKMP"
@@ -387,11 +632,11 @@ impl UnihanDataProcessor {
         }
 
         // Save compatibility variants
-        let path = "data/processed/normalization/compatibility_variants.json";
+        let path = paths::COMPAT_VARIANTS;
         let json = serde_json::to_string_pretty(&compatibility_mappings)?;
         fs::write(path, json)?;
-        println!(
-            "✅ Saved {} compatibility variant mappings to: {}",
+        log::info!(
+            "saved {} compatibility variant mappings to {}",
             compatibility_mappings.len(),
             path
         );
@@ -639,11 +884,11 @@ impl UnihanDataProcessor {
         }
 
         // Save Kangxi mappings
-        let path = "data/processed/normalization/kangxi_radicals.json";
+        let path = paths::KANGXI_RADICALS;
         let json = serde_json::to_string_pretty(&kangxi_mappings)?;
         fs::write(path, json)?;
-        println!(
-            "✅ Saved {} Kangxi radical mappings to: {}",
+        log::info!(
+            "saved {} Kangxi radical mappings to {}",
             kangxi_mappings.len(),
             path
         );
@@ -659,3 +904,76 @@ impl UnihanDataProcessor {
     // - estimate_complexity (replaced by get_iicore_count)
     // - calculate_stats (replaced by inline statistics generation)
 }
+
+/// Value of a script conversion entry: one target, or every candidate in order of preference
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Candidates {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl Candidates {
+    fn into_vec(self) -> Vec<String> {
+        match self {
+            Candidates::One(target) => vec![target],
+            Candidates::Many(targets) => targets,
+        }
+    }
+}
+
+/// Whether `ch`, which has candidates in the other script, is also one of them
+///
+/// Unihan lists a character among its own variants where it is written as
+/// is in the other script too (后 kTraditionalVariant 后 後, 乾
+/// kSimplifiedVariant 乾 干). It also does so for many simplified characters
+/// whose traditional use is marginal (这, 个); those would make every
+/// conversion of them ambiguous, so towards Traditional the character must
+/// also be among the common characters of Big5.
+fn keeps_identity(ch: char, own_variant: &HashSet<char>, common: Option<&HashSet<char>>) -> bool {
+    own_variant.contains(&ch) && common.is_none_or(|common| common.contains(&ch))
+}
+
+/// Characters of the first, common level of Big5 (kBigFive A440–C67E)
+fn common_big5_chars(path: &str) -> Result<HashSet<char>, Box<dyn std::error::Error>> {
+    let mut chars = HashSet::new();
+    let reader = BufReader::new(File::open(path)?);
+    for line in reader.lines() {
+        let line = line?;
+        let parts: Vec<&str> = line.split('\t').collect();
+        if parts.len() < 3 || parts[1] != "kBigFive" {
+            continue;
+        }
+        let Ok(code) = u32::from_str_radix(parts[2].trim(), 16) else {
+            continue;
+        };
+        if let Some(ch) = code_point_to_char(parts[0]).filter(|_| (0xA440..=0xC67E).contains(&code))
+        {
+            chars.insert(ch);
+        }
+    }
+    Ok(chars)
+}
+
+/// Append `target` to the candidates of `source` unless it is already one
+fn add_candidate(mappings: &mut BTreeMap<String, Vec<String>>, source: &str, target: &str) {
+    let candidates = mappings.entry(source.to_string()).or_default();
+    if !candidates.iter().any(|candidate| candidate == target) {
+        candidates.push(target.to_string());
+    }
+}
+
+/// Script conversion table as written to disk: a string for a single target, an array otherwise
+fn candidates_json(mappings: &BTreeMap<String, Vec<String>>) -> serde_json::Value {
+    mappings
+        .iter()
+        .map(|(source, targets)| {
+            let value = match targets.as_slice() {
+                [target] => serde_json::Value::from(target.as_str()),
+                _ => serde_json::Value::from(targets.clone()),
+            };
+            (source.clone(), value)
+        })
+        .collect::<serde_json::Map<_, _>>()
+        .into()
+}
@@ -0,0 +1,154 @@
+//! Hangul syllable composition, the initial-sound rule and Yale romanization
+
+/// First precomposed Hangul syllable (가)
+const SYLLABLE_BASE: u32 = 0xAC00;
+const VOWEL_COUNT: u32 = 21;
+const FINAL_COUNT: u32 = 28;
+const SYLLABLE_COUNT: u32 = 19 * VOWEL_COUNT * FINAL_COUNT;
+
+/// Initial consonant indices used by the rules below
+const INITIAL_N: u32 = 2; // ㄴ
+const INITIAL_R: u32 = 5; // ㄹ
+const INITIAL_NONE: u32 = 11; // ㅇ
+/// Final consonant indices used by the rules below
+const FINAL_NONE: u32 = 0;
+const FINAL_N: u32 = 4; // ㄴ
+
+/// Vowels ㅑ ㅕ ㅖ ㅛ ㅠ ㅣ, before which a word-initial ㄹ or ㄴ is dropped
+const Y_VOWELS: [u32; 6] = [2, 6, 7, 12, 17, 20];
+
+/// Yale spellings of the initial consonants, in jamo order (ㅇ is unwritten)
+const YALE_INITIALS: [&str; 19] = [
+    "k", "kk", "n", "t", "tt", "l", "m", "p", "pp", "s", "ss", "", "c", "cc", "ch", "kh", "th",
+    "ph", "h",
+];
+
+/// Yale spellings of the vowels, in jamo order
+const YALE_VOWELS: [&str; 21] = [
+    "a", "ay", "ya", "yay", "e", "ey", "ye", "yey", "o", "wa", "way", "oy", "yo", "wu", "we",
+    "wey", "wi", "yu", "u", "uy", "i",
+];
+
+/// Yale spellings of the final consonants and their jamo indices
+const YALE_FINALS: [(&str, u32); 9] = [
+    ("", 0),
+    ("k", 1),
+    ("n", 4),
+    ("t", 7),
+    ("l", 8),
+    ("m", 16),
+    ("p", 17),
+    ("s", 19),
+    ("ng", 21),
+];
+
+/// Initial, vowel and final indices of a precomposed Hangul syllable
+fn decompose(syllable: char) -> Option<(u32, u32, u32)> {
+    let index = (syllable as u32).checked_sub(SYLLABLE_BASE)?;
+    if index >= SYLLABLE_COUNT {
+        return None;
+    }
+    Some((
+        index / (VOWEL_COUNT * FINAL_COUNT),
+        index % (VOWEL_COUNT * FINAL_COUNT) / FINAL_COUNT,
+        index % FINAL_COUNT,
+    ))
+}
+
+fn compose(initial: u32, vowel: u32, final_consonant: u32) -> char {
+    char::from_u32(SYLLABLE_BASE + (initial * VOWEL_COUNT + vowel) * FINAL_COUNT + final_consonant)
+        .expect("jamo indices are in range")
+}
+
+/// Whether the character is a precomposed Hangul syllable
+pub fn is_syllable(ch: char) -> bool {
+    decompose(ch).is_some()
+}
+
+/// Reading of a Sino-Korean syllable at the start of a word (두음법칙)
+///
+/// A word-initial ㄹ becomes ㄴ (로 → 노), and ㅇ before ㅑ ㅕ ㅖ ㅛ ㅠ ㅣ
+/// (리 → 이); a word-initial ㄴ before those vowels becomes ㅇ (녀 → 여).
+pub fn word_initial(syllable: char) -> char {
+    match decompose(syllable) {
+        Some((INITIAL_R, vowel, final_consonant)) if Y_VOWELS.contains(&vowel) => {
+            compose(INITIAL_NONE, vowel, final_consonant)
+        }
+        Some((INITIAL_R, vowel, final_consonant)) => compose(INITIAL_N, vowel, final_consonant),
+        Some((INITIAL_N, vowel, final_consonant)) if Y_VOWELS.contains(&vowel) => {
+            compose(INITIAL_NONE, vowel, final_consonant)
+        }
+        _ => syllable,
+    }
+}
+
+/// Reading of a Sino-Korean syllable inside a word, after `previous`
+///
+/// 렬 and 률 become 열 and 율 after a vowel or ㄴ (비율, 선열).
+pub fn word_medial(previous: char, syllable: char) -> char {
+    let after_vowel_or_n = matches!(decompose(previous), Some((_, _, FINAL_NONE | FINAL_N)));
+    match decompose(syllable) {
+        Some((INITIAL_R, vowel @ (6 | 17), 8)) if after_vowel_or_n => {
+            compose(INITIAL_NONE, vowel, 8)
+        }
+        _ => syllable,
+    }
+}
+
+/// Convert one syllable in Yale romanization (as in Unihan kKorean, e.g. `KWUK`) to Hangul
+///
+/// After p, ph, pp and m, `u` is read as ㅜ like `wu` (Yale drops the `w`
+/// there, and no Sino-Korean syllable has ㅡ after a labial).
+pub fn from_yale(romanized: &str) -> Option<char> {
+    let romanized = romanized.trim().to_lowercase();
+    let (initial, rest) = longest_prefix(&romanized, &YALE_INITIALS)?;
+    let (vowel, rest) = longest_prefix(rest, &YALE_VOWELS)?;
+    let vowel = match (initial, vowel) {
+        (6..=8 | 17, 18) => 13,
+        _ => vowel,
+    };
+    let &(_, final_consonant) = YALE_FINALS.iter().find(|(spelling, _)| *spelling == rest)?;
+    Some(compose(initial as u32, vowel as u32, final_consonant))
+}
+
+/// Index of the longest spelling `text` starts with, and the rest of `text`
+fn longest_prefix<'a>(text: &'a str, spellings: &[&str]) -> Option<(usize, &'a str)> {
+    spellings
+        .iter()
+        .enumerate()
+        .filter(|(_, spelling)| text.starts_with(*spelling))
+        .max_by_key(|(_, spelling)| spelling.len())
+        .map(|(index, spelling)| (index, &text[spelling.len()..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_initial_sound_rule() {
+        assert_eq!(word_initial('리'), '이');
+        assert_eq!(word_initial('로'), '노');
+        assert_eq!(word_initial('녀'), '여');
+        assert_eq!(word_initial('량'), '양');
+        assert_eq!(word_initial('국'), '국');
+        assert_eq!(word_initial('a'), 'a');
+
+        assert_eq!(word_medial('비', '률'), '율');
+        assert_eq!(word_medial('선', '렬'), '열');
+        assert_eq!(word_medial('법', '률'), '률');
+        assert_eq!(word_medial('도', '리'), '리');
+    }
+
+    #[test]
+    fn test_from_yale() {
+        assert_eq!(from_yale("KWUK"), Some('국'));
+        assert_eq!(from_yale("HAN"), Some('한'));
+        assert_eq!(from_yale("LI"), Some('리'));
+        assert_eq!(from_yale("CHENG"), Some('청'));
+        assert_eq!(from_yale("MWUL"), Some('물'));
+        assert_eq!(from_yale("MUN"), Some('문'));
+        assert_eq!(from_yale("UY"), Some('의'));
+        assert_eq!(from_yale("XYZ"), None);
+    }
+}
@@ -1,7 +1,10 @@
 //! Utility functions for text normalization
 
 pub mod data_processor;
+pub mod hangul;
+pub mod opencc_engine;
 pub mod opencc_validator;
+pub mod pinyin;
 pub mod unicode_utils;
 
 pub use data_processor::*;
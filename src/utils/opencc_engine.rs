@@ -0,0 +1,443 @@
+//! Native OpenCC-compatible conversion engine
+//!
+//! Reads OpenCC's JSON configs (`s2t.json`, `t2tw.json`, ...) and plain-text
+//! dictionaries (`STPhrases.txt`, `TSCharacters.txt`, `TWVariants.txt`, ...)
+//! and converts the way OpenCC does: the text is split into segments by
+//! maximum matching against the segmentation dictionary, then every
+//! conversion in the chain rewrites each segment by maximum matching against
+//! its dictionary. No C++ library is needed.
+//!
+//! Configs that name compiled dictionaries (`.ocd2`, `.ocd`) are served from
+//! the `.txt` file with the same name, as found in OpenCC's source tree.
+//! Without a directory, an engine serves only the converters it was built
+//! with, such as `s2t` and `t2s` over the shipped tables.
+
+use crate::error::{Error, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// OpenCC plain-text dictionary: one `key<TAB>value [value ...]` entry per line
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TextDictionary {
+    entries: HashMap<String, Vec<String>>,
+    /// Length in chars of the longest key
+    longest_key: usize,
+}
+
+impl TextDictionary {
+    /// Parse the contents of a dictionary file; blank lines and `#` comments are skipped
+    pub fn parse(contents: &str) -> Self {
+        let mut dictionary = Self::default();
+        for line in contents.lines() {
+            let line = line.trim_end_matches('\r');
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((key, values)) = line.split_once('\t') else {
+                continue;
+            };
+            let values: Vec<String> = values.split(' ').map(str::to_string).collect();
+            dictionary.insert(key, values);
+        }
+        dictionary
+    }
+
+    /// Read a dictionary file
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        std::fs::read_to_string(path)
+            .map(|contents| Self::parse(&contents))
+            .map_err(|error| {
+                Error::OpenCCUnavailable(format!("cannot read {}: {}", path.display(), error))
+            })
+    }
+
+    /// Add an entry; the first value is the one conversion uses
+    pub fn insert(&mut self, key: &str, values: Vec<String>) {
+        if key.is_empty() || values.is_empty() {
+            return;
+        }
+        self.longest_key = self.longest_key.max(key.chars().count());
+        self.entries.insert(key.to_string(), values);
+    }
+
+    /// Values of an exact key
+    pub fn get(&self, key: &str) -> Option<&[String]> {
+        self.entries.get(key).map(Vec::as_slice)
+    }
+
+    /// Number of entries
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the dictionary has no entries
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Longest key that `text` starts with, as its length in bytes and its first value
+    fn match_prefix(&self, text: &str) -> Option<(usize, &str)> {
+        let ends: Vec<usize> = text
+            .char_indices()
+            .map(|(index, ch)| index + ch.len_utf8())
+            .take(self.longest_key)
+            .collect();
+        ends.into_iter().rev().find_map(|end| {
+            self.entries
+                .get(&text[..end])
+                .map(|values| (end, values[0].as_str()))
+        })
+    }
+}
+
+/// Dictionary of a config: a single file or a group searched in order
+#[derive(Debug, Clone)]
+enum Dictionary {
+    Text(Arc<TextDictionary>),
+    /// The first member with any match wins, as in OpenCC's `DictGroup`
+    Group(Vec<Dictionary>),
+}
+
+impl Dictionary {
+    fn match_prefix(&self, text: &str) -> Option<(usize, &str)> {
+        match self {
+            Dictionary::Text(dictionary) => dictionary.match_prefix(text),
+            Dictionary::Group(members) => {
+                members.iter().find_map(|member| member.match_prefix(text))
+            }
+        }
+    }
+}
+
+/// Converter built from one OpenCC config
+#[derive(Debug, Clone)]
+pub struct NativeConverter {
+    segmentation: Dictionary,
+    chain: Vec<Dictionary>,
+}
+
+impl NativeConverter {
+    /// Converter laid out like OpenCC's `s2t` and `t2s` configs: text is
+    /// segmented by `phrases`, then each segment is converted by `phrases`
+    /// and, where no phrase matches, by `characters`
+    pub fn from_dictionaries(phrases: TextDictionary, characters: TextDictionary) -> Self {
+        let phrases = Dictionary::Text(Arc::new(phrases));
+        Self {
+            segmentation: phrases.clone(),
+            chain: vec![Dictionary::Group(vec![
+                phrases,
+                Dictionary::Text(Arc::new(characters)),
+            ])],
+        }
+    }
+
+    /// Convert text the way OpenCC does with this config
+    pub fn convert(&self, text: &str) -> String {
+        let mut segments = self.segment(text);
+        for dictionary in &self.chain {
+            for segment in segments.iter_mut() {
+                *segment = convert_segment(dictionary, segment);
+            }
+        }
+        segments.concat()
+    }
+
+    /// Split text by maximum matching; unmatched characters are grouped into one segment
+    fn segment(&self, text: &str) -> Vec<String> {
+        let mut segments = Vec::new();
+        let mut unmatched = String::new();
+        let mut rest = text;
+        while let Some(ch) = rest.chars().next() {
+            match self.segmentation.match_prefix(rest) {
+                Some((len, _)) => {
+                    if !unmatched.is_empty() {
+                        segments.push(std::mem::take(&mut unmatched));
+                    }
+                    segments.push(rest[..len].to_string());
+                    rest = &rest[len..];
+                }
+                None => {
+                    unmatched.push(ch);
+                    rest = &rest[ch.len_utf8()..];
+                }
+            }
+        }
+        if !unmatched.is_empty() {
+            segments.push(unmatched);
+        }
+        segments
+    }
+}
+
+/// Rewrite a segment by maximum matching, copying unmatched characters
+fn convert_segment(dictionary: &Dictionary, segment: &str) -> String {
+    let mut converted = String::with_capacity(segment.len());
+    let mut rest = segment;
+    while let Some(ch) = rest.chars().next() {
+        match dictionary.match_prefix(rest) {
+            Some((len, value)) => {
+                converted.push_str(value);
+                rest = &rest[len..];
+            }
+            None => {
+                converted.push(ch);
+                rest = &rest[ch.len_utf8()..];
+            }
+        }
+    }
+    converted
+}
+
+/// On-disk layout of an OpenCC config
+#[derive(Deserialize)]
+struct ConfigFile {
+    segmentation: SegmentationConfig,
+    conversion_chain: Vec<ConversionConfig>,
+}
+
+#[derive(Deserialize)]
+struct SegmentationConfig {
+    #[serde(rename = "type")]
+    kind: String,
+    dict: DictionaryConfig,
+}
+
+#[derive(Deserialize)]
+struct ConversionConfig {
+    dict: DictionaryConfig,
+}
+
+#[derive(Deserialize)]
+struct DictionaryConfig {
+    #[serde(rename = "type")]
+    kind: String,
+    file: Option<String>,
+    #[serde(default)]
+    dicts: Vec<DictionaryConfig>,
+}
+
+/// Loads OpenCC configs and dictionaries from a directory and caches the converters
+///
+/// Configs are looked up as `<dir>/<name>.json` or `<dir>/config/<name>.json`
+/// and dictionaries next to the config, in `<dir>` or in `<dir>/dictionary`,
+/// so both a flat directory and OpenCC's `data/` source directory work.
+#[derive(Debug)]
+pub struct OpenCCEngine {
+    dir: Option<PathBuf>,
+    dictionaries: Mutex<HashMap<PathBuf, Arc<TextDictionary>>>,
+    converters: Mutex<HashMap<String, Arc<NativeConverter>>>,
+}
+
+impl OpenCCEngine {
+    /// Create an engine reading configs and dictionaries from `dir`
+    pub fn new<P: Into<PathBuf>>(dir: P) -> Self {
+        Self {
+            dir: Some(dir.into()),
+            dictionaries: Mutex::new(HashMap::new()),
+            converters: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Create an engine serving only the given converters, keyed by config name
+    pub fn with_converters<I>(converters: I) -> Self
+    where
+        I: IntoIterator<Item = (String, NativeConverter)>,
+    {
+        Self {
+            dir: None,
+            dictionaries: Mutex::new(HashMap::new()),
+            converters: Mutex::new(
+                converters
+                    .into_iter()
+                    .map(|(config, converter)| (config, Arc::new(converter)))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Directory the configs and dictionaries are read from, if any
+    pub fn dir(&self) -> Option<&Path> {
+        self.dir.as_deref()
+    }
+
+    /// Converter of a config such as `s2t` or `t2tw.json`, loaded on first use
+    pub fn converter(&self, config: &str) -> Result<Arc<NativeConverter>> {
+        if let Some(converter) = lock(&self.converters).get(config) {
+            return Ok(converter.clone());
+        }
+        let converter = Arc::new(self.load_converter(config)?);
+        lock(&self.converters).insert(config.to_string(), converter.clone());
+        Ok(converter)
+    }
+
+    /// Convert text with the named config
+    pub fn convert(&self, config: &str, text: &str) -> Result<String> {
+        Ok(self.converter(config)?.convert(text))
+    }
+
+    fn load_converter(&self, config: &str) -> Result<NativeConverter> {
+        let Some(dir) = &self.dir else {
+            return Err(Error::OpenCCUnavailable(format!(
+                "config {} is not built in",
+                config
+            )));
+        };
+        let file_name = if config.ends_with(".json") {
+            config.to_string()
+        } else {
+            format!("{}.json", config)
+        };
+        let path = [dir.join(&file_name), dir.join("config").join(&file_name)]
+            .into_iter()
+            .find(|path| path.is_file())
+            .ok_or_else(|| {
+                Error::OpenCCUnavailable(format!(
+                    "config {} not found in {}",
+                    file_name,
+                    dir.display()
+                ))
+            })?;
+        let contents = std::fs::read(&path).map_err(|error| {
+            Error::OpenCCUnavailable(format!("cannot read {}: {}", path.display(), error))
+        })?;
+        let file: ConfigFile = serde_json::from_slice(&contents).map_err(|error| {
+            Error::OpenCCUnavailable(format!("invalid config {}: {}", path.display(), error))
+        })?;
+
+        if file.segmentation.kind != "mmseg" {
+            return Err(Error::OpenCCUnavailable(format!(
+                "unsupported segmentation {:?} in {}",
+                file.segmentation.kind,
+                path.display()
+            )));
+        }
+        let config_dir = path.parent().unwrap_or(dir);
+        let segmentation = self.load_dictionary(&file.segmentation.dict, dir, config_dir)?;
+        let chain = file
+            .conversion_chain
+            .iter()
+            .map(|conversion| self.load_dictionary(&conversion.dict, dir, config_dir))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(NativeConverter {
+            segmentation,
+            chain,
+        })
+    }
+
+    fn load_dictionary(
+        &self,
+        config: &DictionaryConfig,
+        dir: &Path,
+        config_dir: &Path,
+    ) -> Result<Dictionary> {
+        match config.kind.as_str() {
+            "group" => config
+                .dicts
+                .iter()
+                .map(|member| self.load_dictionary(member, dir, config_dir))
+                .collect::<Result<Vec<_>>>()
+                .map(Dictionary::Group),
+            "text" | "ocd2" | "ocd" => {
+                let file = config.file.as_deref().ok_or_else(|| {
+                    Error::OpenCCUnavailable(format!("{} dictionary without a file", config.kind))
+                })?;
+                let text_file = Path::new(file).with_extension("txt");
+                let path = [
+                    config_dir.join(&text_file),
+                    dir.join(&text_file),
+                    dir.join("dictionary").join(&text_file),
+                ]
+                .into_iter()
+                .find(|path| path.is_file())
+                .ok_or_else(|| {
+                    Error::OpenCCUnavailable(format!(
+                        "dictionary {} not found in {}",
+                        text_file.display(),
+                        dir.display()
+                    ))
+                })?;
+
+                if let Some(dictionary) = lock(&self.dictionaries).get(&path) {
+                    return Ok(Dictionary::Text(dictionary.clone()));
+                }
+                let dictionary = Arc::new(TextDictionary::load(&path)?);
+                lock(&self.dictionaries).insert(path, dictionary.clone());
+                Ok(Dictionary::Text(dictionary))
+            }
+            kind => Err(Error::OpenCCUnavailable(format!(
+                "unsupported dictionary type {:?}",
+                kind
+            ))),
+        }
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Directory with an `s2t` config and its dictionaries
+    fn opencc_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("zho-opencc-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(dir.join("dictionary")).unwrap();
+        std::fs::write(
+            dir.join("s2t.json"),
+            r#"{
+                "name": "Simplified to Traditional",
+                "segmentation": {"type": "mmseg", "dict": {"type": "ocd2", "file": "STPhrases.ocd2"}},
+                "conversion_chain": [{"dict": {"type": "group", "dicts": [
+                    {"type": "ocd2", "file": "STPhrases.ocd2"},
+                    {"type": "text", "file": "STCharacters.txt"}
+                ]}}]
+            }"#,
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("dictionary/STPhrases.txt"),
+            "头发\t頭髮\n发展\t發展\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("dictionary/STCharacters.txt"),
+            "发\t發 髮\n头\t頭\n汉\t漢\n",
+        )
+        .unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_text_dictionary() {
+        let dictionary = TextDictionary::parse("干\t幹 乾 干\n# comment\n\n干燥\t乾燥\n");
+
+        assert_eq!(dictionary.len(), 2);
+        assert_eq!(dictionary.get("干").unwrap()[1], "乾");
+        assert_eq!(
+            dictionary.match_prefix("干燥剂"),
+            Some(("干燥".len(), "乾燥"))
+        );
+    }
+
+    #[test]
+    fn test_max_match_conversion() {
+        let dir = opencc_dir("convert");
+        let engine = OpenCCEngine::new(&dir);
+
+        assert_eq!(engine.convert("s2t", "头发和发展").unwrap(), "頭髮和發展");
+        assert_eq!(engine.convert("s2t", "汉").unwrap(), "漢");
+        assert!(matches!(
+            engine.convert("t2s", "漢"),
+            Err(Error::OpenCCUnavailable(_))
+        ));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
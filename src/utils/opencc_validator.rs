@@ -1,41 +1,258 @@
 //! OpenCC validator for Traditional ↔ Simplified conversion
+//!
+//! Conversion goes either through the libopencc binding (the `opencc` cargo
+//! feature) or through the native engine in [`opencc_engine`](super::opencc_engine),
+//! which reads OpenCC's configs and plain-text dictionaries from a directory or
+//! serves `s2t` and `t2s` from the shipped script tables.
 
+use super::opencc_engine::{NativeConverter, OpenCCEngine, TextDictionary};
+use crate::data::ScriptTable;
+use crate::error::{Error, Result};
+use crate::types::ScriptMapping;
+#[cfg(feature = "opencc")]
 use opencc::OpenCC;
+#[cfg(feature = "opencc")]
+use std::collections::HashMap;
+use std::path::PathBuf;
+#[cfg(feature = "opencc")]
+use std::sync::Mutex;
 
 /// OpenCC validator for script conversion
+///
+/// Every converter is safe to share between threads.
 pub struct OpenCCValidator {
-    trad_to_simp: OpenCC,
-    simp_to_trad: OpenCC,
+    backend: Backend,
 }
 
+enum Backend {
+    /// libopencc; each converter sits behind a mutex
+    #[cfg(feature = "opencc")]
+    Library {
+        trad_to_simp: Mutex<Converter>,
+        simp_to_trad: Mutex<Converter>,
+        /// Converters for other configs (e.g. `s2tw`), opened on first use
+        others: Mutex<HashMap<&'static str, Converter>>,
+    },
+    /// Pure-Rust engine over OpenCC's plain-text dictionaries
+    Native(OpenCCEngine),
+}
+
+/// OpenCC handle that may be moved to another thread
+#[cfg(feature = "opencc")]
+struct Converter(OpenCC);
+
+// SAFETY: `OpenCC` is only `!Send` because it holds the raw pointer to its
+// libopencc handle. The handle is owned exclusively by this wrapper, libopencc
+// converters have no thread affinity, and every access goes through a `Mutex`.
+#[cfg(feature = "opencc")]
+unsafe impl Send for Converter {}
+
 impl OpenCCValidator {
-    /// Create a new OpenCC validator
-    pub fn new() -> Result<Self, Box<dyn std::error::Error>> {
+    /// Create a new OpenCC validator backed by the system libopencc
+    ///
+    /// Fails with [`Error::OpenCCUnavailable`] if the converters do not convert
+    /// a known character pair (e.g. the OpenCC configuration is missing), or
+    /// if the crate was built without the `opencc` feature.
+    #[cfg(feature = "opencc")]
+    pub fn new() -> Result<Self> {
         // Traditional to Simplified
         let trad_to_simp = OpenCC::new("t2s");
 
         // Simplified to Traditional
         let simp_to_trad = OpenCC::new("s2t");
 
-        Ok(Self {
-            trad_to_simp,
-            simp_to_trad,
-        })
+        Self {
+            backend: Backend::Library {
+                trad_to_simp: Mutex::new(Converter(trad_to_simp)),
+                simp_to_trad: Mutex::new(Converter(simp_to_trad)),
+                others: Mutex::new(HashMap::new()),
+            },
+        }
+        .checked()
+    }
+
+    /// Create a new OpenCC validator backed by the system libopencc
+    ///
+    /// Fails with [`Error::OpenCCUnavailable`] if the converters do not convert
+    /// a known character pair (e.g. the OpenCC configuration is missing), or
+    /// if the crate was built without the `opencc` feature.
+    #[cfg(not(feature = "opencc"))]
+    pub fn new() -> Result<Self> {
+        Err(Error::OpenCCUnavailable(
+            "built without the `opencc` feature; set an OpenCC dictionary directory to use the native engine"
+                .to_string(),
+        ))
+    }
+
+    /// Create a validator with the native engine, reading OpenCC's configs and
+    /// plain-text dictionaries from `dir`
+    ///
+    /// Fails with [`Error::OpenCCUnavailable`] if `t2s`/`s2t` cannot be loaded
+    /// or do not convert a known character pair.
+    pub fn native<P: Into<PathBuf>>(dir: P) -> Result<Self> {
+        Self {
+            backend: Backend::Native(OpenCCEngine::new(dir)),
+        }
+        .checked()
+    }
+
+    /// Create a validator with the native engine over the script tables,
+    /// which need no dictionary files
+    ///
+    /// Phrase entries stand in for OpenCC's `TSPhrases` / `STPhrases` and
+    /// single characters, with their top-ranked candidate first, for
+    /// `TSCharacters` / `STCharacters`. Only `t2s` and `s2t` are available.
+    pub fn built_in(
+        traditional_to_simplified: &ScriptTable,
+        simplified_to_traditional: &ScriptTable,
+    ) -> Result<Self> {
+        let engine = OpenCCEngine::with_converters([
+            (
+                "t2s".to_string(),
+                table_converter(traditional_to_simplified, |mapping| &mapping.simplified),
+            ),
+            (
+                "s2t".to_string(),
+                table_converter(simplified_to_traditional, |mapping| &mapping.traditional),
+            ),
+        ]);
+        Self {
+            backend: Backend::Native(engine),
+        }
+        .checked()
+    }
+
+    /// Check that `t2s` and `s2t` convert 漢 ↔ 汉
+    fn checked(self) -> Result<Self> {
+        if self.traditional_to_simplified("漢")? != "汉"
+            || self.simplified_to_traditional("汉")? != "漢"
+        {
+            return Err(Error::OpenCCUnavailable(
+                "t2s/s2t converters failed the 漢 ↔ 汉 check".to_string(),
+            ));
+        }
+        Ok(self)
     }
 
     /// Convert Traditional to Simplified using OpenCC
-    pub fn traditional_to_simplified(
-        &self,
-        text: &str,
-    ) -> Result<String, Box<dyn std::error::Error>> {
-        Ok(self.trad_to_simp.convert(text))
+    pub fn traditional_to_simplified(&self, text: &str) -> Result<String> {
+        self.convert_with("t2s", text)
     }
 
     /// Convert Simplified to Traditional using OpenCC
-    pub fn simplified_to_traditional(
-        &self,
-        text: &str,
-    ) -> Result<String, Box<dyn std::error::Error>> {
-        Ok(self.simp_to_trad.convert(text))
+    pub fn simplified_to_traditional(&self, text: &str) -> Result<String> {
+        self.convert_with("s2t", text)
+    }
+
+    /// Convert using the named OpenCC config (e.g. `s2tw`, `t2hk`, `t2jp`)
+    pub fn convert_with(&self, config: &'static str, text: &str) -> Result<String> {
+        match &self.backend {
+            #[cfg(feature = "opencc")]
+            Backend::Library {
+                trad_to_simp,
+                simp_to_trad,
+                others,
+            } => match config {
+                "t2s" => Ok(Self::lock(trad_to_simp).0.convert(text)),
+                "s2t" => Ok(Self::lock(simp_to_trad).0.convert(text)),
+                _ => {
+                    let mut others = others
+                        .lock()
+                        .unwrap_or_else(|poisoned| poisoned.into_inner());
+                    let converter = others
+                        .entry(config)
+                        .or_insert_with(|| Converter(OpenCC::new(config)));
+                    Ok(converter.0.convert(text))
+                }
+            },
+            Backend::Native(engine) => engine.convert(config, text),
+        }
+    }
+
+    /// Lock a converter; a panic in another thread leaves the handle usable
+    #[cfg(feature = "opencc")]
+    fn lock(converter: &Mutex<Converter>) -> std::sync::MutexGuard<'_, Converter> {
+        converter
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// OpenCC-style converter over a script table, taking each candidate's target with `target`
+fn table_converter(table: &ScriptTable, target: fn(&ScriptMapping) -> &String) -> NativeConverter {
+    let mut phrases = TextDictionary::default();
+    let mut characters = TextDictionary::default();
+    for (source, candidates) in table {
+        let targets = candidates.iter().map(|mapping| target(mapping).clone());
+        let dictionary = if source.chars().nth(1).is_some() {
+            &mut phrases
+        } else {
+            &mut characters
+        };
+        dictionary.insert(source, targets.collect());
+    }
+    NativeConverter::from_dictionaries(phrases, characters)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_native_validator() {
+        let dir = std::env::temp_dir().join(format!("zho-validator-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for (config, dictionary, entries) in [
+            ("t2s", "TSCharacters", "漢\t汉\n"),
+            ("s2t", "STCharacters", "汉\t漢\n"),
+        ] {
+            std::fs::write(
+                dir.join(format!("{}.json", config)),
+                format!(
+                    r#"{{"segmentation": {{"type": "mmseg", "dict": {{"type": "text", "file": "{0}.txt"}}}},
+                        "conversion_chain": [{{"dict": {{"type": "ocd2", "file": "{0}.ocd2"}}}}]}}"#,
+                    dictionary
+                ),
+            )
+            .unwrap();
+            std::fs::write(dir.join(format!("{}.txt", dictionary)), entries).unwrap();
+        }
+
+        let validator = OpenCCValidator::native(&dir).unwrap();
+        assert_eq!(validator.traditional_to_simplified("漢字").unwrap(), "汉字");
+        assert!(matches!(
+            validator.convert_with("s2tw", "汉"),
+            Err(Error::OpenCCUnavailable(_))
+        ));
+        assert!(matches!(
+            OpenCCValidator::native(dir.join("missing")),
+            Err(Error::OpenCCUnavailable(_))
+        ));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_built_in_validator() {
+        let (t2s, s2t) = crate::data::DataStore::default().script_tables();
+        let validator = OpenCCValidator::built_in(&t2s, &s2t).unwrap();
+
+        assert_eq!(
+            validator.simplified_to_traditional("头发和发展").unwrap(),
+            "頭髮和發展"
+        );
+        assert_eq!(validator.simplified_to_traditional("干净").unwrap(), "乾淨");
+        assert_eq!(
+            validator.traditional_to_simplified("著名的乾隆").unwrap(),
+            "著名的乾隆"
+        );
+        assert!(matches!(
+            validator.convert_with("s2tw", "汉"),
+            Err(Error::OpenCCUnavailable(_))
+        ));
+
+        // The minimal fallback tables fail the check
+        let (t2s, s2t) = crate::normalizers::script_converter::ScriptConverter::fallback_mappings();
+        assert!(OpenCCValidator::built_in(&t2s, &s2t).is_err());
     }
 }
@@ -0,0 +1,234 @@
+//! Hanyu Pinyin → Zhuyin (Bopomofo) conversion for single syllables
+
+/// Initials, two-letter ones first so that `zh` is not read as `z`
+const INITIALS: &[(&str, &str)] = &[
+    ("zh", "ㄓ"),
+    ("ch", "ㄔ"),
+    ("sh", "ㄕ"),
+    ("b", "ㄅ"),
+    ("p", "ㄆ"),
+    ("m", "ㄇ"),
+    ("f", "ㄈ"),
+    ("d", "ㄉ"),
+    ("t", "ㄊ"),
+    ("n", "ㄋ"),
+    ("l", "ㄌ"),
+    ("g", "ㄍ"),
+    ("k", "ㄎ"),
+    ("h", "ㄏ"),
+    ("j", "ㄐ"),
+    ("q", "ㄑ"),
+    ("x", "ㄒ"),
+    ("r", "ㄖ"),
+    ("z", "ㄗ"),
+    ("c", "ㄘ"),
+    ("s", "ㄙ"),
+];
+
+/// Finals in their full (unabbreviated) spelling
+const FINALS: &[(&str, &str)] = &[
+    ("a", "ㄚ"),
+    ("o", "ㄛ"),
+    ("e", "ㄜ"),
+    ("ê", "ㄝ"),
+    ("ai", "ㄞ"),
+    ("ei", "ㄟ"),
+    ("ao", "ㄠ"),
+    ("ou", "ㄡ"),
+    ("an", "ㄢ"),
+    ("en", "ㄣ"),
+    ("ang", "ㄤ"),
+    ("eng", "ㄥ"),
+    ("ong", "ㄨㄥ"),
+    ("er", "ㄦ"),
+    ("i", "ㄧ"),
+    ("ia", "ㄧㄚ"),
+    ("io", "ㄧㄛ"),
+    ("ie", "ㄧㄝ"),
+    ("iai", "ㄧㄞ"),
+    ("iao", "ㄧㄠ"),
+    ("iou", "ㄧㄡ"),
+    ("ian", "ㄧㄢ"),
+    ("in", "ㄧㄣ"),
+    ("iang", "ㄧㄤ"),
+    ("ing", "ㄧㄥ"),
+    ("iong", "ㄩㄥ"),
+    ("u", "ㄨ"),
+    ("ua", "ㄨㄚ"),
+    ("uo", "ㄨㄛ"),
+    ("uai", "ㄨㄞ"),
+    ("uei", "ㄨㄟ"),
+    ("uan", "ㄨㄢ"),
+    ("uen", "ㄨㄣ"),
+    ("uang", "ㄨㄤ"),
+    ("ueng", "ㄨㄥ"),
+    ("ü", "ㄩ"),
+    ("üe", "ㄩㄝ"),
+    ("üan", "ㄩㄢ"),
+    ("ün", "ㄩㄣ"),
+];
+
+/// Spellings of finals written without an initial
+const STANDALONE: &[(&str, &str)] = &[
+    ("yi", "i"),
+    ("ya", "ia"),
+    ("yo", "io"),
+    ("ye", "ie"),
+    ("yai", "iai"),
+    ("yao", "iao"),
+    ("you", "iou"),
+    ("yan", "ian"),
+    ("yin", "in"),
+    ("yang", "iang"),
+    ("ying", "ing"),
+    ("yong", "iong"),
+    ("yu", "ü"),
+    ("yue", "üe"),
+    ("yuan", "üan"),
+    ("yun", "ün"),
+    ("wu", "u"),
+    ("wa", "ua"),
+    ("wo", "uo"),
+    ("wai", "uai"),
+    ("wei", "uei"),
+    ("wan", "uan"),
+    ("wen", "uen"),
+    ("wang", "uang"),
+    ("weng", "ueng"),
+];
+
+/// Convert one pinyin syllable, with tone marks (`zhōng`) or a tone number
+/// (`zhong1`), to Zhuyin (`ㄓㄨㄥ`)
+///
+/// Returns `None` for text that is not a standard Mandarin syllable
+/// (including the syllabic nasals `m`, `n` and `ng`).
+pub fn to_zhuyin(syllable: &str) -> Option<String> {
+    let (plain, tone) = split_tone(syllable)?;
+
+    let (initial, rest) = match STANDALONE.iter().find(|(spelling, _)| *spelling == plain) {
+        Some(&(_, full)) => ("", full.to_string()),
+        None => {
+            let &(spelling, initial) = INITIALS
+                .iter()
+                .find(|(spelling, _)| plain.starts_with(spelling))
+                .unwrap_or(&("", ""));
+            (initial, plain[spelling.len()..].to_string())
+        }
+    };
+
+    let palatal = matches!(initial, "ㄐ" | "ㄑ" | "ㄒ");
+    let final_part = match rest.as_str() {
+        // zhi, chi, shi, ri, zi, ci, si have no written final
+        "i" if matches!(initial, "ㄓ" | "ㄔ" | "ㄕ" | "ㄖ" | "ㄗ" | "ㄘ" | "ㄙ") => "",
+        // Abbreviated spellings
+        "iu" => "iou",
+        "ui" => "uei",
+        "un" if palatal => "ün",
+        "un" => "uen",
+        // ü is written u after j, q and x
+        "u" if palatal => "ü",
+        "ue" => "üe",
+        "uan" if palatal => "üan",
+        other => other,
+    };
+    let final_zhuyin = if final_part.is_empty() && !rest.is_empty() {
+        ""
+    } else {
+        FINALS
+            .iter()
+            .find(|(spelling, _)| *spelling == final_part)
+            .map(|&(_, zhuyin)| zhuyin)?
+    };
+
+    let mut zhuyin = String::new();
+    if tone == 5 {
+        zhuyin.push('˙');
+    }
+    zhuyin.push_str(initial);
+    zhuyin.push_str(final_zhuyin);
+    match tone {
+        2 => zhuyin.push('ˊ'),
+        3 => zhuyin.push('ˇ'),
+        4 => zhuyin.push('ˋ'),
+        _ => {}
+    }
+    Some(zhuyin)
+}
+
+/// Lowercase syllable without tone marks (`ü` kept, `v` read as `ü`) and its tone, 1 to 5
+fn split_tone(syllable: &str) -> Option<(String, u8)> {
+    let mut plain = String::with_capacity(syllable.len());
+    let mut tone = None;
+    for ch in syllable.trim().to_lowercase().chars() {
+        let (base, mark) = match ch {
+            'ā' => ('a', 1),
+            'á' => ('a', 2),
+            'ǎ' => ('a', 3),
+            'à' => ('a', 4),
+            'ē' => ('e', 1),
+            'é' => ('e', 2),
+            'ě' => ('e', 3),
+            'è' => ('e', 4),
+            'ī' => ('i', 1),
+            'í' => ('i', 2),
+            'ǐ' => ('i', 3),
+            'ì' => ('i', 4),
+            'ō' => ('o', 1),
+            'ó' => ('o', 2),
+            'ǒ' => ('o', 3),
+            'ò' => ('o', 4),
+            'ū' => ('u', 1),
+            'ú' => ('u', 2),
+            'ǔ' => ('u', 3),
+            'ù' => ('u', 4),
+            'ǖ' => ('ü', 1),
+            'ǘ' => ('ü', 2),
+            'ǚ' => ('ü', 3),
+            'ǜ' => ('ü', 4),
+            'v' => ('ü', 0),
+            '1'..='5' => {
+                tone = ch.to_digit(10).map(|digit| digit as u8);
+                continue;
+            }
+            'a'..='z' | 'ü' | 'ê' => (ch, 0),
+            _ => return None,
+        };
+        if mark != 0 {
+            tone = Some(mark);
+        }
+        plain.push(base);
+    }
+    if plain.is_empty() {
+        return None;
+    }
+    // A syllable without a mark is in the neutral tone
+    Some((plain, tone.unwrap_or(5)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_zhuyin() {
+        let cases = [
+            ("zhōng", "ㄓㄨㄥ"),
+            ("fā", "ㄈㄚ"),
+            ("shì", "ㄕˋ"),
+            ("lǜ", "ㄌㄩˋ"),
+            ("xué", "ㄒㄩㄝˊ"),
+            ("yuǎn", "ㄩㄢˇ"),
+            ("guì", "ㄍㄨㄟˋ"),
+            ("qiú", "ㄑㄧㄡˊ"),
+            ("jun1", "ㄐㄩㄣ"),
+            ("wèn", "ㄨㄣˋ"),
+            ("de", "˙ㄉㄜ"),
+        ];
+        for (pinyin, zhuyin) in cases {
+            assert_eq!(to_zhuyin(pinyin).as_deref(), Some(zhuyin), "{}", pinyin);
+        }
+        assert_eq!(to_zhuyin("ng"), None);
+        assert_eq!(to_zhuyin("n"), None);
+        assert_eq!(to_zhuyin("漢"), None);
+    }
+}
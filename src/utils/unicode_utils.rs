@@ -2,8 +2,7 @@
 
 /// Convert a Unicode code point string (e.g., "U+4E00") to a char
 pub fn code_point_to_char(code_point: &str) -> Option<char> {
-    if code_point.starts_with("U+") {
-        let hex = &code_point[2..];
+    if let Some(hex) = code_point.strip_prefix("U+") {
         if let Ok(value) = u32::from_str_radix(hex, 16) {
             return char::from_u32(value);
         }